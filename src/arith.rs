@@ -0,0 +1,480 @@
+// Integer arithmetic for `let`, `(( ))` and `$(( ))`, with the operators and
+// precedence of C. Variables that aren't set or are empty count as 0, and
+// those holding an expression are evaluated in turn
+
+use std::fmt;
+
+use crate::cmd::is_name;
+use crate::shell::Shell;
+
+// How deep variables holding expressions are followed
+const MAX_DEPTH: usize = 32;
+
+#[derive(Debug, PartialEq)]
+pub enum ArithError {
+    Syntax(String),
+    DivisionByZero,
+    NegativeExponent,
+    NotAVariable,
+    TooDeep,
+    // A variable that isn't set with `set -u`
+    Unbound(String),
+}
+
+impl fmt::Display for ArithError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ArithError::Syntax(token) => write!(f, "{}", tr!("syntax error near `{}`", token)),
+            ArithError::DivisionByZero => write!(f, "{}", tr!("division by 0")),
+            ArithError::NegativeExponent => write!(f, "{}", tr!("exponent less than 0")),
+            ArithError::NotAVariable => {
+                write!(f, "{}", tr!("attempted assignment to non-variable"))
+            }
+            ArithError::TooDeep => write!(f, "{}", tr!("expression recursion level exceeded")),
+            ArithError::Unbound(name) => write!(f, "{}", tr!("{}: unbound variable", name)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Num(i64),
+    Name(String),
+    Op(&'static str),
+}
+
+// Longest first so `<<=` isn't read as `<` and `<=`
+const OPERATORS: &[&str] = &[
+    "<<=", ">>=", "**", "++", "--", "<<", ">>", "<=", ">=", "==", "!=", "&&", "||", "+=", "-=",
+    "*=", "/=", "%=", "&=", "|=", "^=", "+", "-", "*", "/", "%", "<", ">", "&", "|", "^", "!", "~",
+    "?", ":", "=", ",", "(", ")",
+];
+
+fn tokenize(expr: &str) -> Result<Vec<Token>, ArithError> {
+    let mut tokens = vec![];
+    let mut rest = expr.trim_start();
+
+    while let Some(c) = rest.chars().next() {
+        let len = if c.is_ascii_digit() {
+            let len = rest
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '#' && c != '_')
+                .unwrap_or(rest.len());
+            let number = parse_number(&rest[..len])
+                .ok_or_else(|| ArithError::Syntax(rest[..len].to_string()))?;
+            tokens.push(Token::Num(number));
+            len
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let len = rest
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                .unwrap_or(rest.len());
+            tokens.push(Token::Name(rest[..len].to_string()));
+            len
+        } else {
+            match OPERATORS.iter().find(|op| rest.starts_with(*op)) {
+                Some(op) => {
+                    tokens.push(Token::Op(op));
+                    op.len()
+                }
+                None => return Err(ArithError::Syntax(c.to_string())),
+            }
+        };
+
+        rest = rest[len..].trim_start();
+    }
+
+    Ok(tokens)
+}
+
+// Decimal, `0x` hexadecimal, `0` octal or `base#digits` for bases up to 64
+fn parse_number(s: &str) -> Option<i64> {
+    let (base, digits) = if let Some((base, digits)) = s.split_once('#') {
+        (
+            base.parse().ok().filter(|base| (2..=64).contains(base))?,
+            digits,
+        )
+    } else if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        (16, hex)
+    } else if s.len() > 1 && s.starts_with('0') {
+        (8, &s[1..])
+    } else {
+        (10, s)
+    };
+
+    if digits.is_empty() {
+        return None;
+    }
+
+    digits.chars().try_fold(0i64, |n, c| {
+        let digit = match c {
+            '0'..='9' => c as i64 - '0' as i64,
+            'a'..='z' => c as i64 - 'a' as i64 + 10,
+            'A'..='Z' if base <= 36 => c as i64 - 'A' as i64 + 10,
+            'A'..='Z' => c as i64 - 'A' as i64 + 36,
+            '@' => 62,
+            '_' => 63,
+            _ => return None,
+        };
+
+        (digit < base).then(|| n.wrapping_mul(base).wrapping_add(digit))
+    })
+}
+
+enum Node {
+    Num(i64),
+    Var(String),
+    Unary(&'static str, Box<Node>),
+    Binary(&'static str, Box<Node>, Box<Node>),
+    // `name op= value`, just `=` being a plain assignment
+    Assign(String, &'static str, Box<Node>),
+    // `++name` and `--name` give the new value, `name++` and `name--` the old
+    Step {
+        name: String,
+        delta: i64,
+        prefix: bool,
+    },
+    Cond(Box<Node>, Box<Node>, Box<Node>),
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+// Binding power of the binary operators and whether they group to the right
+fn precedence(op: &str) -> Option<(u8, bool)> {
+    Some(match op {
+        "," => (1, false),
+        "=" | "+=" | "-=" | "*=" | "/=" | "%=" | "<<=" | ">>=" | "&=" | "|=" | "^=" => (2, true),
+        "?" => (3, true),
+        "||" => (4, false),
+        "&&" => (5, false),
+        "|" => (6, false),
+        "^" => (7, false),
+        "&" => (8, false),
+        "==" | "!=" => (9, false),
+        "<" | "<=" | ">" | ">=" => (10, false),
+        "<<" | ">>" => (11, false),
+        "+" | "-" => (12, false),
+        "*" | "/" | "%" => (13, false),
+        "**" => (14, true),
+        _ => return None,
+    })
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn unexpected(&self) -> ArithError {
+        match self.peek() {
+            Some(Token::Num(n)) => ArithError::Syntax(n.to_string()),
+            Some(Token::Name(name)) => ArithError::Syntax(name.clone()),
+            Some(Token::Op(op)) => ArithError::Syntax(op.to_string()),
+            None => ArithError::Syntax(tr!("end of expression")),
+        }
+    }
+
+    fn expect(&mut self, op: &str) -> Result<(), ArithError> {
+        match self.peek() {
+            Some(Token::Op(found)) if *found == op => {
+                self.pos += 1;
+                Ok(())
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    fn expr(&mut self, min: u8) -> Result<Node, ArithError> {
+        let mut left = self.unary()?;
+
+        while let Some(Token::Op(op)) = self.peek() {
+            let op = *op;
+            let (prec, right) = match precedence(op) {
+                Some((prec, right)) if prec >= min => (prec, right),
+                _ => break,
+            };
+            self.pos += 1;
+            let next = if right { prec } else { prec + 1 };
+
+            left = match op {
+                "?" => {
+                    let then = self.expr(1)?;
+                    self.expect(":")?;
+                    let otherwise = self.expr(next)?;
+                    Node::Cond(Box::new(left), Box::new(then), Box::new(otherwise))
+                }
+
+                _ if prec == 2 => match left {
+                    Node::Var(name) => Node::Assign(name, op, Box::new(self.expr(next)?)),
+                    _ => return Err(ArithError::NotAVariable),
+                },
+
+                _ => Node::Binary(op, Box::new(left), Box::new(self.expr(next)?)),
+            };
+        }
+
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Node, ArithError> {
+        match self.next() {
+            Some(Token::Op(op @ ("++" | "--"))) => match self.next() {
+                Some(Token::Name(name)) => Ok(Node::Step {
+                    name,
+                    delta: if op == "++" { 1 } else { -1 },
+                    prefix: true,
+                }),
+                _ => Err(ArithError::NotAVariable),
+            },
+
+            Some(Token::Op(op @ ("-" | "+" | "!" | "~"))) => {
+                Ok(Node::Unary(op, Box::new(self.unary()?)))
+            }
+
+            Some(Token::Op("(")) => {
+                let inner = self.expr(1)?;
+                self.expect(")")?;
+                Ok(inner)
+            }
+
+            Some(Token::Num(n)) => Ok(Node::Num(n)),
+
+            Some(Token::Name(name)) => match self.peek() {
+                Some(Token::Op(op @ ("++" | "--"))) => {
+                    let delta = if *op == "++" { 1 } else { -1 };
+                    self.pos += 1;
+                    Ok(Node::Step {
+                        name,
+                        delta,
+                        prefix: false,
+                    })
+                }
+                _ => Ok(Node::Var(name)),
+            },
+
+            _ => {
+                self.pos -= 1;
+                Err(self.unexpected())
+            }
+        }
+    }
+}
+
+fn parse(expr: &str) -> Result<Node, ArithError> {
+    let mut parser = Parser {
+        tokens: tokenize(expr)?,
+        pos: 0,
+    };
+
+    let node = parser.expr(1)?;
+    match parser.peek() {
+        None => Ok(node),
+        Some(_) => Err(parser.unexpected()),
+    }
+}
+
+pub fn eval(expr: &str, shell: &mut Shell) -> Result<i64, ArithError> {
+    eval_nested(expr, shell, 0)
+}
+
+fn eval_nested(expr: &str, shell: &mut Shell, depth: usize) -> Result<i64, ArithError> {
+    if expr.trim().is_empty() {
+        return Ok(0);
+    }
+
+    let node = parse(expr)?;
+    Evaluator { shell, depth }.eval(&node)
+}
+
+struct Evaluator<'a> {
+    shell: &'a mut Shell,
+    depth: usize,
+}
+
+impl Evaluator<'_> {
+    fn var(&mut self, name: &str) -> Result<i64, ArithError> {
+        let value = match self.shell.lookup(name) {
+            Some(value) => value,
+            None if self.shell.options.nounset => return Err(ArithError::Unbound(name.into())),
+            None => String::new(),
+        };
+        let value = value.trim();
+
+        if let Some(n) = parse_number(value) {
+            return Ok(n);
+        }
+
+        if self.depth >= MAX_DEPTH {
+            return Err(ArithError::TooDeep);
+        }
+        eval_nested(value, self.shell, self.depth + 1)
+    }
+
+    fn set(&mut self, name: &str, value: i64) {
+        if is_name(name) {
+            self.shell.set_var(name, value.to_string());
+        }
+    }
+
+    fn eval(&mut self, node: &Node) -> Result<i64, ArithError> {
+        Ok(match node {
+            Node::Num(n) => *n,
+            Node::Var(name) => self.var(name)?,
+
+            Node::Unary(op, operand) => {
+                let n = self.eval(operand)?;
+                match *op {
+                    "-" => n.wrapping_neg(),
+                    "!" => (n == 0) as i64,
+                    "~" => !n,
+                    _ => n,
+                }
+            }
+
+            Node::Binary("&&", left, right) => {
+                (self.eval(left)? != 0 && self.eval(right)? != 0) as i64
+            }
+            Node::Binary("||", left, right) => {
+                (self.eval(left)? != 0 || self.eval(right)? != 0) as i64
+            }
+            Node::Binary(",", left, right) => {
+                self.eval(left)?;
+                self.eval(right)?
+            }
+            Node::Binary(op, left, right) => {
+                let left = self.eval(left)?;
+                let right = self.eval(right)?;
+                apply(op, left, right)?
+            }
+
+            Node::Assign(name, op, value) => {
+                let value = self.eval(value)?;
+                let value = match op.strip_suffix('=').filter(|op| !op.is_empty()) {
+                    Some(op) => apply(op, self.var(name)?, value)?,
+                    None => value,
+                };
+                self.set(name, value);
+                value
+            }
+
+            Node::Step {
+                name,
+                delta,
+                prefix,
+            } => {
+                let old = self.var(name)?;
+                let new = old.wrapping_add(*delta);
+                self.set(name, new);
+                if *prefix {
+                    new
+                } else {
+                    old
+                }
+            }
+
+            Node::Cond(cond, then, otherwise) => match self.eval(cond)? {
+                0 => self.eval(otherwise)?,
+                _ => self.eval(then)?,
+            },
+        })
+    }
+}
+
+fn apply(op: &str, left: i64, right: i64) -> Result<i64, ArithError> {
+    Ok(match op {
+        "+" => left.wrapping_add(right),
+        "-" => left.wrapping_sub(right),
+        "*" => left.wrapping_mul(right),
+        "/" | "%" if right == 0 => return Err(ArithError::DivisionByZero),
+        "/" => left.wrapping_div(right),
+        "%" => left.wrapping_rem(right),
+        "**" if right < 0 => return Err(ArithError::NegativeExponent),
+        "**" => left.wrapping_pow(right.min(u32::MAX as i64) as u32),
+        "<<" => left.wrapping_shl(right as u32),
+        ">>" => left.wrapping_shr(right as u32),
+        "&" => left & right,
+        "|" => left | right,
+        "^" => left ^ right,
+        "==" => (left == right) as i64,
+        "!=" => (left != right) as i64,
+        "<" => (left < right) as i64,
+        "<=" => (left <= right) as i64,
+        ">" => (left > right) as i64,
+        ">=" => (left >= right) as i64,
+        _ => unreachable!(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_precedence() {
+        let mut shell = Shell::new();
+        let mut eval = |expr| eval(expr, &mut shell);
+
+        assert_eq!(eval("1 + 2 * 3"), Ok(7));
+        assert_eq!(eval("(1 + 2) * 3"), Ok(9));
+        assert_eq!(eval("2 ** 3 ** 2"), Ok(512));
+        assert_eq!(eval("-2 ** 2"), Ok(4));
+        assert_eq!(eval("7 % 3 == 1 && !0"), Ok(1));
+        assert_eq!(eval("1 < 2 ? 10 : 20"), Ok(10));
+        assert_eq!(eval("0x1f + 010 + 2#101"), Ok(44));
+        assert_eq!(eval("1 << 4 | 1"), Ok(17));
+        assert_eq!(eval(""), Ok(0));
+    }
+
+    #[test]
+    fn test_variables() {
+        let mut shell = Shell::new();
+
+        assert_eq!(eval("x = 5, x += 2, x", &mut shell), Ok(7));
+        assert_eq!(eval("x++ + ++x", &mut shell), Ok(16));
+        assert_eq!(shell.vars["x"], "9");
+        assert_eq!(eval("unset + 1", &mut shell), Ok(1));
+
+        shell.vars.insert("e".into(), "x * 2".into());
+        assert_eq!(eval("e + 1", &mut shell), Ok(19));
+
+        // The branch not taken has no effects
+        assert_eq!(eval("0 && (y = 1)", &mut shell), Ok(0));
+        assert!(!shell.vars.contains_key("y"));
+
+        shell.vars.insert("loop".into(), "loop".into());
+        assert_eq!(eval("loop", &mut shell), Err(ArithError::TooDeep));
+
+        shell.options.nounset = true;
+        assert_eq!(
+            eval("unset + 1", &mut shell),
+            Err(ArithError::Unbound("unset".into()))
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut shell = Shell::new();
+        let mut eval = |expr| eval(expr, &mut shell);
+
+        assert_eq!(eval("1 / 0"), Err(ArithError::DivisionByZero));
+        assert_eq!(eval("2 ** -1"), Err(ArithError::NegativeExponent));
+        assert_eq!(eval("1 = 2"), Err(ArithError::NotAVariable));
+        assert_eq!(
+            eval("1 +"),
+            Err(ArithError::Syntax(tr!("end of expression")))
+        );
+        assert_eq!(
+            eval("(1"),
+            Err(ArithError::Syntax(tr!("end of expression")))
+        );
+        assert_eq!(eval("1 2"), Err(ArithError::Syntax(String::from("2"))));
+        assert_eq!(eval("09"), Err(ArithError::Syntax(String::from("09"))));
+    }
+}
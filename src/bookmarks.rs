@@ -0,0 +1,88 @@
+// Command lines saved under a name with `save-cmd` and run again with `run`,
+// kept one per line as `name\tcommand` with newlines and backslashes in the
+// command escaped
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// `RUSH_BOOKMARKS`, or `~/.rush_bookmarks` by default
+pub fn default_path() -> Option<PathBuf> {
+    match env::var_os("RUSH_BOOKMARKS") {
+        Some(path) => Some(PathBuf::from(path)),
+        None => env::var_os("HOME").map(|home| PathBuf::from(home).join(".rush_bookmarks")),
+    }
+}
+
+pub fn load(path: &Path) -> BTreeMap<String, String> {
+    let contents = fs::read_to_string(path).unwrap_or_default();
+
+    contents
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .map(|(name, command)| (name.to_string(), unescape(command)))
+        .collect()
+}
+
+pub fn store(path: &Path, bookmarks: &BTreeMap<String, String>) -> io::Result<()> {
+    let mut contents = String::new();
+
+    for (name, command) in bookmarks {
+        contents.push_str(name);
+        contents.push('\t');
+        contents.push_str(&escape(command));
+        contents.push('\n');
+    }
+
+    fs::write(path, contents)
+}
+
+fn escape(command: &str) -> String {
+    command.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn unescape(command: &str) -> String {
+    let mut out = String::new();
+    let mut chars = command.chars();
+
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n')) => {
+                out.push('\n');
+                chars.next();
+            }
+            ('\\', Some('\\')) => {
+                out.push('\\');
+                chars.next();
+            }
+            (c, _) => out.push(c),
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_bookmarks_round_trip() {
+        let path = env::temp_dir().join(format!("rush-bookmarks-{}", std::process::id()));
+        let mut bookmarks = BTreeMap::new();
+        bookmarks.insert(
+            "deploy".to_string(),
+            "make && \\\n  ./ship 'a\\nb'".to_string(),
+        );
+        bookmarks.insert("t".to_string(), "cargo test".to_string());
+
+        store(&path, &bookmarks).unwrap();
+        assert_eq!(load(&path), bookmarks);
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 2);
+
+        fs::remove_file(&path).unwrap();
+        assert!(load(&path).is_empty());
+    }
+}
@@ -0,0 +1,154 @@
+// The builtins, each in a module of its own and looked up by name when a
+// command runs, so adding one doesn't touch the parser. A function of the same
+// name takes precedence, `builtin name` gets past it
+
+mod alias;
+mod arith;
+mod bind;
+mod bookmarks;
+mod command;
+mod complete;
+mod declare;
+mod dirs;
+mod env;
+mod exit;
+mod flow;
+mod getconf;
+mod hash;
+mod history;
+mod jobs;
+mod kill;
+mod params;
+mod random;
+mod restart;
+mod set;
+mod shtest;
+mod sleep;
+mod source;
+mod status;
+mod strftime;
+mod trap;
+mod ulimit;
+mod umask;
+mod vars;
+
+pub use self::dirs::change_dir;
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::cmd::{report, Error};
+use crate::shell::Shell;
+use crate::status::Status;
+use crate::sys;
+
+pub trait BuiltinCommand {
+    // The arguments are expanded already, the builtin's name left out
+    fn run(&self, args: Vec<String>, shell: &mut Shell) -> Result<Status, Error>;
+}
+
+pub struct Builtins {
+    // Shared so a builtin can run while the shell it gets is borrowed mutably,
+    // even if it ends up running itself again
+    commands: HashMap<&'static str, Rc<dyn BuiltinCommand>>,
+}
+
+impl Builtins {
+    pub fn register(&mut self, name: &'static str, command: impl BuiltinCommand + 'static) {
+        self.commands.insert(name, Rc::new(command));
+    }
+
+    pub fn get(&self, name: &str) -> Option<Rc<dyn BuiltinCommand>> {
+        self.commands.get(name).cloned()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.commands.contains_key(name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.commands.keys().copied()
+    }
+}
+
+// Run a builtin. What fails it without being reported already, like its
+// output not being written, is reported with its name
+pub fn run(
+    builtin: Rc<dyn BuiltinCommand>,
+    name: &str,
+    args: Vec<String>,
+    shell: &mut Shell,
+) -> Status {
+    match builtin.run(args, shell) {
+        Ok(status) => status,
+        Err(Error::Io(e)) => {
+            report(format!("{}: {}", name, sys::describe(&e)), shell);
+            Status::Exited(1)
+        }
+        Err(e) => {
+            report(format!("{}: {}", name, e), shell);
+            Status::Exited(1)
+        }
+    }
+}
+
+impl Default for Builtins {
+    fn default() -> Self {
+        let mut builtins = Builtins {
+            commands: HashMap::new(),
+        };
+
+        builtins.register(".", source::Source);
+        builtins.register(":", status::True);
+        builtins.register("alias", alias::Alias);
+        builtins.register("async-source", source::AsyncSource);
+        builtins.register("bg", jobs::Bg);
+        builtins.register("bind", bind::Bind);
+        builtins.register("break", flow::Break);
+        builtins.register("builtin", command::Builtin);
+        builtins.register("cd", dirs::Cd);
+        builtins.register("command", command::Command);
+        builtins.register("complete", complete::Complete);
+        builtins.register("continue", flow::Continue);
+        builtins.register("declare", declare::Declare);
+        builtins.register("dirs", dirs::Dirs);
+        builtins.register("disown", jobs::Disown);
+        builtins.register("env", env::Env);
+        builtins.register("exit", exit::Exit);
+        builtins.register("export", vars::Export);
+        builtins.register("false", status::False);
+        builtins.register("fg", jobs::Fg);
+        builtins.register("getconf", getconf::Getconf);
+        builtins.register("getopts", params::Getopts);
+        builtins.register("hash", hash::Hash);
+        builtins.register("history", history::History);
+        builtins.register("jobs", jobs::Jobs);
+        builtins.register("kill", kill::Kill);
+        builtins.register("let", arith::Let);
+        builtins.register("local", vars::Local);
+        builtins.register("popd", dirs::Popd);
+        builtins.register("printenv", env::Printenv);
+        builtins.register("pushd", dirs::Pushd);
+        builtins.register("random", random::Random);
+        builtins.register("restart", restart::Restart);
+        builtins.register("return", flow::Return);
+        builtins.register("run", bookmarks::Run);
+        builtins.register("save-cmd", bookmarks::SaveCmd);
+        builtins.register("set", set::Set);
+        builtins.register("shift", params::Shift);
+        builtins.register("shopt", set::Shopt);
+        builtins.register("shtest", shtest::Shtest);
+        builtins.register("sleep", sleep::Sleep);
+        builtins.register("source", source::Source);
+        builtins.register("strftime", strftime::Strftime);
+        builtins.register("trap", trap::Trap);
+        builtins.register("true", status::True);
+        builtins.register("type", command::Type);
+        builtins.register("ulimit", ulimit::Ulimit);
+        builtins.register("umask", umask::Umask);
+        builtins.register("unalias", alias::Unalias);
+        builtins.register("unset", vars::Unset);
+        builtins.register("wait", jobs::Wait);
+        builtins
+    }
+}
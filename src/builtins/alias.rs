@@ -0,0 +1,67 @@
+use std::io::{self, Write};
+
+use super::BuiltinCommand;
+use crate::cmd::{quote, report, Error};
+use crate::shell::Shell;
+use crate::status::Status;
+
+// `alias [name[=value]]...`: define aliases, or print them in a form that can
+// be read back by the shell
+pub struct Alias;
+
+impl BuiltinCommand for Alias {
+    fn run(&self, args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        let mut stdout = io::stdout();
+
+        if args.is_empty() {
+            for (name, value) in &shell.aliases {
+                writeln!(stdout, "alias {}={}", name, quote(value)).map_err(Error::Io)?;
+            }
+            return Ok(Status::Exited(0));
+        }
+
+        let mut status = true;
+
+        for arg in args {
+            match arg.find('=') {
+                Some(eq) => {
+                    shell
+                        .aliases
+                        .insert(arg[..eq].to_string(), arg[eq + 1..].to_string());
+                }
+
+                None => match shell.aliases.get(&arg) {
+                    Some(value) => {
+                        writeln!(stdout, "alias {}={}", arg, quote(value)).map_err(Error::Io)?
+                    }
+                    None => {
+                        report(tr!("alias: {}: not found", arg), shell);
+                        status = false;
+                    }
+                },
+            }
+        }
+
+        Ok(Status::from(status))
+    }
+}
+
+// `unalias [-a] name...`: remove aliases, `-a` removes all of them
+pub struct Unalias;
+
+impl BuiltinCommand for Unalias {
+    fn run(&self, args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        let mut status = true;
+
+        for arg in args {
+            if arg == "-a" {
+                shell.aliases.clear();
+            } else if shell.aliases.remove(&arg).is_none() {
+                report(tr!("unalias: {}: not found", arg), shell);
+                status = false;
+            }
+        }
+
+        Ok(Status::from(status))
+    }
+}
@@ -0,0 +1,53 @@
+use super::BuiltinCommand;
+use crate::arith;
+use crate::cmd::{report, Error};
+use crate::shell::Shell;
+use crate::status::Status;
+
+// `let expr...`: evaluate every expression, succeeding when the last one
+// isn't 0
+pub struct Let;
+
+impl BuiltinCommand for Let {
+    fn run(&self, args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        if args.is_empty() {
+            report(tr!("let: expression expected"), shell);
+            return Ok(Status::Exited(1));
+        }
+
+        let mut last = 0;
+        for arg in &args {
+            match arith::eval(arg, shell) {
+                Ok(n) => last = n,
+                Err(e) => {
+                    report(format!("let: {}: {}", arg, e), shell);
+                    return Ok(Status::Exited(1));
+                }
+            }
+        }
+
+        Ok(Status::Exited((last == 0) as i32))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_let() {
+        let mut shell = Shell::new();
+        let words = |words: &[&str]| words.iter().map(|word| word.to_string()).collect();
+
+        assert_eq!(
+            Let.run(words(&["x = 2", "x *= 3"]), &mut shell).unwrap(),
+            Status::Exited(0)
+        );
+        assert_eq!(shell.vars["x"], "6");
+        assert_eq!(
+            Let.run(words(&["x - 6"]), &mut shell).unwrap(),
+            Status::Exited(1)
+        );
+        assert_eq!(Let.run(vec![], &mut shell).unwrap(), Status::Exited(1));
+    }
+}
@@ -0,0 +1,176 @@
+use std::io::{self, Write};
+
+use super::BuiltinCommand;
+use crate::cmd::{report, Error};
+use crate::editor::{Action, Binding, Key, Keymap, ACTIONS, PRESETS};
+use crate::shell::Shell;
+use crate::status::Status;
+
+// `bind --preset [name]`: switch the line editor to one of the sets of key
+// bindings it ships with, or list them with the one in use marked.
+//
+// `bind keys action` or readline's `bind '"keys": action'` binds a key, or a
+// sequence of them, on top of the preset and `bind -r keys` unbinds it. With
+// `bind -x '"keys": command'` the keys run a command that can change the
+// line through `READLINE_LINE` and `READLINE_POINT`, as in bash. `bind -p`
+// lists the actions bound and `bind -X` the commands, in the form that binds
+// them again, and `bind -l` the actions there are. Keys are written like in
+// an inputrc, `\C-f`, `\ef` or `\C-x\C-e` for instance
+pub struct Bind;
+
+impl BuiltinCommand for Bind {
+    fn run(&self, args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let mut stdout = io::stdout();
+
+        match args[..] {
+            ["--preset"] => {
+                for (name, _) in PRESETS {
+                    let mark = if *name == shell.keymap.preset_name() {
+                        '*'
+                    } else {
+                        ' '
+                    };
+                    writeln!(stdout, "{} {}", mark, name).map_err(Error::Io)?;
+                }
+                Ok(Status::Exited(0))
+            }
+
+            ["--preset", name] => match Keymap::preset(name) {
+                Some(keymap) => {
+                    shell.keymap = keymap;
+                    Ok(Status::Exited(0))
+                }
+                None => {
+                    report(tr!("bind: {}: no such preset", name), shell);
+                    Ok(Status::Exited(1))
+                }
+            },
+
+            [] | ["-p"] | ["-X"] => {
+                let mut bindings: Vec<(String, String)> = shell
+                    .keymap
+                    .bindings()
+                    .filter_map(|(keys, binding)| match binding {
+                        Binding::Action(action) if args != ["-X"] => {
+                            Some((written(keys), action.name().to_string()))
+                        }
+                        Binding::Command(command) if args == ["-X"] => {
+                            Some((written(keys), format!("\"{}\"", command)))
+                        }
+                        _ => None,
+                    })
+                    .collect();
+                bindings.sort();
+
+                for (keys, bound) in bindings {
+                    writeln!(stdout, "\"{}\": {}", keys, bound).map_err(Error::Io)?;
+                }
+                Ok(Status::Exited(0))
+            }
+
+            ["-l"] => {
+                for action in ACTIONS {
+                    writeln!(stdout, "{}", action.name()).map_err(Error::Io)?;
+                }
+                Ok(Status::Exited(0))
+            }
+
+            ["-r", keys] => {
+                let keys = match parse_keys(keys, shell) {
+                    Some(keys) => keys,
+                    None => return Ok(Status::Exited(1)),
+                };
+                shell.keymap.unbind(&keys);
+                Ok(Status::Exited(0))
+            }
+
+            ["-x", binding] if binding.contains(':') => match split_binding(binding) {
+                Some((keys, command)) => {
+                    let command = command
+                        .strip_prefix('"')
+                        .and_then(|command| command.strip_suffix('"'))
+                        .unwrap_or(command);
+                    bind(shell, keys, Binding::Command(command.to_string()))
+                }
+                None => usage(shell),
+            },
+
+            ["-x", keys, command] => bind(shell, keys, Binding::Command(command.to_string())),
+
+            [binding] if binding.contains(':') => match split_binding(binding) {
+                Some((keys, action)) => bind_action(shell, keys, action),
+                None => usage(shell),
+            },
+
+            [keys, action] => bind_action(shell, keys, action),
+
+            _ => usage(shell),
+        }
+    }
+}
+
+fn usage(shell: &Shell) -> Result<Status, Error> {
+    report(
+        tr!("bind: usage: bind [-lpX] [-r keys] [-x keys command] [--preset [name]] [keys action]"),
+        shell,
+    );
+    Ok(Status::Exited(2))
+}
+
+// Split readline's `"keys": what`, the keys may be left unquoted
+fn split_binding(binding: &str) -> Option<(&str, &str)> {
+    let binding = binding.trim();
+
+    let (keys, rest) = match binding.strip_prefix('"') {
+        Some(quoted) => {
+            // The first quote that isn't escaped closes them
+            let mut escaped = false;
+            let (end, _) = quoted.char_indices().find(|&(_, c)| {
+                let closing = c == '"' && !escaped;
+                escaped = c == '\\' && !escaped;
+                closing
+            })?;
+            (&quoted[..end], &quoted[end + 1..])
+        }
+        None => {
+            let colon = binding.find(':')?;
+            (binding[..colon].trim_end(), &binding[colon..])
+        }
+    };
+
+    Some((keys, rest.trim_start().strip_prefix(':')?.trim()))
+}
+
+fn bind_action(shell: &mut Shell, keys: &str, action: &str) -> Result<Status, Error> {
+    match Action::from_name(action) {
+        Some(action) => bind(shell, keys, Binding::Action(action)),
+        None => {
+            report(tr!("bind: {}: unknown action", action), shell);
+            Ok(Status::Exited(1))
+        }
+    }
+}
+
+fn bind(shell: &mut Shell, keys: &str, binding: Binding) -> Result<Status, Error> {
+    match parse_keys(keys, shell) {
+        Some(keys) => {
+            shell.keymap.bind(keys, binding);
+            Ok(Status::Exited(0))
+        }
+        None => Ok(Status::Exited(1)),
+    }
+}
+
+fn parse_keys(spec: &str, shell: &Shell) -> Option<Vec<Key>> {
+    let keys = Key::parse(spec);
+    if keys.is_none() {
+        report(tr!("bind: {}: invalid key sequence", spec), shell);
+    }
+    keys
+}
+
+// In the notation they're bound with
+fn written(keys: &[Key]) -> String {
+    keys.iter().map(Key::to_string).collect()
+}
@@ -0,0 +1,105 @@
+use std::io::{self, Write};
+
+use super::BuiltinCommand;
+use crate::bookmarks;
+use crate::cmd::{self, report, Error};
+use crate::shell::Shell;
+use crate::status::Status;
+
+// `save-cmd [-d] name [command...]`: save the command given, or the previous
+// command line, under `name` for `run`. `-d` forgets it instead
+pub struct SaveCmd;
+
+impl BuiltinCommand for SaveCmd {
+    fn run(&self, args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        let (delete, args) = match args.split_first() {
+            Some((flag, rest)) if flag == "-d" => (true, rest),
+            _ => (false, &args[..]),
+        };
+
+        let name = match args.first() {
+            Some(name) if !name.is_empty() && !name.contains(char::is_whitespace) => name,
+            _ => {
+                report(
+                    tr!("save-cmd: usage: save-cmd [-d] name [command...]"),
+                    shell,
+                );
+                return Ok(Status::Exited(2));
+            }
+        };
+
+        let path = match bookmarks::default_path() {
+            Some(path) => path,
+            None => {
+                report(tr!("save-cmd: HOME isn't set"), shell);
+                return Ok(Status::Exited(1));
+            }
+        };
+        let mut saved = bookmarks::load(&path);
+
+        if delete {
+            if saved.remove(name).is_none() {
+                report(tr!("save-cmd: {}: no such command saved", name), shell);
+                return Ok(Status::Exited(1));
+            }
+        } else {
+            // The line running this is in the history already
+            let command = match args.len() {
+                1 => (0..shell.history.len())
+                    .rev()
+                    .filter_map(|i| shell.history.get(i))
+                    .find(|line| !line.starts_with("save-cmd"))
+                    .map(str::to_string),
+                _ => Some(args[1..].join(" ")),
+            };
+
+            match command {
+                Some(command) => saved.insert(name.clone(), command),
+                None => {
+                    report(tr!("save-cmd: no previous command"), shell);
+                    return Ok(Status::Exited(1));
+                }
+            };
+        }
+
+        if let Err(e) = bookmarks::store(&path, &saved) {
+            report(format!("{}: {}", path.display(), e), shell);
+            return Ok(Status::Exited(1));
+        }
+        Ok(Status::Exited(0))
+    }
+}
+
+// `run [name [arg...]]`: run the command saved under `name`, the arguments
+// being its positional parameters, or list the saved ones
+pub struct Run;
+
+impl BuiltinCommand for Run {
+    fn run(&self, args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        let saved =
+            bookmarks::default_path().map_or_else(Default::default, |path| bookmarks::load(&path));
+
+        let mut args = args.into_iter();
+        let name = match args.next() {
+            Some(name) => name,
+            None => {
+                let mut stdout = io::stdout();
+                for (name, command) in &saved {
+                    writeln!(stdout, "{}\t{}", name, command).map_err(Error::Io)?;
+                }
+                return Ok(Status::Exited(0));
+            }
+        };
+
+        match saved.get(&name) {
+            Some(command) => {
+                shell.with_positional(args.collect(), |shell| cmd::run_line(command, shell));
+                Ok(shell.status)
+            }
+            None => {
+                report(tr!("run: {}: no such command saved", name), shell);
+                Ok(Status::Exited(1))
+            }
+        }
+    }
+}
@@ -0,0 +1,260 @@
+use std::io::{self, Write};
+
+use super::BuiltinCommand;
+use crate::cmd::{quote, report, spawn_external, Error, KEYWORDS};
+use crate::path;
+use crate::shell::Shell;
+use crate::status::Status;
+
+// `command [-p] name [arg...]`: run a builtin or an external command even
+// when a function or an alias has the same name, `-p` looks it up on a
+// default PATH. `command -v|-V name...` prints how each name would be run
+// instead
+pub struct Command;
+
+impl BuiltinCommand for Command {
+    fn run(&self, mut args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        let mut default_path = false;
+
+        while let Some(option) = args.first() {
+            match option.as_str() {
+                "-p" => default_path = true,
+                "-v" | "-V" => {
+                    let verbose = option == "-V";
+                    args.remove(0);
+                    return lookup(verbose, args, shell);
+                }
+                "--" => {
+                    args.remove(0);
+                    break;
+                }
+                _ if option.starts_with('-') && option.len() > 1 => {
+                    report(
+                        tr!("command: usage: command [-p] name [arg...] or command -v|-V name..."),
+                        shell,
+                    );
+                    return Ok(Status::Exited(2));
+                }
+                _ => break,
+            }
+            args.remove(0);
+        }
+
+        if args.is_empty() {
+            return Ok(Status::Exited(0));
+        }
+
+        if let Some(builtin) = shell.builtins.get(&args[0]) {
+            let name = args.remove(0);
+            return Ok(super::run(builtin, &name, args, shell));
+        }
+        spawn_external(args, default_path, shell)
+    }
+}
+
+// `builtin name [arg...]`: run the builtin even when a function has the same
+// name
+pub struct Builtin;
+
+impl BuiltinCommand for Builtin {
+    fn run(&self, mut args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        if args.is_empty() {
+            return Ok(Status::Exited(0));
+        }
+        let name = args.remove(0);
+
+        match shell.builtins.get(&name) {
+            Some(builtin) => Ok(super::run(builtin, &name, args, shell)),
+            None => {
+                report(tr!("builtin: {}: not a shell builtin", name), shell);
+                Ok(Status::Exited(1))
+            }
+        }
+    }
+}
+
+// `type [-apt] name...`: tell what runs for every name, `-t` prints just the
+// kind of command, `-p` just the path of executables and `-a` every match
+// instead of the first one
+pub struct Type;
+
+impl BuiltinCommand for Type {
+    fn run(&self, args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        describe("type", args, shell)
+    }
+}
+
+fn lookup(verbose: bool, args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+    if verbose {
+        return describe("command", args, shell);
+    }
+
+    let mut stdout = io::stdout();
+    let mut status = true;
+
+    for name in args {
+        if let Some(value) = shell.aliases.get(&name) {
+            writeln!(stdout, "alias {}={}", name, quote(value)).map_err(Error::Io)?;
+        } else if KEYWORDS.contains(&name.as_str())
+            || shell.functions.contains_key(&name)
+            || shell.builtins.contains(&name)
+        {
+            writeln!(stdout, "{}", name).map_err(Error::Io)?;
+        } else if let Some(path) = path::find_all_executables(&name).first() {
+            writeln!(stdout, "{}", path.display()).map_err(Error::Io)?;
+        } else {
+            status = false;
+        }
+    }
+
+    Ok(Status::from(status))
+}
+
+// What `type` prints, for `command -V` too. Errors name the builtin that was
+// run
+fn describe(builtin: &str, args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+    let mut stdout = io::stdout();
+    let mut all = false;
+    let mut terse = false;
+    let mut path_only = false;
+    let mut status = true;
+
+    let mut names = args.into_iter().peekable();
+    while let Some(flags) = names.peek().and_then(|arg| arg.strip_prefix('-')) {
+        if flags.is_empty() {
+            break;
+        }
+        for flag in flags.chars() {
+            match flag {
+                'a' => all = true,
+                't' => terse = true,
+                'p' => path_only = true,
+                _ => {
+                    report(tr!("{}: -{}: invalid option", builtin, flag), shell);
+                    return Ok(Status::Exited(1));
+                }
+            }
+        }
+        names.next();
+    }
+
+    for name in names {
+        let mut found = false;
+
+        if !path_only {
+            if let Some(value) = shell.aliases.get(&name) {
+                found = true;
+                match terse {
+                    true => writeln!(stdout, "alias"),
+                    false => writeln!(stdout, "{}", tr!("{} is aliased to `{}'", name, value)),
+                }
+                .map_err(Error::Io)?;
+            }
+
+            if (all || !found) && KEYWORDS.contains(&name.as_str()) {
+                found = true;
+                match terse {
+                    true => writeln!(stdout, "keyword"),
+                    false => writeln!(stdout, "{}", tr!("{} is a shell keyword", name)),
+                }
+                .map_err(Error::Io)?;
+            }
+
+            if let Some(body) = shell.functions.get(&name).filter(|_| all || !found) {
+                found = true;
+                match terse {
+                    true => writeln!(stdout, "function"),
+                    false => writeln!(
+                        stdout,
+                        "{}\n{} () {}",
+                        tr!("{} is a function", name),
+                        name,
+                        body
+                    ),
+                }
+                .map_err(Error::Io)?;
+            }
+
+            if (all || !found) && shell.builtins.contains(&name) {
+                found = true;
+                match terse {
+                    true => writeln!(stdout, "builtin"),
+                    false => writeln!(stdout, "{}", tr!("{} is a shell builtin", name)),
+                }
+                .map_err(Error::Io)?;
+            }
+        }
+
+        if all || !found {
+            for path in path::find_all_executables(&name) {
+                found = true;
+                match (terse, path_only) {
+                    (true, false) => writeln!(stdout, "file"),
+                    (_, true) => writeln!(stdout, "{}", path.display()),
+                    _ => writeln!(stdout, "{}", tr!("{} is {}", name, path.display())),
+                }
+                .map_err(Error::Io)?;
+
+                if !all {
+                    break;
+                }
+            }
+        }
+
+        if !found {
+            if !terse && !path_only {
+                report(tr!("{}: {}: not found", builtin, name), shell);
+            }
+            status = false;
+        }
+    }
+
+    Ok(Status::from(status))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_command_skips_functions() {
+        let mut shell = Shell::new();
+        shell.functions.insert("true".into(), "false".into());
+        let args = |words: &[&str]| words.iter().map(|word| word.to_string()).collect();
+
+        assert_eq!(
+            Command.run(args(&["true"]), &mut shell).unwrap(),
+            Status::Exited(0)
+        );
+        assert_eq!(
+            Command
+                .run(args(&["-p", "--", "true"]), &mut shell)
+                .unwrap(),
+            Status::Exited(0)
+        );
+        assert_eq!(
+            Command.run(args(&["-x"]), &mut shell).unwrap(),
+            Status::Exited(2)
+        );
+    }
+
+    #[test]
+    fn test_builtin() {
+        let mut shell = Shell::new();
+        shell.functions.insert("true".into(), "false".into());
+        let args = |words: &[&str]| words.iter().map(|word| word.to_string()).collect();
+
+        assert_eq!(
+            Builtin.run(args(&["true"]), &mut shell).unwrap(),
+            Status::Exited(0)
+        );
+        assert_eq!(
+            Builtin.run(args(&["ls"]), &mut shell).unwrap(),
+            Status::Exited(1)
+        );
+        assert_eq!(
+            Builtin.run(args(&["x=1", "true"]), &mut shell).unwrap(),
+            Status::Exited(1)
+        );
+    }
+}
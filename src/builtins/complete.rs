@@ -0,0 +1,58 @@
+use std::io::{self, Write};
+
+use super::BuiltinCommand;
+use crate::cmd::{self, report, Error};
+use crate::shell::Shell;
+use crate::status::Status;
+
+// `complete -W words name...`: complete the arguments of the commands named
+// from the blank separated words. `-r` goes back to how they were completed
+// before, and without arguments or with `-p` the words given are listed
+pub struct Complete;
+
+impl BuiltinCommand for Complete {
+    fn run(&self, args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+        match args[..] {
+            [] | ["-p"] => {
+                let mut stdout = io::stdout();
+                for (name, words) in shell.completions.words() {
+                    let words = cmd::quote(&words.join(" "));
+                    writeln!(stdout, "complete -W {} {}", words, name).map_err(Error::Io)?;
+                }
+                Ok(Status::Exited(0))
+            }
+
+            ["-W", words, ref names @ ..] if !names.is_empty() => {
+                let words: Vec<String> = words.split_whitespace().map(String::from).collect();
+                for name in names {
+                    shell.completions.set_words(name, words.clone());
+                }
+                Ok(Status::Exited(0))
+            }
+
+            ["-r", ref names @ ..] if !names.is_empty() => {
+                let mut status = Status::Exited(0);
+                for name in names {
+                    if !shell.completions.remove_words(name) {
+                        report(
+                            tr!("complete: {}: no completion specification", name),
+                            shell,
+                        );
+                        status = Status::Exited(1);
+                    }
+                }
+                Ok(status)
+            }
+
+            _ => {
+                report(
+                    tr!("complete: usage: complete [-p] [-W words | -r] [name...]"),
+                    shell,
+                );
+                Ok(Status::Exited(2))
+            }
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use std::io::{self, Write};
+
+use super::BuiltinCommand;
+use crate::cmd::{self, is_name, report, Error};
+use crate::shell::Shell;
+use crate::status::Status;
+
+// `declare [-A] [name[=value]...]`: set the variables given, with `-A` as
+// associative arrays that are empty to begin with. `-A` alone lists the
+// arrays, `map[key]=value` sets their elements
+pub struct Declare;
+
+impl BuiltinCommand for Declare {
+    fn run(&self, args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        let (arrays, names) = match args.split_first() {
+            Some((flag, rest)) if flag == "-A" => (true, rest),
+            Some((flag, _)) if flag.starts_with('-') && flag != "-" => {
+                report(tr!("declare: {}: invalid option", flag), shell);
+                return Ok(Status::Exited(2));
+            }
+            _ => (false, &args[..]),
+        };
+
+        if arrays && names.is_empty() {
+            return list(shell).map(|_| Status::Exited(0));
+        }
+
+        let mut status = Status::Exited(0);
+
+        for arg in names {
+            let (name, value) = match arg.split_once('=') {
+                Some((name, value)) => (name, Some(value)),
+                None => (&arg[..], None),
+            };
+
+            if !is_name(name) {
+                report(tr!("declare: `{}': not a valid identifier", arg), shell);
+                status = Status::Exited(1);
+                continue;
+            }
+
+            match (arrays, value) {
+                (true, Some(_)) => {
+                    report(
+                        tr!("declare: {}: arrays are set one element at a time", name),
+                        shell,
+                    );
+                    status = Status::Exited(1);
+                }
+                (true, None) => {
+                    shell.vars.remove(name);
+                    shell.maps.entry(name.to_string()).or_default();
+                }
+                (false, Some(value)) => shell.set_var(name, value.to_string()),
+                (false, None) => {}
+            }
+        }
+
+        Ok(status)
+    }
+}
+
+// In a form that can be read back, the keys sorted
+fn list(shell: &Shell) -> Result<(), Error> {
+    let mut stdout = io::stdout();
+
+    for (name, map) in &shell.maps {
+        let mut elements: Vec<_> = map.iter().collect();
+        elements.sort();
+
+        let elements: Vec<String> = elements
+            .into_iter()
+            .map(|(key, value)| format!("[{}]={}", cmd::quote_if_needed(key), cmd::quote(value)))
+            .collect();
+        writeln!(stdout, "declare -A {}=({})", name, elements.join(" ")).map_err(Error::Io)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,238 @@
+use std::env;
+use std::io::{self, Write};
+use std::path::Path;
+
+use super::BuiltinCommand;
+use crate::cmd::{report, run_function, run_line, Error};
+use crate::dirs;
+use crate::shell::Shell;
+use crate::status::Status;
+use crate::sys;
+
+// `cd dir`: change to the directory, `cd +n` and `cd -n` rotate the directory
+// stack like `pushd`
+pub struct Cd;
+
+impl BuiltinCommand for Cd {
+    fn run(&self, args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        let path = match args.first() {
+            Some(path) => path,
+            None => {
+                report(tr!("cd: no directory given"), shell);
+                return Ok(Status::Exited(1));
+            }
+        };
+
+        let changed = match shell.dirs.index(path) {
+            Some(n) => rotate_dirs("cd", n, shell),
+            None => change_dir("cd", Path::new(path), shell),
+        };
+        Ok(Status::from(changed))
+    }
+}
+
+// Change directories for `builtin`, which is named when it fails. After
+// changing the `chpwd` function runs if there's one, and with `shopt -s
+// autols` so does `RUSH_AUTOLS_COMMAND`, `ls` by default
+pub fn change_dir(builtin: &str, path: &Path, shell: &mut Shell) -> bool {
+    let changed = path
+        .canonicalize()
+        .and_then(|path| env::set_current_dir(&path));
+
+    if let Err(e) = changed {
+        report(
+            format!("{}: {}: {}", builtin, path.display(), sys::describe(&e)),
+            shell,
+        );
+        return false;
+    }
+
+    // The hooks don't change the status of the `cd`
+    let status = shell.status;
+
+    run_function("chpwd", vec![], shell);
+
+    if shell.shopts.autols {
+        let command = shell
+            .lookup("RUSH_AUTOLS_COMMAND")
+            .unwrap_or_else(|| String::from("ls"));
+        run_line(&command, shell);
+    }
+
+    shell.status = status;
+    true
+}
+
+// Bring entry `n` of the directory stack to the top and change to it
+fn rotate_dirs(builtin: &str, n: usize, shell: &mut Shell) -> bool {
+    let rotated = shell.dirs.rotated(n);
+    if !change_dir(builtin, &rotated[0], shell) {
+        return false;
+    }
+    shell.dirs.replace(rotated);
+    true
+}
+
+// A `+n` or `-n` argument, or `None` for directories
+fn stack_index(builtin: &str, arg: &str, shell: &Shell) -> Result<Option<usize>, ()> {
+    let numbered = arg.len() > 1
+        && arg.starts_with(['+', '-'])
+        && arg[1..].bytes().all(|b| b.is_ascii_digit());
+
+    match shell.dirs.index(arg) {
+        Some(n) => Ok(Some(n)),
+        None if numbered => {
+            report(
+                tr!("{}: {}: directory stack index out of range", builtin, arg),
+                shell,
+            );
+            Err(())
+        }
+        None => Ok(None),
+    }
+}
+
+// `dirs [-clpv] [+n|-n]`: print the directory stack, the entry given or, with
+// `-c`, clear it. `-v` numbers the entries, `-p` prints one per line and `-l`
+// doesn't show the home directory as `~`
+pub struct Dirs;
+
+impl BuiltinCommand for Dirs {
+    fn run(&self, args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        let mut stdout = io::stdout();
+        let (mut clear, mut long, mut lines, mut numbered) = (false, false, false, false);
+        let mut entry = None;
+
+        for arg in &args {
+            if let Some(flags) = arg
+                .strip_prefix('-')
+                .filter(|flags| !flags.is_empty() && !flags.bytes().all(|b| b.is_ascii_digit()))
+            {
+                for flag in flags.chars() {
+                    match flag {
+                        'c' => clear = true,
+                        'l' => long = true,
+                        'p' => lines = true,
+                        'v' => numbered = true,
+                        _ => {
+                            report(tr!("dirs: -{}: invalid option", flag), shell);
+                            return Ok(Status::Exited(1));
+                        }
+                    }
+                }
+            } else {
+                match stack_index("dirs", arg, shell) {
+                    Ok(Some(n)) => entry = Some(n),
+                    Ok(None) => {
+                        report(tr!("dirs: {}: invalid argument", arg), shell);
+                        return Ok(Status::Exited(1));
+                    }
+                    Err(()) => return Ok(Status::Exited(1)),
+                }
+            }
+        }
+
+        if clear {
+            shell.dirs.clear();
+            return Ok(Status::Exited(0));
+        }
+
+        let show = |dir: &Path| match long {
+            true => dir.display().to_string(),
+            false => dirs::tilde(dir),
+        };
+        let full = shell.dirs.full();
+
+        if let Some(n) = entry {
+            writeln!(stdout, "{}", show(&full[n])).map_err(Error::Io)?;
+        } else if numbered {
+            for (i, dir) in full.iter().enumerate() {
+                writeln!(stdout, "{:2}  {}", i, show(dir)).map_err(Error::Io)?;
+            }
+        } else if lines {
+            for dir in &full {
+                writeln!(stdout, "{}", show(dir)).map_err(Error::Io)?;
+            }
+        } else {
+            let dirs: Vec<String> = full.iter().map(|dir| show(dir)).collect();
+            writeln!(stdout, "{}", dirs.join(" ")).map_err(Error::Io)?;
+        }
+
+        Ok(Status::Exited(0))
+    }
+}
+
+// `pushd [dir|+n|-n]`: change to the directory keeping the current one on the
+// stack, rotate entry n to the top or without arguments swap the top two
+pub struct Pushd;
+
+impl BuiltinCommand for Pushd {
+    fn run(&self, args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        match args.first() {
+            None if shell.dirs.is_empty() => {
+                report(tr!("pushd: no other directory"), shell);
+                return Ok(Status::Exited(1));
+            }
+
+            None => {
+                let mut full = shell.dirs.full();
+                full.swap(0, 1);
+                if !change_dir("pushd", &full[0], shell) {
+                    return Ok(Status::Exited(1));
+                }
+                shell.dirs.replace(full);
+            }
+
+            Some(arg) => match stack_index("pushd", arg, shell) {
+                Ok(Some(n)) if !rotate_dirs("pushd", n, shell) => return Ok(Status::Exited(1)),
+                Ok(Some(_)) => {}
+                Ok(None) => {
+                    let cwd = env::current_dir().map_err(Error::Io)?;
+                    if !change_dir("pushd", Path::new(arg), shell) {
+                        return Ok(Status::Exited(1));
+                    }
+                    shell.dirs.push(cwd);
+                }
+                Err(()) => return Ok(Status::Exited(1)),
+            },
+        }
+
+        Dirs.run(vec![], shell)
+    }
+}
+
+// `popd [+n|-n]`: remove the top of the stack and change to the next entry,
+// or remove entry n
+pub struct Popd;
+
+impl BuiltinCommand for Popd {
+    fn run(&self, args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        if shell.dirs.is_empty() {
+            report(tr!("popd: directory stack empty"), shell);
+            return Ok(Status::Exited(1));
+        }
+
+        let n = match args.first() {
+            None => 0,
+            Some(arg) => match stack_index("popd", arg, shell) {
+                Ok(Some(n)) => n,
+                Ok(None) => {
+                    report(tr!("popd: {}: invalid argument", arg), shell);
+                    return Ok(Status::Exited(1));
+                }
+                Err(()) => return Ok(Status::Exited(1)),
+            },
+        };
+
+        if n == 0 {
+            if !change_dir("popd", &shell.dirs.full()[1], shell) {
+                return Ok(Status::Exited(1));
+            }
+            shell.dirs.remove(1);
+        } else {
+            shell.dirs.remove(n);
+        }
+
+        Dirs.run(vec![], shell)
+    }
+}
@@ -0,0 +1,103 @@
+use std::env;
+use std::ffi::OsString;
+use std::io::{self, Write};
+use std::os::unix::ffi::OsStrExt;
+use std::process::Command;
+
+use super::BuiltinCommand;
+use crate::cmd::{spawn_and_wait, Error};
+use crate::shell::Shell;
+use crate::status::Status;
+
+// `env [-i] [NAME=value]... [binary [args]...]`: list the environment children
+// would inherit, or run a binary with a scrubbed or augmented environment
+pub struct Env;
+
+impl BuiltinCommand for Env {
+    fn run(&self, args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        let mut args = args.into_iter();
+        let mut clear = false;
+        let mut vars = vec![];
+        let mut binary = None;
+
+        for arg in &mut args {
+            if arg == "-i" || arg == "--ignore-environment" {
+                clear = true;
+            } else if let Some(eq) = arg.find('=') {
+                vars.push((OsString::from(&arg[..eq]), OsString::from(&arg[eq + 1..])));
+            } else {
+                binary = Some(arg);
+                break;
+            }
+        }
+
+        match binary {
+            Some(binary) => {
+                let mut command = Command::new(&binary);
+                command.args(args);
+                if clear {
+                    command.env_clear();
+                }
+                command.envs(vars);
+                spawn_and_wait(command, &binary, shell)
+            }
+
+            None => {
+                let mut listing: Vec<(OsString, OsString)> = if clear {
+                    vec![]
+                } else {
+                    env::vars_os().collect()
+                };
+
+                for (name, value) in vars {
+                    match listing.iter_mut().find(|(n, _)| *n == name) {
+                        Some(entry) => entry.1 = value,
+                        None => listing.push((name, value)),
+                    }
+                }
+
+                print_vars(&listing).map(|_| Status::Exited(0))
+            }
+        }
+    }
+}
+
+// `printenv [NAME]...`: without names behaves as `env`, otherwise prints the
+// value of every name given and fails if any of them is unset
+pub struct Printenv;
+
+impl BuiltinCommand for Printenv {
+    fn run(&self, names: Vec<String>, _: &mut Shell) -> Result<Status, Error> {
+        if names.is_empty() {
+            return print_vars(&env::vars_os().collect::<Vec<_>>()).map(|_| Status::Exited(0));
+        }
+
+        let mut stdout = io::stdout();
+        let mut found_all = true;
+
+        for name in names {
+            match env::var_os(&name) {
+                Some(value) => {
+                    stdout.write_all(value.as_bytes()).map_err(Error::Io)?;
+                    stdout.write_all(b"\n").map_err(Error::Io)?;
+                }
+                None => found_all = false,
+            }
+        }
+
+        Ok(Status::from(found_all))
+    }
+}
+
+fn print_vars(vars: &[(OsString, OsString)]) -> Result<(), Error> {
+    let mut stdout = io::stdout();
+
+    for (name, value) in vars {
+        stdout.write_all(name.as_bytes()).map_err(Error::Io)?;
+        stdout.write_all(b"=").map_err(Error::Io)?;
+        stdout.write_all(value.as_bytes()).map_err(Error::Io)?;
+        stdout.write_all(b"\n").map_err(Error::Io)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,34 @@
+use super::BuiltinCommand;
+use crate::cmd::{confirm_exit, exit, report, Error};
+use crate::shell::Shell;
+use crate::status::Status;
+
+// `exit [-f] [n]`: exit with status n, the status of the last command by
+// default. With jobs left it warns instead the first time, unless `-f` is
+// given
+pub struct Exit;
+
+impl BuiltinCommand for Exit {
+    fn run(&self, args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        let (force, args) = match args.split_first() {
+            Some((flag, rest)) if flag == "-f" => (true, rest),
+            _ => (false, &args[..]),
+        };
+
+        let status = match args.first() {
+            None => shell.status.code(),
+            Some(arg) => match arg.parse::<i32>() {
+                Ok(n) => n & 0xff,
+                Err(_) => {
+                    report(tr!("exit: {}: numeric argument required", arg), shell);
+                    return Ok(Status::Exited(2));
+                }
+            },
+        };
+
+        if !force && !confirm_exit(shell) {
+            return Ok(Status::Exited(1));
+        }
+        exit(status, shell)
+    }
+}
@@ -0,0 +1,85 @@
+use super::BuiltinCommand;
+use crate::cmd::{report, Error};
+use crate::shell::{Jump, Shell};
+use crate::status::Status;
+
+// `break [n]`: leave the n innermost loops, one by default. More levels than
+// loops running leave them all
+pub struct Break;
+
+// `continue [n]`: like `break`, the last of the loops left going on with its
+// next iteration
+pub struct Continue;
+
+impl BuiltinCommand for Break {
+    fn run(&self, args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        Ok(jump("break", args, shell))
+    }
+}
+
+impl BuiltinCommand for Continue {
+    fn run(&self, args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        Ok(jump("continue", args, shell))
+    }
+}
+
+// `return [n]`: leave the function or sourced file running with status n, the
+// status of the last command by default
+pub struct Return;
+
+impl BuiltinCommand for Return {
+    fn run(&self, args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        if shell.scopes.is_empty() && shell.sourcing == 0 {
+            report(
+                tr!("return: can only `return' from a function or sourced script"),
+                shell,
+            );
+            return Ok(Status::Exited(1));
+        }
+
+        let status = match args.first() {
+            None => shell.status,
+            Some(arg) => match arg.parse::<i32>() {
+                Ok(n) => Status::Exited(n & 0xff),
+                Err(_) => {
+                    report(tr!("return: {}: numeric argument required", arg), shell);
+                    return Ok(Status::Exited(2));
+                }
+            },
+        };
+
+        shell.returning = true;
+        Ok(status)
+    }
+}
+
+// The jump is taken by the loops once the builtin returns
+fn jump(name: &str, args: Vec<String>, shell: &mut Shell) -> Status {
+    let n = match args.first().map(|arg| arg.parse::<i64>()) {
+        None => 1,
+        Some(Ok(n)) if n > 0 => n as usize,
+        Some(Ok(n)) => {
+            report(tr!("{}: {}: loop count out of range", name, n), shell);
+            return Status::Exited(1);
+        }
+        Some(Err(_)) => {
+            report(
+                tr!("{}: {}: numeric argument required", name, args[0]),
+                shell,
+            );
+            return Status::Exited(1);
+        }
+    };
+
+    if shell.loops == 0 {
+        report(tr!("{}: only meaningful in a loop", name), shell);
+        return Status::Exited(1);
+    }
+
+    let n = n.min(shell.loops);
+    shell.jump = Some(match name {
+        "break" => Jump::Break(n),
+        _ => Jump::Continue(n),
+    });
+    Status::Exited(0)
+}
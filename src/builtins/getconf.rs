@@ -0,0 +1,87 @@
+use std::env;
+use std::io::{self, Write};
+
+use super::BuiltinCommand;
+use crate::cmd::{is_name, report, Error};
+use crate::shell::Shell;
+use crate::status::Status;
+use crate::sys;
+
+// `getconf [-v var] [name...]`: print facts about the platform, all of them
+// without names, or assign one to `var`
+pub struct Getconf;
+
+impl BuiltinCommand for Getconf {
+    fn run(&self, args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        getconf(args, shell).map(Status::from)
+    }
+}
+
+fn getconf(args: Vec<String>, shell: &mut Shell) -> Result<bool, Error> {
+    let mut stdout = io::stdout();
+    let mut args = args.into_iter().peekable();
+
+    let var = match args.next_if(|arg| arg == "-v") {
+        Some(_) => match args.next() {
+            Some(var) if is_name(&var) => Some(var),
+            _ => {
+                report(tr!("getconf: -v: variable name expected"), shell);
+                return Ok(false);
+            }
+        },
+        None => None,
+    };
+
+    if args.peek().is_none() {
+        if var.is_some() {
+            report(tr!("getconf: -v needs a name to look up"), shell);
+            return Ok(false);
+        }
+
+        for names in CONF_NAMES {
+            let value = conf_value(names[0]).unwrap_or_default();
+            writeln!(stdout, "{:<12}{}", names[0], value).map_err(Error::Io)?;
+        }
+        return Ok(true);
+    }
+
+    let mut status = true;
+
+    for name in args {
+        match (conf_value(&name), &var) {
+            (Some(value), Some(var)) => shell.set_var(var, value),
+            (Some(value), None) => writeln!(stdout, "{}", value).map_err(Error::Io)?,
+            (None, _) => {
+                report(tr!("getconf: {}: unknown name", name), shell);
+                status = false;
+            }
+        }
+    }
+
+    Ok(status)
+}
+
+// The names `getconf` knows, the first of each is the one listed
+const CONF_NAMES: &[&[&str]] = &[
+    &["OS"],
+    &["ARCH"],
+    &["NPROC", "_NPROCESSORS_ONLN"],
+    &["PAGESIZE", "PAGE_SIZE"],
+    &["PATH_MAX"],
+];
+
+fn conf_value(name: &str) -> Option<String> {
+    match name {
+        "OS" => Some(env::consts::OS.to_string()),
+        "ARCH" => Some(env::consts::ARCH.to_string()),
+        "NPROC" | "_NPROCESSORS_ONLN" => std::thread::available_parallelism()
+            .ok()
+            .map(|n| n.to_string()),
+        "PAGESIZE" | "PAGE_SIZE" => Some(unsafe { sys::sysconf(sys::_SC_PAGESIZE) }.to_string()),
+        "PATH_MAX" => {
+            let max = unsafe { sys::pathconf(b"/\0".as_ptr().cast(), sys::_PC_PATH_MAX) };
+            Some(max.to_string())
+        }
+        _ => None,
+    }
+}
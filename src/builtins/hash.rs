@@ -0,0 +1,46 @@
+use std::io::{self, Write};
+
+use super::BuiltinCommand;
+use crate::cmd::{report, Error};
+use crate::shell::Shell;
+use crate::status::Status;
+
+// `hash [-r] [name...]`: list where the commands run were found, forget them
+// with `-r` or look the names given up again
+pub struct Hash;
+
+impl BuiltinCommand for Hash {
+    fn run(&self, args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        let mut stdout = io::stdout();
+        let mut args = args.into_iter().peekable();
+
+        if args.next_if(|arg| arg == "-r").is_some() {
+            shell.hash.clear();
+        } else if args.peek().is_none() {
+            if shell.hash.entries().next().is_none() {
+                writeln!(stdout, "{}", tr!("hash: hash table empty")).map_err(Error::Io)?;
+            } else {
+                writeln!(stdout, "{}\t{}", tr!("hits"), tr!("command")).map_err(Error::Io)?;
+            }
+
+            for (_, hashed) in shell.hash.entries() {
+                writeln!(stdout, "{:4}\t{}", hashed.hits, hashed.path.display())
+                    .map_err(Error::Io)?;
+            }
+            return Ok(Status::Exited(0));
+        }
+
+        let mut status = true;
+
+        for name in args {
+            let builtin = shell.builtins.contains(&name) || shell.functions.contains_key(&name);
+
+            if !name.contains('/') && !builtin && shell.hash.lookup(&name).is_none() {
+                report(tr!("hash: {}: not found", name), shell);
+                status = false;
+            }
+        }
+
+        Ok(Status::from(status))
+    }
+}
@@ -0,0 +1,77 @@
+use std::fs;
+use std::io::{self, Write};
+
+use super::BuiltinCommand;
+use crate::cmd::{report, Error};
+use crate::shell::Shell;
+use crate::status::Status;
+
+// `history [-c] [--failed] [--in-dir dir] [--host] [--session] [n]`: list the
+// numbered history, or clear it. The listing can be narrowed down to the
+// commands that failed, ran in `dir`, on this host or in this shell, and to
+// the last `n` of those
+pub struct History;
+
+impl BuiltinCommand for History {
+    fn run(&self, args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        let mut count = None;
+        let mut failed = false;
+        let mut dir = None;
+        let mut host = None;
+        let mut session = None;
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-c" => {
+                    shell.history.clear();
+                    return Ok(Status::Exited(0));
+                }
+
+                "--failed" => failed = true,
+                "--host" => host = Some(shell.history.host()),
+                "--session" => session = Some(shell.history.session()),
+
+                "--in-dir" => match args.next() {
+                    Some(path) => {
+                        let path =
+                            fs::canonicalize(&path).map_or(path, |path| path.display().to_string());
+                        dir = Some(path);
+                    }
+                    None => {
+                        report(tr!("history: --in-dir: directory required"), shell);
+                        return Ok(Status::Exited(1));
+                    }
+                },
+
+                _ => match arg.parse::<usize>() {
+                    Ok(n) => count = Some(n),
+                    Err(_) => {
+                        report(tr!("history: {}: numeric argument required", arg), shell);
+                        return Ok(Status::Exited(1));
+                    }
+                },
+            }
+        }
+
+        let entries: Vec<_> = shell
+            .history
+            .entries()
+            .filter(|(_, entry)| !failed || entry.status != 0)
+            .filter(|(_, entry)| dir.as_ref().is_none_or(|dir| entry.cwd == *dir))
+            .filter(|(_, entry)| host.is_none_or(|host| entry.host == host))
+            .filter(|(_, entry)| session.is_none_or(|session| entry.session == session))
+            .collect();
+
+        let mut stdout = io::stdout();
+        let skip = count.map_or(0, |count| entries.len().saturating_sub(count));
+
+        // Commands that failed are marked
+        for (n, entry) in &entries[skip..] {
+            let marker = if entry.status != 0 { '✗' } else { ' ' };
+            writeln!(stdout, "{:5} {} {}", n, marker, entry.command).map_err(Error::Io)?;
+        }
+
+        Ok(Status::Exited(0))
+    }
+}
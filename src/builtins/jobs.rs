@@ -0,0 +1,281 @@
+use std::io::{self, Write};
+
+use super::BuiltinCommand;
+use crate::cmd::{report, Error};
+use crate::job;
+use crate::shell::Shell;
+use crate::status::Status;
+use crate::sys;
+
+// `jobs [-p]`: list the jobs, or just their process group ids. `jobs --logs
+// [pid]` lists the logs of the jobs started with `&!`, or prints one
+pub struct Jobs;
+
+impl BuiltinCommand for Jobs {
+    fn run(&self, args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        let mut stdout = io::stdout();
+
+        let pids_only = match args.first().map(String::as_str) {
+            Some("--logs") => return show_job_logs(args.get(1), shell),
+            Some("-p") => true,
+            Some(arg) => {
+                report(tr!("jobs: {}: invalid option", arg), shell);
+                return Ok(Status::Exited(1));
+            }
+            None => false,
+        };
+
+        shell.jobs.reap();
+
+        for job in shell.jobs.iter() {
+            match pids_only {
+                true => writeln!(stdout, "{}", job.pgid),
+                false => writeln!(stdout, "{}", shell.jobs.format(job)),
+            }
+            .map_err(Error::Io)?;
+        }
+
+        shell.jobs.forget_done();
+        Ok(Status::Exited(0))
+    }
+}
+
+fn show_job_logs(pid: Option<&String>, shell: &Shell) -> Result<Status, Error> {
+    let mut stdout = io::stdout();
+    let logs = job::log_dir().map_or(vec![], |dir| job::logs(&dir));
+
+    let pid = match pid {
+        Some(pid) => pid,
+        None => {
+            for log in &logs {
+                let started = sys::format_time("%F %T", log.started as i64);
+                writeln!(stdout, "{}\t{}\t{}", log.pid, started, log.cmdline).map_err(Error::Io)?;
+            }
+            return Ok(Status::Exited(0));
+        }
+    };
+
+    // Pids get reused, the newest log is the one meant
+    match logs.iter().rev().find(|log| log.pid.to_string() == *pid) {
+        Some(log) => {
+            let output = job::output(log).map_err(Error::Io)?;
+            stdout.write_all(output.as_bytes()).map_err(Error::Io)?;
+            Ok(Status::Exited(0))
+        }
+        None => {
+            report(tr!("jobs: {}: no such log", pid), shell);
+            Ok(Status::Exited(1))
+        }
+    }
+}
+
+// `fg [%job]`: continue a job in the foreground and wait for it
+pub struct Fg;
+
+impl BuiltinCommand for Fg {
+    fn run(&self, args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        if !shell.job_control {
+            report(tr!("fg: no job control"), shell);
+            return Ok(Status::Exited(1));
+        }
+
+        let job = match find_job("fg", args.first().map(String::as_str), shell)
+            .and_then(|id| shell.jobs.remove(id))
+        {
+            Some(job) => job,
+            None => return Ok(Status::Exited(1)),
+        };
+
+        println!("{}", job.cmdline);
+        shell.jobs.foreground(job).map_err(Error::Io)
+    }
+}
+
+// `bg [%job...]`: continue stopped jobs in the background
+pub struct Bg;
+
+impl BuiltinCommand for Bg {
+    fn run(&self, args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        if !shell.job_control {
+            report(tr!("bg: no job control"), shell);
+            return Ok(Status::Exited(1));
+        }
+
+        let specs = match args.is_empty() {
+            true => vec![None],
+            false => args.iter().map(|arg| Some(arg.as_str())).collect(),
+        };
+
+        let mut status = true;
+
+        for spec in specs {
+            match find_job("bg", spec, shell) {
+                Some(id) if shell.jobs.background(id) => {
+                    if let Some(job) = shell.jobs.get(id) {
+                        println!("[{}]{} {} &", id, shell.jobs.marker(id), job.cmdline);
+                    }
+                }
+
+                Some(id) => report(tr!("bg: job {} already in background", id), shell),
+
+                None => status = false,
+            }
+        }
+
+        Ok(Status::from(status))
+    }
+}
+
+// `wait [%job | pid]...`: wait for the given jobs returning the status of the
+// last, or for every running job
+pub struct Wait;
+
+impl BuiltinCommand for Wait {
+    fn run(&self, args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        shell.jobs.reap();
+
+        let ids: Vec<Option<usize>> = match args.is_empty() {
+            true => shell
+                .jobs
+                .iter()
+                .filter(|job| !job.stopped)
+                .map(|job| Some(job.id))
+                .collect(),
+            false => args.iter().map(|arg| wait_target(arg, shell)).collect(),
+        };
+
+        let mut status = Status::Exited(0);
+
+        for id in ids {
+            status = match id.and_then(|id| shell.jobs.remove(id)) {
+                Some(job) => {
+                    let untraced = shell.job_control;
+                    shell.jobs.wait_job(job, untraced).map_err(Error::Io)?
+                }
+                None => Status::Exited(127),
+            };
+        }
+
+        // Everything waited for is done, with no need to report it
+        if args.is_empty() {
+            shell.jobs.forget_done();
+            return Ok(Status::Exited(0));
+        }
+
+        Ok(status)
+    }
+}
+
+fn wait_target(arg: &str, shell: &mut Shell) -> Option<usize> {
+    if arg.starts_with('%') {
+        return find_job("wait", Some(arg), shell);
+    }
+
+    let pid = match arg.parse::<i32>() {
+        Ok(pid) => pid,
+        Err(_) => {
+            report(tr!("wait: {}: not a pid or valid job spec", arg), shell);
+            return None;
+        }
+    };
+
+    let job = shell
+        .jobs
+        .iter()
+        .find(|job| job.processes.iter().any(|process| process.pid == pid));
+
+    if job.is_none() {
+        report(tr!("wait: pid {} is not a child of this shell", pid), shell);
+    }
+
+    job.map(|job| job.id)
+}
+
+// `disown [-ahr] [%job...]`: forget jobs so they aren't reported or hung up
+// on exit, with `-h` they're only left alone on exit. `-a` applies to every
+// job and `-r` to every running one
+pub struct Disown;
+
+impl BuiltinCommand for Disown {
+    fn run(&self, args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        let mut keep = false;
+        let mut all = false;
+        let mut running = false;
+
+        let mut specs = args.iter().map(String::as_str).peekable();
+        while let Some(flags) = specs.peek().and_then(|arg| arg.strip_prefix('-')) {
+            for flag in flags.chars() {
+                match flag {
+                    'h' => keep = true,
+                    'a' => all = true,
+                    'r' => running = true,
+                    _ => {
+                        report(tr!("disown: -{}: invalid option", flag), shell);
+                        return Ok(Status::Exited(1));
+                    }
+                }
+            }
+            specs.next();
+        }
+
+        shell.jobs.reap();
+
+        let mut status = true;
+
+        let ids: Vec<usize> = if all || running {
+            shell
+                .jobs
+                .iter()
+                .filter(|job| all || matches!(job.state(), job::State::Running))
+                .map(|job| job.id)
+                .collect()
+        } else {
+            let specs: Vec<Option<&str>> = match specs.peek() {
+                Some(_) => specs.map(Some).collect(),
+                None => vec![None],
+            };
+
+            specs
+                .into_iter()
+                .filter_map(|spec| {
+                    let id = find_job("disown", spec, shell);
+                    status &= id.is_some();
+                    id
+                })
+                .collect()
+        };
+
+        for id in ids {
+            match keep {
+                true => {
+                    if let Some(job) = shell.jobs.get_mut(id) {
+                        job.no_hangup = true;
+                    }
+                }
+                false => {
+                    shell.jobs.remove(id);
+                }
+            }
+        }
+
+        Ok(Status::from(status))
+    }
+}
+
+// The id of the job a builtin was given, the current job by default,
+// reporting those that don't exist
+pub fn find_job(builtin: &str, spec: Option<&str>, shell: &mut Shell) -> Option<usize> {
+    shell.jobs.reap();
+
+    let id = match spec {
+        Some(spec) => shell.jobs.find(spec),
+        None => shell.jobs.current(),
+    };
+
+    if id.is_none() {
+        let spec = spec.unwrap_or("current");
+        report(tr!("{}: {}: no such job", builtin, spec), shell);
+    }
+
+    id
+}
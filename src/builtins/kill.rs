@@ -0,0 +1,135 @@
+use std::io::{self, Write};
+
+use super::jobs::find_job;
+use super::BuiltinCommand;
+use crate::cmd::{report, Error};
+use crate::shell::Shell;
+use crate::signal;
+use crate::status::Status;
+use crate::sys;
+
+// `kill -l [status]...` lists signal names, translating exit statuses of
+// 128+n back to the name of signal n. `kill [-s sig | -sig] pid|%job...`
+// sends a signal, SIGTERM by default, jobs get it in their whole process group
+pub struct Kill;
+
+impl BuiltinCommand for Kill {
+    fn run(&self, args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        let mut stdout = io::stdout();
+        let mut args = args.into_iter().peekable();
+        let mut signum = signal::SIGTERM;
+
+        match args.peek().map(String::as_str) {
+            Some("-l") | Some("-L") => {
+                args.next();
+
+                if args.peek().is_none() {
+                    stdout
+                        .write_all(signal::table().as_bytes())
+                        .map_err(Error::Io)?;
+                    return Ok(Status::Exited(0));
+                }
+
+                let mut status = true;
+
+                for arg in args {
+                    let listed = match arg.parse::<i32>() {
+                        Ok(n) => signal::from_status(n)
+                            .and_then(signal::name)
+                            .map(str::to_string),
+                        Err(_) => signal::parse(&arg).map(|n| n.to_string()),
+                    };
+
+                    match listed {
+                        Some(listed) => writeln!(stdout, "{}", listed).map_err(Error::Io)?,
+                        None => {
+                            report(tr!("kill: {}: invalid signal specification", arg), shell);
+                            status = false;
+                        }
+                    }
+                }
+
+                return Ok(Status::from(status));
+            }
+
+            Some("-s") => {
+                args.next();
+                let spec = args.next().unwrap_or_default();
+                match signal::parse(&spec) {
+                    Some(n) => signum = n,
+                    None => {
+                        report(tr!("kill: {}: invalid signal specification", spec), shell);
+                        return Ok(Status::Exited(1));
+                    }
+                }
+            }
+
+            Some(arg) if arg.starts_with('-') && arg != "--" => {
+                match signal::parse(&arg[1..]) {
+                    Some(n) => signum = n,
+                    None => {
+                        report(
+                            tr!("kill: {}: invalid signal specification", &arg[1..]),
+                            shell,
+                        );
+                        return Ok(Status::Exited(1));
+                    }
+                }
+                args.next();
+            }
+
+            _ => {}
+        }
+
+        if args.peek().map(String::as_str) == Some("--") {
+            args.next();
+        }
+
+        let mut status = true;
+
+        for arg in args {
+            if arg.starts_with('%') {
+                let job = find_job("kill", Some(&arg), shell).and_then(|id| shell.jobs.get(id));
+
+                let (pgid, stopped) = match job {
+                    Some(job) => (job.pgid, job.stopped),
+                    None => {
+                        status = false;
+                        continue;
+                    }
+                };
+
+                if unsafe { sys::kill(-pgid, signum) } != 0 {
+                    report(format!("kill: {} - {}", arg, sys::last_error()), shell);
+                    status = false;
+                } else if stopped
+                    && !signal::JOB_CONTROL.contains(&signum)
+                    && signum != signal::SIGSTOP
+                {
+                    // A stopped job only gets to act on the signal once continued
+                    unsafe { sys::kill(-pgid, signal::SIGCONT) };
+                }
+
+                continue;
+            }
+
+            match arg.parse::<i32>() {
+                Ok(pid) => {
+                    if unsafe { sys::kill(pid, signum) } != 0 {
+                        report(format!("kill: ({}) - {}", pid, sys::last_error()), shell);
+                        status = false;
+                    }
+                }
+                Err(_) => {
+                    report(
+                        tr!("kill: {}: arguments must be process or job IDs", arg),
+                        shell,
+                    );
+                    status = false;
+                }
+            }
+        }
+
+        Ok(Status::from(status))
+    }
+}
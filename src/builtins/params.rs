@@ -0,0 +1,230 @@
+use super::BuiltinCommand;
+use crate::cmd::{is_name, report, Error};
+use crate::shell::Shell;
+use crate::status::Status;
+
+// `shift [n]`: drop the first n positional parameters, one by default
+pub struct Shift;
+
+impl BuiltinCommand for Shift {
+    fn run(&self, args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        let n = match args.first().map(|arg| arg.parse::<usize>()) {
+            None => 1,
+            Some(Ok(n)) => n,
+            Some(Err(_)) => {
+                report(tr!("shift: {}: numeric argument required", args[0]), shell);
+                return Ok(Status::Exited(1));
+            }
+        };
+
+        if n > shell.args.len() - 1 {
+            report(tr!("shift: {}: shift count out of range", n), shell);
+            return Ok(Status::Exited(1));
+        }
+
+        shell.args.drain(1..1 + n);
+        Ok(Status::Exited(0))
+    }
+}
+
+// `getopts optstring name [arg...]`: put the next option of the arguments, the
+// positional parameters by default, in `name`. Options followed by `:` in
+// optstring take an argument, which goes in `OPTARG`, and `OPTIND` is the
+// index of the next argument. Errors set `name` to `?`, and with optstring
+// starting with `:` they aren't reported and `OPTARG` is the option
+pub struct Getopts;
+
+impl BuiltinCommand for Getopts {
+    fn run(&self, args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        let (optstring, name) = match (args.first(), args.get(1)) {
+            (Some(optstring), Some(name)) if is_name(name) => (optstring.as_str(), name.as_str()),
+            _ => {
+                report(
+                    tr!("getopts: usage: getopts optstring name [arg...]"),
+                    shell,
+                );
+                return Ok(Status::Exited(1));
+            }
+        };
+
+        let params = match args.len() {
+            2 => shell.args[1..].to_vec(),
+            _ => args[2..].to_vec(),
+        };
+        let (silent, optstring) = match optstring.strip_prefix(':') {
+            Some(optstring) => (true, optstring),
+            None => (false, optstring),
+        };
+
+        let mut optind = shell
+            .lookup("OPTIND")
+            .and_then(|optind| optind.parse().ok())
+            .filter(|&optind| optind > 0)
+            .unwrap_or(1);
+
+        let arg = match params.get(optind - 1) {
+            Some(arg) if arg.len() > 1 && arg.starts_with('-') && arg != "--" => arg,
+            end => {
+                if end.is_some_and(|arg| arg == "--") {
+                    shell.set_var("OPTIND", (optind + 1).to_string());
+                }
+                shell.set_var(name, String::from("?"));
+                shell.unset_var("OPTARG");
+                return Ok(Status::Exited(1));
+            }
+        };
+
+        // Where in a group of options like `-ab` the next one is, starting over
+        // when `OPTIND` or the arguments were changed by someone else
+        let mut offset = match &shell.getopts_position {
+            (index, offset, args)
+                if *index == optind
+                    && *args == params
+                    && *offset < arg.len()
+                    && arg.is_char_boundary(*offset) =>
+            {
+                *offset
+            }
+            _ => 1,
+        };
+
+        let option = arg[offset..].chars().next().unwrap();
+        offset += option.len_utf8();
+
+        let mut next = || {
+            if offset >= arg.len() {
+                optind += 1;
+                offset = 1;
+            }
+        };
+
+        let found = optstring
+            .char_indices()
+            .find(|&(_, c)| c == option && c != ':');
+
+        let (value, optarg) = match found {
+            None => {
+                next();
+                if !silent {
+                    report(tr!("getopts: illegal option -- {}", option), shell);
+                }
+                (String::from("?"), silent.then(|| option.to_string()))
+            }
+
+            Some((i, _)) if optstring[i + option.len_utf8()..].starts_with(':') => {
+                if offset < arg.len() {
+                    let optarg = arg[offset..].to_string();
+                    optind += 1;
+                    offset = 1;
+                    (option.to_string(), Some(optarg))
+                } else if let Some(optarg) = params.get(optind) {
+                    optind += 2;
+                    offset = 1;
+                    (option.to_string(), Some(optarg.clone()))
+                } else {
+                    optind += 1;
+                    offset = 1;
+                    if silent {
+                        (String::from(":"), Some(option.to_string()))
+                    } else {
+                        report(
+                            tr!("getopts: option requires an argument -- {}", option),
+                            shell,
+                        );
+                        (String::from("?"), None)
+                    }
+                }
+            }
+
+            Some(_) => {
+                next();
+                (option.to_string(), None)
+            }
+        };
+
+        shell.getopts_position = (optind, offset, params);
+        shell.set_var("OPTIND", optind.to_string());
+        shell.set_var(name, value);
+
+        match optarg {
+            Some(optarg) => shell.set_var("OPTARG", optarg),
+            None => shell.unset_var("OPTARG"),
+        }
+
+        Ok(Status::Exited(0))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_getopts() {
+        let mut shell = Shell::new();
+        let words = |words: &[&str]| words.iter().map(|word| word.to_string()).collect();
+        let mut next = |args: &[&str]| {
+            let args = args.iter().map(|arg| arg.to_string()).collect();
+            let more = Getopts.run(args, &mut shell).unwrap().success();
+            let optarg = shell.lookup("OPTARG");
+            (more, shell.vars["opt"].clone(), optarg)
+        };
+        let args = ["ab:c", "opt", "-ac", "-bvalue", "-b", "x", "file"];
+
+        assert_eq!(next(&args), (true, "a".into(), None));
+        assert_eq!(next(&args), (true, "c".into(), None));
+        assert_eq!(next(&args), (true, "b".into(), Some("value".into())));
+        assert_eq!(next(&args), (true, "b".into(), Some("x".into())));
+        assert_eq!(next(&args), (false, "?".into(), None));
+        assert_eq!(shell.vars["OPTIND"], "5");
+
+        shell.vars.insert("OPTIND".into(), "1".into());
+        let mut next = |args: &[&str]| {
+            let args = args.iter().map(|arg| arg.to_string()).collect();
+            let more = Getopts.run(args, &mut shell).unwrap().success();
+            (more, shell.vars["opt"].clone(), shell.lookup("OPTARG"))
+        };
+        let args = [":a:", "opt", "-z", "-a"];
+
+        assert_eq!(next(&args), (true, "?".into(), Some("z".into())));
+        assert_eq!(next(&args), (true, ":".into(), Some("a".into())));
+        assert_eq!(next(&args), (false, "?".into(), None));
+
+        // A position left in other arguments isn't taken up again
+        shell.vars.insert("OPTIND".into(), "1".into());
+        assert!(Getopts
+            .run(words(&["ab", "opt", "-ab"]), &mut shell)
+            .unwrap()
+            .success());
+        shell.args.extend(words(&["-x"]));
+        assert!(Getopts
+            .run(words(&["ab", "opt"]), &mut shell)
+            .unwrap()
+            .success());
+        assert_eq!(shell.vars["opt"], "?");
+        shell.vars.insert("OPTIND".into(), "1".into());
+        assert!(Getopts
+            .run(words(&["ab", "opt", "-ab"]), &mut shell)
+            .unwrap()
+            .success());
+        assert!(Getopts
+            .run(words(&["ab", "opt", "-é"]), &mut shell)
+            .unwrap()
+            .success());
+        assert_eq!(shell.vars["opt"], "?");
+    }
+
+    #[test]
+    fn test_shift() {
+        let mut shell = Shell::new();
+        let words = |words: &[&str]| words.iter().map(|word| word.to_string()).collect();
+        shell.args.extend(words(&["a", "b", "c"]));
+
+        assert!(Shift.run(vec![], &mut shell).unwrap().success());
+        assert_eq!(shell.args[1..], ["b", "c"]);
+        assert!(!Shift.run(words(&["3"]), &mut shell).unwrap().success());
+        assert!(!Shift.run(words(&["x"]), &mut shell).unwrap().success());
+        assert!(Shift.run(words(&["2"]), &mut shell).unwrap().success());
+        assert_eq!(shell.args, ["rush"]);
+    }
+}
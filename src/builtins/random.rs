@@ -0,0 +1,68 @@
+use std::io::{self, Write};
+
+use super::BuiltinCommand;
+use crate::cmd::{report, Error};
+use crate::shell::Shell;
+use crate::status::Status;
+
+// `random [low high]` or `random choice word...`: print a number between low
+// and high, both included, 0 to 32767 by default, or one of the words
+pub struct Random;
+
+impl BuiltinCommand for Random {
+    fn run(&self, args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        random(args, shell).map(Status::from)
+    }
+}
+
+fn random(args: Vec<String>, shell: &mut Shell) -> Result<bool, Error> {
+    if !shell.rng.is_seeded() {
+        let seed = shell.lookup("RANDOM_SEED");
+        shell.rng.seed(seed.as_deref());
+    }
+
+    let output = match args.first().map(String::as_str) {
+        Some("choice") if args.len() > 1 => {
+            let i = shell.rng.range(1, args.len() as i64 - 1) as usize;
+            args[i].clone()
+        }
+
+        Some("choice") => {
+            report(tr!("random: choice: nothing to choose from"), shell);
+            return Ok(false);
+        }
+
+        None => shell.rng.range(0, 32767).to_string(),
+
+        Some(_) if args.len() == 2 => {
+            let bounds: Vec<i64> = match args.iter().map(|arg| arg.parse()).collect() {
+                Ok(bounds) => bounds,
+                Err(_) => {
+                    let arg = args.iter().find(|arg| arg.parse::<i64>().is_err()).unwrap();
+                    report(tr!("random: {}: not a number", arg), shell);
+                    return Ok(false);
+                }
+            };
+
+            if bounds[0] > bounds[1] {
+                report(
+                    tr!("random: {} is greater than {}", bounds[0], bounds[1]),
+                    shell,
+                );
+                return Ok(false);
+            }
+            shell.rng.range(bounds[0], bounds[1]).to_string()
+        }
+
+        Some(_) => {
+            report(
+                tr!("random: usage: random [low high] | random choice word..."),
+                shell,
+            );
+            return Ok(false);
+        }
+    };
+
+    writeln!(io::stdout(), "{}", output).map_err(Error::Io)?;
+    Ok(true)
+}
@@ -0,0 +1,64 @@
+use std::env;
+use std::ffi::OsStr;
+use std::io::{self, Write};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::process::CommandExt;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::Duration;
+
+use super::BuiltinCommand;
+use crate::cmd::{self, report, Error};
+use crate::shell::Shell;
+use crate::status::Status;
+
+// `restart`: replace the shell with a fresh run of its binary, with the same
+// arguments, working directory and environment. What isn't exported, like
+// functions and aliases, is left behind and read again from the startup
+// files, so it picks up a new binary or deep configuration changes
+pub struct Restart;
+
+impl BuiltinCommand for Restart {
+    fn run(&self, _: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        // Jobs wouldn't be the new shell's, so it takes asking twice like
+        // exiting does
+        if !cmd::confirm_exit(shell) {
+            return Ok(Status::Exited(1));
+        }
+
+        let exe = match binary() {
+            Ok(exe) => exe,
+            Err(e) => {
+                report(format!("restart: {}", e), shell);
+                return Ok(Status::Exited(1));
+            }
+        };
+
+        // This line is in the history but only stored once it's done
+        if let Err(e) = shell.history.finish(Duration::ZERO, 0) {
+            report(format!("history: {}", e), shell);
+        }
+        io::stdout().flush().map_err(Error::Io)?;
+
+        let mut args = env::args_os();
+        let mut command = Command::new(&exe);
+        if let Some(arg0) = args.next() {
+            command.arg0(arg0);
+        }
+
+        let e = command.args(args).exec();
+        report(format!("restart: {}: {}", exe.display(), e), shell);
+        Ok(Status::Exited(1))
+    }
+}
+
+// The binary the shell runs from. Once it's replaced, as by an upgrade, Linux
+// has it as `/path/rush (deleted)` and the new one is at the path itself
+fn binary() -> io::Result<PathBuf> {
+    let exe = env::current_exe()?;
+
+    match exe.as_os_str().as_bytes().strip_suffix(b" (deleted)") {
+        Some(path) if !exe.exists() => Ok(PathBuf::from(OsStr::from_bytes(path))),
+        _ => Ok(exe),
+    }
+}
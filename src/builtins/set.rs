@@ -0,0 +1,235 @@
+use std::io::{self, Write};
+
+use super::BuiltinCommand;
+use crate::cmd::{quote_if_needed, report, Error};
+use crate::editor::Keymap;
+use crate::options;
+use crate::shell::Shell;
+use crate::status::Status;
+
+// `set [-eux] [+eux] [-o name] [+o name] [--] [arg...]`: turn options on or
+// off and set the positional parameters to the arguments if there are any,
+// `-o` alone lists the options and `+o` alone prints the commands that
+// restore them. Without arguments the shell variables are listed
+pub struct Set;
+
+impl BuiltinCommand for Set {
+    fn run(&self, args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        let mut stdout = io::stdout();
+
+        if args.is_empty() {
+            for (name, value) in &shell.vars {
+                writeln!(stdout, "{}={}", name, quote_if_needed(value)).map_err(Error::Io)?;
+            }
+            return Ok(Status::Exited(0));
+        }
+
+        let mut args = args.into_iter().peekable();
+
+        while let Some(arg) = args.peek() {
+            let on = match arg.chars().next() {
+                Some('-') => true,
+                Some('+') => false,
+                _ => break,
+            };
+            let arg = args.next().unwrap_or_default();
+
+            match &arg[1..] {
+                // `--` ends the options, even with no arguments after it to
+                // replace the positional parameters
+                "-" => break,
+
+                // `-` and `+` do too for compatibility, leaving them as they are
+                // when there's nothing after
+                "" if args.peek().is_none() => return Ok(Status::Exited(0)),
+                "" => break,
+
+                "o" => match args.next() {
+                    Some(name) if shell.options.set(&name, on) => {}
+
+                    // The editing modes pick the keymap preset
+                    Some(name) if name == "vi" || name == "emacs" => {
+                        let preset = match (name == "vi", on) {
+                            (true, true) | (false, false) => "vi",
+                            _ => "readline",
+                        };
+                        shell.keymap = Keymap::preset(preset).unwrap();
+                    }
+
+                    Some(name) => {
+                        report(tr!("set: {}: invalid option name", name), shell);
+                        return Ok(Status::Exited(1));
+                    }
+                    None => {
+                        let vi = shell.keymap.preset_name() == "vi";
+                        let mut names: Vec<(&str, bool)> = options::NAMES
+                            .iter()
+                            .map(|(name, _)| (*name, shell.options.get(name) == Some(true)))
+                            .chain([("emacs", !vi), ("vi", vi)].iter().copied())
+                            .collect();
+                        names.sort();
+
+                        for (name, value) in names {
+                            match on {
+                                true => {
+                                    writeln!(
+                                        stdout,
+                                        "{:<16}{}",
+                                        name,
+                                        if value { "on" } else { "off" }
+                                    )
+                                }
+                                false => {
+                                    writeln!(
+                                        stdout,
+                                        "set {}o {}",
+                                        if value { '-' } else { '+' },
+                                        name
+                                    )
+                                }
+                            }
+                            .map_err(Error::Io)?;
+                        }
+                    }
+                },
+
+                letters => {
+                    for letter in letters.chars() {
+                        match options::by_letter(letter) {
+                            Some(name) => {
+                                shell.options.set(name, on);
+                            }
+                            None => {
+                                report(tr!("set: -{}: invalid option", letter), shell);
+                                return Ok(Status::Exited(1));
+                            }
+                        }
+                    }
+                }
+            }
+
+            // Arguments after the options replace the positional parameters
+            if args.peek().is_none() && arg != "--" {
+                return Ok(Status::Exited(0));
+            }
+        }
+
+        shell.args.truncate(1);
+        shell.args.extend(args);
+        Ok(Status::Exited(0))
+    }
+}
+
+// `shopt [-pqsu] [name...]`: turn the named extended options on with `-s` or
+// off with `-u`, otherwise tell whether they're on, or list every option. `-p`
+// lists them as commands and `-q` just sets the status
+pub struct Shopt;
+
+impl BuiltinCommand for Shopt {
+    fn run(&self, args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        let mut stdout = io::stdout();
+        let mut set = None;
+        let mut reusable = false;
+        let mut quiet = false;
+
+        let mut args = args.into_iter().peekable();
+        while let Some(flags) = args.peek().and_then(|arg| arg.strip_prefix('-')) {
+            for flag in flags.chars() {
+                match flag {
+                    's' => set = Some(true),
+                    'u' => set = Some(false),
+                    'p' => reusable = true,
+                    'q' => quiet = true,
+                    _ => {
+                        report(tr!("shopt: -{}: invalid option", flag), shell);
+                        return Ok(Status::Exited(1));
+                    }
+                }
+            }
+            args.next();
+        }
+
+        let names: Vec<String> = args.collect();
+
+        for name in &names {
+            if shell.shopts.get(name).is_none() {
+                report(tr!("shopt: {}: invalid shell option name", name), shell);
+                return Ok(Status::Exited(1));
+            }
+        }
+
+        if let Some(on) = set {
+            for name in &names {
+                shell.shopts.set(name, on);
+            }
+            return Ok(Status::Exited(0));
+        }
+
+        // Listing every option succeeds, asking about some only if they're on
+        let listed: Vec<&str> = match names.is_empty() {
+            true => options::SHOPT_NAMES.to_vec(),
+            false => names.iter().map(String::as_str).collect(),
+        };
+
+        let mut status = true;
+
+        for name in listed {
+            let on = shell.shopts.get(name) == Some(true);
+            status &= on || names.is_empty();
+
+            if quiet {
+                continue;
+            }
+
+            match reusable {
+                true => writeln!(stdout, "shopt {} {}", if on { "-s" } else { "-u" }, name),
+                false => writeln!(stdout, "{:<16}{}", name, if on { "on" } else { "off" }),
+            }
+            .map_err(Error::Io)?;
+        }
+
+        Ok(Status::from(status))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_set_builtin() {
+        let mut shell = Shell::new();
+        let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect();
+
+        assert!(Set
+            .run(args(&["-eu", "-o", "pipefail", "a", "b"]), &mut shell)
+            .unwrap()
+            .success());
+        assert_eq!(shell.options.letters(), "eu");
+        assert!(shell.options.pipefail);
+        assert_eq!(shell.args[1..], ["a", "b"]);
+
+        assert!(Set.run(args(&["+e"]), &mut shell).unwrap().success());
+        assert_eq!(shell.options.letters(), "u");
+        assert_eq!(shell.args.len(), 3);
+
+        assert!(Set.run(args(&["-"]), &mut shell).unwrap().success());
+        assert!(Set.run(args(&["+"]), &mut shell).unwrap().success());
+        assert_eq!(shell.args[1..], ["a", "b"]);
+        assert!(Set.run(args(&["-", "c"]), &mut shell).unwrap().success());
+        assert_eq!(shell.args[1..], ["c"]);
+
+        assert!(Set.run(args(&["--"]), &mut shell).unwrap().success());
+        assert_eq!(shell.args.len(), 1);
+
+        assert!(Set.run(args(&["-o", "vi"]), &mut shell).unwrap().success());
+        assert_eq!(shell.keymap.preset_name(), "vi");
+        assert!(Set
+            .run(args(&["-o", "emacs"]), &mut shell)
+            .unwrap()
+            .success());
+        assert_eq!(shell.keymap.preset_name(), "readline");
+
+        assert!(!Set.run(args(&["-q"]), &mut shell).unwrap().success());
+    }
+}
@@ -0,0 +1,85 @@
+use std::path::Path;
+
+use super::BuiltinCommand;
+use crate::cmd::{fork, report, run_function, run_source, wait_pid, Error};
+use crate::shell::Shell;
+use crate::status::Status;
+
+// `shtest file...`: source every file in a subshell and run each `test_*`
+// function it defines in a subshell of its own, between `setup` and
+// `teardown` if the file defines those, reporting the ones that fail
+pub struct Shtest;
+
+impl BuiltinCommand for Shtest {
+    fn run(&self, files: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        if files.is_empty() {
+            report(tr!("shtest: usage: shtest file..."), shell);
+            return Ok(Status::Exited(1));
+        }
+
+        let mut success = true;
+
+        for file in files {
+            let pid = fork(shell, |shell| {
+                let before = shell.functions.clone();
+
+                if let Err(e) = run_source(Path::new(&file), vec![], shell) {
+                    report(format!("{}: {}", file, e), shell);
+                    return Status::Exited(2);
+                }
+
+                let tests: Vec<String> = shell
+                    .functions
+                    .iter()
+                    .filter(|(name, body)| {
+                        name.starts_with("test_") && before.get(*name) != Some(body)
+                    })
+                    .map(|(name, _)| name.clone())
+                    .collect();
+
+                println!("{}:", file);
+                let mut failed = 0;
+
+                for name in &tests {
+                    let status = fork(shell, |shell| run_test(name, shell)).and_then(wait_pid);
+
+                    match status {
+                        Ok(status) if status.success() => println!("ok {}", name),
+                        Ok(status) => {
+                            failed += 1;
+                            println!("not ok {} ({})", name, status);
+                        }
+                        Err(e) => {
+                            failed += 1;
+                            println!("not ok {} ({})", name, e);
+                        }
+                    }
+                }
+
+                println!(
+                    "{}",
+                    tr!("{} passed, {} failed", tests.len() - failed, failed)
+                );
+                Status::from(failed == 0)
+            })?;
+
+            if !wait_pid(pid)?.success() {
+                success = false;
+            }
+        }
+
+        Ok(Status::from(success))
+    }
+}
+
+fn run_test(name: &str, shell: &mut Shell) -> Status {
+    let call = |name: &str, shell: &mut Shell| run_function(name, vec![], shell);
+
+    if let Some(status) = call("setup", shell).filter(|status| !status.success()) {
+        return status;
+    }
+
+    let status = call(name, shell).unwrap_or(Status::Exited(127));
+    call("teardown", shell);
+    status
+}
@@ -0,0 +1,94 @@
+use std::time::{Duration, Instant};
+
+use super::BuiltinCommand;
+use crate::cmd::{report, spawn_external, Error};
+use crate::shell::Shell;
+use crate::signal;
+use crate::status::Status;
+use crate::sys;
+
+// `sleep number[smhd]...`: wait for the sum of the intervals, fractions
+// allowed. A signal with a trap or Ctrl-C ends it early. Under job control
+// without traps the external sleep runs instead, so Ctrl-Z can stop it
+pub struct Sleep;
+
+impl BuiltinCommand for Sleep {
+    fn run(&self, args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        if args.is_empty() {
+            report(tr!("sleep: missing operand"), shell);
+            return Ok(Status::Exited(1));
+        }
+
+        let mut seconds = 0.0;
+
+        for arg in &args {
+            match parse_interval(arg) {
+                Some(interval) => seconds += interval,
+                None => {
+                    report(tr!("sleep: invalid time interval `{}'", arg), shell);
+                    return Ok(Status::Exited(1));
+                }
+            }
+        }
+
+        if shell.job_control && !shell.traps.catches_signals() && shell.hash.find("sleep").is_some()
+        {
+            let mut words = vec![String::from("sleep")];
+            words.extend(args);
+            return spawn_external(words, false, shell);
+        }
+
+        let deadline = Instant::now() + Duration::from_secs_f64(seconds);
+        let mut status = Status::Exited(0);
+
+        while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+            if let Some(signum) = signal::peek() {
+                status = Status::Exited(128 + signum);
+                break;
+            }
+
+            // Returns early when a signal arrives
+            let timeout = left.as_millis().clamp(1, i32::MAX as u128) as i32;
+            unsafe {
+                sys::poll(std::ptr::null_mut(), 0, timeout);
+            }
+        }
+
+        Ok(status)
+    }
+}
+
+fn parse_interval(arg: &str) -> Option<f64> {
+    let (number, unit) = match arg.find(|c: char| c.is_ascii_alphabetic()) {
+        Some(i) => arg.split_at(i),
+        None => (arg, "s"),
+    };
+
+    let scale = match unit {
+        "s" => 1.0,
+        "m" => 60.0,
+        "h" => 3600.0,
+        "d" => 86400.0,
+        _ => return None,
+    };
+
+    match number.parse::<f64>() {
+        Ok(number) if number >= 0.0 && number.is_finite() => Some(number * scale),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sleep_intervals() {
+        assert_eq!(parse_interval("1.5"), Some(1.5));
+        assert_eq!(parse_interval("2m"), Some(120.0));
+        assert_eq!(parse_interval(".5h"), Some(1800.0));
+        assert_eq!(parse_interval("1x"), None);
+        assert_eq!(parse_interval("-1"), None);
+        assert_eq!(parse_interval("inf"), None);
+    }
+}
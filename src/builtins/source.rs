@@ -0,0 +1,308 @@
+use std::fs;
+use std::io::{self, Write};
+use std::os::unix::io::{AsRawFd, FromRawFd};
+use std::path::Path;
+
+use super::BuiltinCommand;
+use crate::cmd::{
+    find_source, fork, is_incomplete, max_depth, report, run_source, Cmd, Error, Expression, Invoke,
+};
+use crate::defer::{Deferred, Snapshot};
+use crate::path;
+use crate::shell::Shell;
+use crate::status::Status;
+use crate::sys;
+use crate::trust;
+
+// `source file [arg...]` or `. file [arg...]`: run the file in the current
+// shell, see `run_source`. `source --check file...` just checks the files
+pub struct Source;
+
+impl BuiltinCommand for Source {
+    fn run(&self, mut args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        if args.first().is_some_and(|arg| arg == "--check") {
+            return check(args.split_off(1), shell);
+        }
+        if args.is_empty() {
+            report(tr!("source: no file given"), shell);
+            return Ok(Status::Exited(1));
+        }
+
+        let path = args.remove(0);
+        match run_source(Path::new(&path), args, shell) {
+            Err(Error::Io(e)) => {
+                report(format!("source: {}: {}", path, sys::describe(&e)), shell);
+                Ok(Status::Exited(1))
+            }
+            status => status,
+        }
+    }
+}
+
+// `async-source file [arg...]`: source the file in a forked shell, what it
+// defines is merged once it's done
+pub struct AsyncSource;
+
+impl BuiltinCommand for AsyncSource {
+    fn run(&self, mut params: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        if params.is_empty() {
+            report(tr!("source: no file given"), shell);
+            return Ok(Status::Exited(1));
+        }
+        let path = find_source(Path::new(&params.remove(0)));
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) => {
+                let file = path.display();
+                report(
+                    format!("async-source: {}: {}", file, sys::describe(&e)),
+                    shell,
+                );
+                return Ok(Status::Exited(1));
+            }
+        };
+
+        // Asked here, the child can't read the terminal
+        if shell.job_control && !trust::allow(&path, &contents) {
+            let file = path.display();
+            report(tr!("source: {}: not trusted, not sourced", file), shell);
+            return Ok(Status::Exited(1));
+        }
+
+        let mut fds = [0; 2];
+
+        if unsafe { sys::pipe(fds.as_mut_ptr()) } != 0 {
+            return Err(Error::Io(io::Error::last_os_error()));
+        }
+
+        let [read_fd, write_fd] = fds;
+        let null = fs::File::open("/dev/null").map_err(Error::Io)?;
+
+        let pid = fork(shell, |shell| {
+            unsafe {
+                sys::dup2(null.as_raw_fd(), 0);
+                sys::close(read_fd);
+            }
+
+            let snapshot = Snapshot::take(shell);
+            let status = match run_source(&path, params, shell) {
+                Ok(status) => status,
+                Err(e) => {
+                    report(format!("{}: {}", path.display(), e), shell);
+                    Status::Exited(1)
+                }
+            };
+
+            let mut pipe = unsafe { fs::File::from_raw_fd(write_fd) };
+            let _ = pipe.write_all(&snapshot.changes(shell));
+            status
+        });
+
+        unsafe {
+            sys::close(write_fd);
+        }
+
+        let pipe = unsafe { fs::File::from_raw_fd(read_fd) };
+        shell.deferred.push(Deferred { pid: pid?, pipe });
+        Ok(Status::Exited(0))
+    }
+}
+
+// `source --check file...`: parse the files without running them, reporting
+// syntax errors and the commands that can't be found
+fn check(files: Vec<String>, shell: &Shell) -> Result<Status, Error> {
+    if files.is_empty() {
+        report(tr!("source: no file given"), shell);
+        return Ok(Status::Exited(1));
+    }
+
+    let mut status = true;
+
+    for file in files {
+        let path = find_source(Path::new(&file));
+        match fs::read_to_string(&path) {
+            Ok(contents) => status &= check_source(&path.display().to_string(), &contents, shell),
+            Err(e) => {
+                report(format!("source: {}: {}", file, sys::describe(&e)), shell);
+                status = false;
+            }
+        }
+    }
+
+    Ok(Status::from(status))
+}
+
+fn check_source(file: &str, contents: &str, shell: &Shell) -> bool {
+    // Lines are joined the same way sourcing does, each statement keeps the
+    // number of its first line
+    let mut statements = vec![];
+    let mut pending = String::new();
+    let mut first = 0;
+    let lines: Vec<&str> = contents.lines().collect();
+
+    for (i, line) in lines.iter().enumerate() {
+        if pending.is_empty() {
+            first = i + 1;
+        }
+        pending.push_str(line);
+        pending.push('\n');
+
+        if i + 1 < lines.len() && is_incomplete(&pending, shell) {
+            continue;
+        }
+
+        statements.push((first, shell.expand_aliases(&pending)));
+        pending.clear();
+    }
+
+    let mut status = true;
+    let mut used = vec![];
+    let mut defined = vec![];
+
+    for (line, text) in &statements {
+        match Expression::parse(text, max_depth()) {
+            Ok(expr) => commands(&expr, *line, *line, &mut used, &mut defined),
+            Err(Error::EmptyLine) => {}
+            Err(e) => {
+                eprintln!("{}:{}: {}", file, line, e);
+                status = false;
+            }
+        }
+    }
+
+    // Only checked once the whole file is parsed, a function may call
+    // functions defined further down
+    for (line, name) in used {
+        let known = defined.contains(&name)
+            || shell.builtins.contains(name)
+            || shell.functions.contains_key(name)
+            || !path::find_all_executables(name).is_empty();
+
+        if !known {
+            eprintln!(
+                "{}:{}: {}",
+                file,
+                line,
+                tr!("warning: {}: command not found", name)
+            );
+        }
+    }
+
+    status
+}
+
+// The commands an expression invokes and the functions it defines, names
+// that are only known once expanded are left out. Its text starts on line
+// `start` and it's on `line`
+fn commands<'a>(
+    expr: &Expression<'a>,
+    start: usize,
+    line: usize,
+    used: &mut Vec<(usize, &'a str)>,
+    defined: &mut Vec<&'a str>,
+) {
+    match expr {
+        Expression::Cmd(cmd) => {
+            let mut cmd = cmd;
+            while let Cmd::WithEnv(_, inner) = cmd {
+                cmd = inner;
+            }
+
+            if let Cmd::Invoke(Invoke { binary, .. }) = cmd {
+                if let Some(name) = binary.to_str() {
+                    if !name.contains(|c| "$`'\"\\~".contains(c)) {
+                        used.push((line, name));
+                    }
+                }
+            }
+        }
+
+        Expression::Compound(compound) => {
+            commands(&compound.left, start, line, used, defined);
+            commands(&compound.right, start, line, used, defined);
+        }
+
+        Expression::Subshell(forked)
+        | Expression::Background(forked)
+        | Expression::Disowned(forked) => commands(&forked.body, start, line, used, defined),
+
+        Expression::Group(body) | Expression::Not(body) => {
+            commands(body, start, line, used, defined)
+        }
+
+        Expression::Case(case) => {
+            for body in case.items.iter().filter_map(|(_, body)| body.as_ref()) {
+                commands(body, start, line, used, defined);
+            }
+        }
+
+        Expression::ArithFor(arith_for) => {
+            if let Ok(body) = Expression::parse(arith_for.body, max_depth()) {
+                let start = start + arith_for.body_line;
+                commands(&body, start, start, used, defined);
+            }
+        }
+
+        Expression::Select(select) => {
+            if let Ok(body) = Expression::parse(select.body, max_depth()) {
+                let start = start + select.body_line;
+                commands(&body, start, start, used, defined);
+            }
+        }
+
+        Expression::If(conditional) => {
+            for (cond, body) in &conditional.branches {
+                commands(cond, start, line, used, defined);
+                commands(body, start, line, used, defined);
+            }
+            if let Some(body) = &conditional.otherwise {
+                commands(body, start, line, used, defined);
+            }
+        }
+
+        Expression::Pipeline(pipeline) => {
+            for stage in &pipeline.stages {
+                commands(stage, start, line, used, defined);
+            }
+        }
+
+        Expression::Function(function) => {
+            defined.push(function.name);
+            if let Ok(body) = Expression::parse(function.body, max_depth()) {
+                let start = start + function.body_line;
+                commands(&body, start, start, used, defined);
+            }
+        }
+
+        Expression::Redirected(redirected) => {
+            commands(&redirected.body, start, line, used, defined)
+        }
+
+        Expression::Line(n, expr) => commands(expr, start, start + n, used, defined),
+
+        Expression::Arith(_) | Expression::Cond(_) | Expression::Assert(_) => {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_source_check() {
+        let shell = Shell::new();
+
+        assert!(check_source(
+            "ok",
+            "f() {\n  g\n}\ng() { true; }\nf\n",
+            &shell
+        ));
+        assert!(check_source(
+            "unknown",
+            "no-such-command-anywhere\n",
+            &shell
+        ));
+        assert!(!check_source("syntax", "true\n{ true\n", &shell));
+        assert!(!check_source("quote", "echo 'a\n", &shell));
+    }
+}
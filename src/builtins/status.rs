@@ -0,0 +1,22 @@
+use super::BuiltinCommand;
+use crate::cmd::Error;
+use crate::shell::Shell;
+use crate::status::Status;
+
+// `true` and `:`, the arguments are still expanded
+pub struct True;
+
+// `false`
+pub struct False;
+
+impl BuiltinCommand for True {
+    fn run(&self, _: Vec<String>, _: &mut Shell) -> Result<Status, Error> {
+        Ok(Status::Exited(0))
+    }
+}
+
+impl BuiltinCommand for False {
+    fn run(&self, _: Vec<String>, _: &mut Shell) -> Result<Status, Error> {
+        Ok(Status::Exited(1))
+    }
+}
@@ -0,0 +1,40 @@
+use std::io::{self, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::BuiltinCommand;
+use crate::cmd::{report, Error};
+use crate::shell::Shell;
+use crate::status::Status;
+use crate::sys;
+
+// `strftime format [seconds]`: print the time given in seconds since the
+// epoch, or the current one, in the format strftime(3) takes
+pub struct Strftime;
+
+impl BuiltinCommand for Strftime {
+    fn run(&self, args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        let format = match args.first() {
+            Some(format) => format,
+            None => {
+                report(tr!("strftime: usage: strftime format [seconds]"), shell);
+                return Ok(Status::Exited(1));
+            }
+        };
+
+        let time = match args.get(1) {
+            Some(seconds) => match seconds.parse() {
+                Ok(seconds) => seconds,
+                Err(_) => {
+                    report(tr!("strftime: {}: invalid time", seconds), shell);
+                    return Ok(Status::Exited(1));
+                }
+            },
+            None => SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |now| now.as_secs() as i64),
+        };
+
+        writeln!(io::stdout(), "{}", sys::format_time(format, time)).map_err(Error::Io)?;
+        Ok(Status::Exited(0))
+    }
+}
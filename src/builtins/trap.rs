@@ -0,0 +1,102 @@
+use std::io::{self, Write};
+
+use super::BuiltinCommand;
+use crate::cmd::{report, Error};
+use crate::shell::Shell;
+use crate::signal;
+use crate::status::Status;
+use crate::trap::{TrapError, Traps};
+
+// `trap [-lp] [[action] condition...]`: set, reset or print the commands run
+// when the shell receives a signal or exits
+pub struct Trap;
+
+impl BuiltinCommand for Trap {
+    fn run(&self, args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        let mut stdout = io::stdout();
+        let mut args = args.into_iter().peekable();
+
+        match args.peek().map(String::as_str) {
+            Some("-l") => {
+                stdout
+                    .write_all(signal::table().as_bytes())
+                    .map_err(Error::Io)?;
+                return Ok(Status::Exited(0));
+            }
+
+            None | Some("-p") => {
+                args.next();
+                let mut conditions = vec![];
+                let mut status = true;
+
+                for spec in args {
+                    match Traps::parse_condition(&spec) {
+                        Ok(condition) => conditions.push(condition),
+                        Err(e) => {
+                            print_trap_error(e, shell);
+                            status = false;
+                        }
+                    }
+                }
+
+                let filter = if conditions.is_empty() {
+                    None
+                } else {
+                    Some(conditions.as_slice())
+                };
+
+                for line in shell.traps.serialize(filter) {
+                    writeln!(stdout, "{}", line).map_err(Error::Io)?;
+                }
+                return Ok(Status::from(status));
+            }
+
+            Some("--") => {
+                args.next();
+            }
+
+            _ => {}
+        }
+
+        let mut args: Vec<_> = args.collect();
+        if args.is_empty() {
+            return Ok(Status::Exited(0));
+        }
+
+        // A lone condition resets it, as does an action of `-`
+        let action = if args.len() == 1 || args[0] == "-" {
+            if args.len() > 1 {
+                args.remove(0);
+            }
+            None
+        } else {
+            Some(args.remove(0))
+        };
+
+        let mut status = true;
+
+        for spec in args {
+            match Traps::parse_condition(&spec) {
+                Ok(condition) => match &action {
+                    Some(action) => shell.traps.set(condition, action.clone()),
+                    None => shell.traps.reset(condition),
+                },
+                Err(e) => {
+                    print_trap_error(e, shell);
+                    status = false;
+                }
+            }
+        }
+
+        Ok(Status::from(status))
+    }
+}
+
+fn print_trap_error(e: TrapError, shell: &Shell) {
+    match e {
+        TrapError::BadSignal(spec) => {
+            report(tr!("trap: {}: invalid signal specification", spec), shell)
+        }
+        TrapError::Untrappable(spec) => report(tr!("trap: {}: cannot be trapped", spec), shell),
+    }
+}
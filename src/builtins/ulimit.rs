@@ -0,0 +1,125 @@
+use std::io::{self, Write};
+
+use super::BuiltinCommand;
+use crate::cmd::{report, Error};
+use crate::shell::Shell;
+use crate::status::Status;
+use crate::sys;
+
+// Resource limits known to `ulimit` as (flag, resource, unit, description)
+const RESOURCES: &[(char, i32, u64, &str)] = &[
+    ('c', 4, 1024, "core file size          (blocks, -c)"),
+    ('f', 1, 1024, "file size               (blocks, -f)"),
+    ('n', 7, 1, "open files                      (-n)"),
+    ('s', 3, 1024, "stack size              (kbytes, -s)"),
+    ('u', 6, 1, "max user processes              (-u)"),
+    ('v', 9, 1024, "virtual memory          (kbytes, -v)"),
+];
+
+// `ulimit [-SH] [-a | -cfnsuv] [limit]`: print or set resource limits, e.g.
+// `ulimit -c unlimited` enables core dumps and `ulimit -c 0` disables them
+pub struct Ulimit;
+
+impl BuiltinCommand for Ulimit {
+    fn run(&self, args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        let mut stdout = io::stdout();
+        let mut soft = false;
+        let mut hard = false;
+        let mut all = false;
+        let mut resource = RESOURCES[1];
+        let mut limit = None;
+
+        for arg in args {
+            match arg.strip_prefix('-') {
+                Some(flags) if !flags.is_empty() => {
+                    for flag in flags.chars() {
+                        match flag {
+                            'S' => soft = true,
+                            'H' => hard = true,
+                            'a' => all = true,
+                            _ => match RESOURCES.iter().find(|r| r.0 == flag) {
+                                Some(r) => resource = *r,
+                                None => {
+                                    report(tr!("ulimit: -{}: invalid option", flag), shell);
+                                    return Ok(Status::Exited(1));
+                                }
+                            },
+                        }
+                    }
+                }
+                _ => limit = Some(arg),
+            }
+        }
+
+        let get = |resource: i32| {
+            let mut rlim = sys::RLimit {
+                rlim_cur: 0,
+                rlim_max: 0,
+            };
+            if unsafe { sys::getrlimit(resource, &mut rlim) } != 0 {
+                return Err(Error::Io(io::Error::last_os_error()));
+            }
+            Ok(rlim)
+        };
+
+        let show = |rlim: &sys::RLimit, unit: u64| {
+            let value = if hard && !soft {
+                rlim.rlim_max
+            } else {
+                rlim.rlim_cur
+            };
+            if value == sys::RLIM_INFINITY {
+                String::from("unlimited")
+            } else {
+                (value / unit).to_string()
+            }
+        };
+
+        if all {
+            for (_, res, unit, description) in RESOURCES {
+                writeln!(stdout, "{} {}", description, show(&get(*res)?, *unit))
+                    .map_err(Error::Io)?;
+            }
+            return Ok(Status::Exited(0));
+        }
+
+        let (_, res, unit, _) = resource;
+        let mut rlim = get(res)?;
+
+        let limit = match limit {
+            None => {
+                writeln!(stdout, "{}", show(&rlim, unit)).map_err(Error::Io)?;
+                return Ok(Status::Exited(0));
+            }
+            Some(limit) => limit,
+        };
+
+        let value = match limit.as_str() {
+            "unlimited" => sys::RLIM_INFINITY,
+            "hard" => rlim.rlim_max,
+            "soft" => rlim.rlim_cur,
+            n => match n.parse::<u64>() {
+                Ok(n) => n.saturating_mul(unit),
+                Err(_) => {
+                    report(tr!("ulimit: {}: invalid number", limit), shell);
+                    return Ok(Status::Exited(1));
+                }
+            },
+        };
+
+        // Without -S or -H both limits are set
+        if soft || !hard {
+            rlim.rlim_cur = value;
+        }
+        if hard || !soft {
+            rlim.rlim_max = value;
+        }
+
+        if unsafe { sys::setrlimit(res, &rlim) } != 0 {
+            report(format!("ulimit: {}", sys::last_error()), shell);
+            return Ok(Status::Exited(1));
+        }
+
+        Ok(Status::Exited(0))
+    }
+}
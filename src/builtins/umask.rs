@@ -0,0 +1,70 @@
+use super::BuiltinCommand;
+use crate::cmd::{quote_if_needed, report, run_line, Error};
+use crate::mode;
+use crate::shell::Shell;
+use crate::status::Status;
+use crate::sys;
+
+// `umask [-pS] [mode [command [args]...]]`: print the file creation mask, in
+// symbolic form with `-S` and as a command setting it with `-p`, or set it
+// from an octal or symbolic mode. Given a command the mask is only set while
+// the command runs
+pub struct Umask;
+
+impl BuiltinCommand for Umask {
+    fn run(&self, args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        let mut args = args.into_iter().peekable();
+        let mut symbolic = false;
+        let mut reusable = false;
+
+        while let Some(flags) = args.peek().and_then(|arg| arg.strip_prefix('-')) {
+            if flags.is_empty() || !flags.chars().all(|flag| flag == 'S' || flag == 'p') {
+                break;
+            }
+            symbolic |= flags.contains('S');
+            reusable |= flags.contains('p');
+            args.next();
+        }
+
+        let current = unsafe { sys::umask(0) };
+        unsafe { sys::umask(current) };
+
+        let print = |mask| match (symbolic, reusable) {
+            (true, true) => println!("umask -S {}", mode::symbolic(mask)),
+            (true, false) => println!("{}", mode::symbolic(mask)),
+            (false, true) => println!("umask {:04o}", mask),
+            (false, false) => println!("{:04o}", mask),
+        };
+
+        let spec = match args.next() {
+            Some(spec) => spec,
+            None => {
+                print(current);
+                return Ok(Status::Exited(0));
+            }
+        };
+
+        let mask = match mode::parse(&spec, current) {
+            Some(mask) => mask,
+            None => {
+                report(tr!("umask: {}: invalid mode", spec), shell);
+                return Ok(Status::Exited(1));
+            }
+        };
+
+        unsafe { sys::umask(mask) };
+
+        let command: Vec<String> = args.map(|arg| quote_if_needed(&arg)).collect();
+
+        if command.is_empty() {
+            if symbolic {
+                print(mask);
+            }
+            return Ok(Status::Exited(0));
+        }
+
+        run_line(&command.join(" "), shell);
+        unsafe { sys::umask(current) };
+        Ok(shell.status)
+    }
+}
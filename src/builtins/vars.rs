@@ -0,0 +1,107 @@
+use std::env;
+use std::io::{self, Write};
+
+use super::BuiltinCommand;
+use crate::cmd::{is_name, quote, report, subscript, Error};
+use crate::shell::Shell;
+use crate::status::Status;
+
+// `export [name[=value]]...`: move variables into the environment inherited by
+// children, without arguments list the exported variables
+pub struct Export;
+
+impl BuiltinCommand for Export {
+    fn run(&self, args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        if args.is_empty() {
+            let mut stdout = io::stdout();
+            let mut vars: Vec<_> = env::vars().collect();
+            vars.sort();
+
+            for (name, value) in vars {
+                writeln!(stdout, "export {}={}", name, quote(&value)).map_err(Error::Io)?;
+            }
+            return Ok(Status::Exited(0));
+        }
+
+        let mut status = true;
+
+        for arg in args {
+            let (name, value) = match arg.find('=') {
+                Some(eq) => (&arg[..eq], Some(arg[eq + 1..].to_string())),
+                None => (arg.as_str(), None),
+            };
+
+            if is_name(name) {
+                shell.export_var(name, value);
+            } else {
+                report(tr!("export: `{}': not a valid identifier", arg), shell);
+                status = false;
+            }
+        }
+
+        Ok(Status::from(status))
+    }
+}
+
+// `unset [-fv] name...`: remove variables, or functions with `-f`, without
+// flags a name that isn't a variable is taken for a function
+pub struct Unset;
+
+impl BuiltinCommand for Unset {
+    fn run(&self, args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        let mut args = args.into_iter().peekable();
+        let only = args.next_if(|arg| arg == "-f" || arg == "-v");
+
+        for name in args {
+            let is_var = shell.vars.contains_key(&name)
+                || shell.maps.contains_key(&name)
+                || subscript(&name).is_some()
+                || env::var_os(&name).is_some();
+
+            match only.as_deref() {
+                Some("-f") => {
+                    shell.functions.remove(&name);
+                    shell.function_locations.remove(&name);
+                }
+                None if !is_var => {
+                    shell.functions.remove(&name);
+                    shell.function_locations.remove(&name);
+                }
+                _ => shell.unset_var(&name),
+            }
+        }
+
+        Ok(Status::Exited(0))
+    }
+}
+
+// `local name[=value]...`: variables that only last until the function
+// returns
+pub struct Local;
+
+impl BuiltinCommand for Local {
+    fn run(&self, args: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+        if shell.scopes.is_empty() {
+            report(tr!("local: can only be used in a function"), shell);
+            return Ok(Status::Exited(1));
+        }
+
+        let mut status = true;
+
+        for arg in args {
+            let (name, value) = match arg.find('=') {
+                Some(eq) => (&arg[..eq], Some(arg[eq + 1..].to_string())),
+                None => (arg.as_str(), None),
+            };
+
+            if is_name(name) {
+                shell.make_local(name, value);
+            } else {
+                report(tr!("local: `{}': not a valid identifier", arg), shell);
+                status = false;
+            }
+        }
+
+        Ok(Status::from(status))
+    }
+}
@@ -0,0 +1,194 @@
+// The commands a line is made of, as the parser builds them
+
+use std::ffi::OsStr;
+use std::vec::IntoIter;
+
+pub enum Expression<'a> {
+    Cmd(Cmd<'a>),
+    Compound(Box<Compound<'a>>),
+
+    // `( list )` runs in a forked copy of the shell
+    Subshell(Box<Forked<'a>>),
+
+    // `{ list; }` runs in the current shell
+    Group(Box<Expression<'a>>),
+
+    // `if list; then list; [elif list; then list;]... [else list;] fi`
+    If(Box<If<'a>>),
+
+    // `for (( init; cond; step )); do list; done`
+    ArithFor(Box<ArithFor<'a>>),
+
+    // `select name [in word...]; do list; done` runs the list with the word
+    // picked from a menu
+    Select(Box<Select<'a>>),
+
+    // `case word in [(]pattern[|pattern]...) list;; ... esac`
+    Case(Box<Case<'a>>),
+
+    // `! pipeline` succeeds when the pipeline fails and the other way around
+    Not(Box<Expression<'a>>),
+
+    // `a | b | c`, every command runs in a forked copy of the shell and they
+    // make up a single job
+    Pipeline(Box<Pipeline<'a>>),
+
+    // `(( expr ))` succeeds when the expression isn't 0
+    Arith(&'a str),
+
+    // `[[ expr ]]` succeeds when the condition holds
+    Cond(&'a str),
+
+    // `assert [[ expr ]] [message...]` exits when the condition doesn't hold
+    Assert(Box<Assert<'a>>),
+
+    // `list &` runs in a forked copy of the shell without waiting for it
+    Background(Box<Forked<'a>>),
+
+    // `list &!` too, but isn't a job and its output goes to a log file
+    Disowned(Box<Forked<'a>>),
+
+    // `name() { list; }` defines a function, the body is parsed again every
+    // time the function is called
+    Function(Box<Function<'a>>),
+
+    // A command with its input or output going elsewhere while it runs
+    Redirected(Box<Redirected<'a>>),
+
+    // A command of a list starting n lines into the text parsed, for the
+    // location it runs at. Those on the first line aren't wrapped
+    Line(usize, Box<Expression<'a>>),
+}
+
+pub struct Redirected<'a> {
+    pub body: Expression<'a>,
+    pub redirections: Vec<Redirection<'a>>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Redirection<'a> {
+    pub fd: i32,
+    pub kind: Redirect,
+    // A file, or the descriptor to duplicate
+    pub target: &'a str,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Redirect {
+    // `<`
+    Read,
+    // `>`
+    Write,
+    // `>>`
+    Append,
+    // `>&` and `<&`, `-` closes the descriptor instead
+    Dup,
+    // `&>` and `&>>`, standard output and error both
+    Both,
+    BothAppend,
+}
+
+pub struct If<'a> {
+    // Every condition along with the list run when it succeeds
+    pub branches: Vec<(Expression<'a>, Expression<'a>)>,
+    pub otherwise: Option<Expression<'a>>,
+}
+
+pub struct Select<'a> {
+    pub name: &'a str,
+    // The positional parameters when there's no `in`
+    pub words: Option<Vec<&'a str>>,
+    pub body: &'a str,
+    // How many lines into the text parsed the body starts
+    pub body_line: usize,
+}
+
+pub struct ArithFor<'a> {
+    pub init: &'a str,
+    // Loops forever when empty
+    pub cond: &'a str,
+    pub step: &'a str,
+    // Parsed again every time around, like the body of a function
+    pub body: &'a str,
+    pub body_line: usize,
+}
+
+pub struct Case<'a> {
+    pub word: &'a str,
+    // The patterns of every item along with what runs when one matches
+    pub items: Vec<(Vec<&'a str>, Option<Expression<'a>>)>,
+}
+
+pub struct Assert<'a> {
+    // `Expression::Cond` or `Expression::Arith`
+    pub cond: Expression<'a>,
+    pub text: &'a str,
+    pub message: Vec<&'a str>,
+}
+
+pub struct Function<'a> {
+    pub name: &'a str,
+    pub body: &'a str,
+    pub body_line: usize,
+}
+
+pub struct Pipeline<'a> {
+    pub stages: Vec<Expression<'a>>,
+    pub text: &'a str,
+}
+
+pub struct Forked<'a> {
+    pub body: Expression<'a>,
+    // The commandline it came from, for the job table
+    pub text: &'a str,
+}
+
+#[derive(Debug)]
+pub enum Cmd<'a> {
+    // An invokable command consists of a binary and its arguments
+    Invoke(Invoke<'a>),
+
+    // `NAME=value...` sets shell variables
+    Assign(Vec<Assignment<'a>>),
+
+    // `NAME=value... cmd` sets environment variables for `cmd` only
+    WithEnv(Vec<Assignment<'a>>, Box<Cmd<'a>>),
+}
+
+#[derive(Debug)]
+pub struct Assignment<'a> {
+    pub name: &'a str,
+    // `name[key]=value` sets an element of an associative array
+    pub key: Option<&'a str>,
+    // Unexpanded, as with any other word
+    pub value: &'a str,
+}
+
+#[derive(Debug)]
+pub struct Invoke<'a> {
+    pub binary: &'a OsStr,
+    pub args: LineIter<'a>,
+}
+
+pub struct Compound<'a> {
+    pub op: Op,
+    pub left: Expression<'a>,
+    pub right: Expression<'a>,
+}
+
+pub enum Op {
+    Semicolon,
+    And,
+    Or,
+}
+
+#[derive(Debug)]
+pub struct LineIter<'a>(pub(super) IntoIter<&'a str>);
+
+impl<'a> Iterator for LineIter<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
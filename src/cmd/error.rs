@@ -0,0 +1,38 @@
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum Error {
+    EmptyLine,
+    Io(io::Error),
+    NoCmd,
+    UnclosedQuote,
+    UnclosedSubstitution,
+    Syntax(String),
+    TooDeep(usize),
+    // The input ended inside a group, more lines may complete it
+    Incomplete,
+    // A variable that isn't set was expanded with `set -u`
+    Unbound(String),
+    // An expansion failed, its error is reported already
+    Expansion,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::EmptyLine => write!(f, "{}", tr!("empty line")),
+            Error::Io(e) => write!(f, "{}", e),
+            Error::NoCmd => write!(f, "{}", tr!("no command given")),
+            Error::UnclosedQuote => write!(f, "{}", tr!("unclosed quote")),
+            Error::UnclosedSubstitution => write!(f, "{}", tr!("unclosed command substitution")),
+            Error::Syntax(token) => write!(f, "{}", tr!("syntax error near `{}`", token)),
+            Error::TooDeep(max) => {
+                write!(f, "{}", tr!("too deeply nested (more than {} levels)", max))
+            }
+            Error::Incomplete => write!(f, "{}", tr!("unexpected end of input")),
+            Error::Unbound(name) => write!(f, "{}", tr!("{}: unbound variable", name)),
+            Error::Expansion => write!(f, "{}", tr!("bad expansion")),
+        }
+    }
+}
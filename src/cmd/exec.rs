@@ -0,0 +1,1881 @@
+// Running expressions in the shell, the commands of a line one after the other
+
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::mem;
+use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd};
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, ExitStatus};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::{
+    is_incomplete, max_depth, quoted_parts, ArithFor, Assert, Assignment, Case, Cmd, Error,
+    Expression, Forked, Function, If, Invoke, Op, Pipeline, Redirect, Redirected, Redirection,
+    Select,
+};
+use crate::arith::{self, ArithError};
+use crate::builtins;
+use crate::cond::{self, CondError};
+use crate::glob;
+use crate::job::{self, Job};
+use crate::path;
+use crate::shell::{Jump, Location, Shell};
+use crate::signal;
+use crate::status::Status;
+use crate::sys;
+use crate::trap;
+use crate::trust;
+
+impl<'a> Expression<'a> {
+    pub fn run(self, shell: &mut Shell) -> Result<Status, Error> {
+        match self {
+            Expression::Subshell(forked) => {
+                let Forked { body, text } = *forked;
+                let pid = fork_job(shell, true, |shell| run_forked(body, shell))?;
+                let status = shell
+                    .jobs
+                    .wait_foreground(Job::new(pid, vec![pid], text), shell.job_control)
+                    .map_err(Error::Io)?;
+                shell.status = status;
+                check_errexit(status, shell);
+                Ok(status)
+            }
+
+            Expression::Group(expr) => expr.run(shell),
+
+            Expression::ArithFor(arith_for) => {
+                run_debug_hook(shell);
+                run_arith_for(*arith_for, shell)
+            }
+
+            Expression::Select(select) => {
+                run_debug_hook(shell);
+                run_select(*select, shell)
+            }
+
+            Expression::Case(case) => {
+                run_debug_hook(shell);
+                let Case { word, items } = *case;
+                let word = expand_string(word, shell)?;
+
+                for (patterns, body) in items {
+                    let mut matched = false;
+                    for pattern in patterns {
+                        if glob::matches(&case_pattern(pattern, shell)?, &word) {
+                            matched = true;
+                            break;
+                        }
+                    }
+
+                    match (matched, body) {
+                        (true, Some(body)) => return body.run(shell),
+                        (true, None) => break,
+                        (false, _) => {}
+                    }
+                }
+
+                shell.status = Status::Exited(0);
+                Ok(shell.status)
+            }
+
+            Expression::If(conditional) => {
+                let If {
+                    branches,
+                    otherwise,
+                } = *conditional;
+
+                for (cond, body) in branches {
+                    let status = run_condition(cond, shell)?;
+
+                    if leaving(shell) {
+                        return Ok(status);
+                    }
+                    if status.success() {
+                        return body.run(shell);
+                    }
+                }
+
+                match otherwise {
+                    Some(body) => body.run(shell),
+                    None => {
+                        shell.status = Status::Exited(0);
+                        Ok(shell.status)
+                    }
+                }
+            }
+
+            // Doesn't make `set -e` exit either way
+            Expression::Not(expr) => {
+                shell.conditions += 1;
+                let status = expr.run(shell);
+                shell.conditions -= 1;
+
+                shell.status = match status?.success() {
+                    true => Status::Exited(1),
+                    false => Status::Exited(0),
+                };
+                Ok(shell.status)
+            }
+
+            Expression::Pipeline(pipeline) => {
+                let status = run_pipeline(*pipeline, shell)?;
+                shell.status = status;
+                check_errexit(status, shell);
+                Ok(status)
+            }
+
+            Expression::Arith(expr) => {
+                run_debug_hook(shell);
+
+                if shell.options.xtrace {
+                    trace(&format!("(( {} ))", expr.trim()), shell);
+                }
+                let status = run_arith(expr, shell);
+                shell.status = status;
+                check_errexit(status, shell);
+                Ok(status)
+            }
+
+            Expression::Cond(expr) => {
+                run_debug_hook(shell);
+
+                if shell.options.xtrace {
+                    trace(&format!("[[ {} ]]", expr.trim()), shell);
+                }
+                let status = run_cond(expr, shell);
+                shell.status = status;
+                check_errexit(status, shell);
+                Ok(status)
+            }
+
+            Expression::Assert(assert) => {
+                shell.status = run_assert(*assert, shell)?;
+                Ok(shell.status)
+            }
+
+            Expression::Background(forked) => {
+                let Forked { body, text } = *forked;
+                let pid = fork_job(shell, false, |shell| run_forked(body, shell))?;
+                let id = shell.jobs.insert(Job::new(pid, vec![pid], text));
+                shell.last_background = Some(pid);
+
+                if shell.job_control {
+                    eprintln!("[{}] {}", id, pid);
+                }
+
+                shell.status = Status::Exited(0);
+                Ok(shell.status)
+            }
+
+            Expression::Redirected(redirected) => {
+                let Redirected { body, redirections } = *redirected;
+
+                let saved = match redirect(&redirections, shell)? {
+                    Some(saved) => saved,
+                    None => {
+                        shell.status = Status::Exited(1);
+                        check_errexit(shell.status, shell);
+                        return Ok(shell.status);
+                    }
+                };
+
+                let status = body.run(shell);
+                restore(saved);
+                status
+            }
+
+            Expression::Disowned(forked) => {
+                shell.status = run_disowned(*forked, shell)?;
+                Ok(shell.status)
+            }
+
+            Expression::Cmd(cmd) => {
+                run_debug_hook(shell);
+                let status = match cmd.run(shell) {
+                    // Reported while expanding, the command doesn't run
+                    Err(Error::Expansion) => Status::Exited(1),
+                    status => status?,
+                };
+                shell.status = status;
+                check_errexit(status, shell);
+                Ok(status)
+            }
+
+            Expression::Function(function) => {
+                let Function {
+                    name,
+                    body,
+                    body_line,
+                } = *function;
+                shell.functions.insert(name.to_string(), body.to_string());
+
+                // Its commands report the lines of the file it's defined in
+                match &shell.location {
+                    Some(location) => {
+                        if let Some(coverage) = &mut shell.coverage {
+                            coverage.hit(location);
+                        }
+                        let location = Location {
+                            file: location.file.clone(),
+                            line: shell.text_line + body_line,
+                        };
+                        shell.function_locations.insert(name.to_string(), location);
+                    }
+                    None => {
+                        shell.function_locations.remove(name);
+                    }
+                }
+
+                shell.status = Status::Exited(0);
+                Ok(shell.status)
+            }
+
+            Expression::Line(line, expr) => {
+                if let Some(location) = &mut shell.location {
+                    location.line = shell.text_line + line;
+                }
+                expr.run(shell)
+            }
+
+            Expression::Compound(compound) => match compound.op {
+                Op::Semicolon => {
+                    let status = compound.left.run(shell)?;
+
+                    if leaving(shell) {
+                        return Ok(status);
+                    }
+                    compound.right.run(shell)
+                }
+
+                Op::And => {
+                    let status = run_condition(compound.left, shell)?;
+
+                    if status.success() && !leaving(shell) {
+                        compound.right.run(shell)
+                    } else {
+                        Ok(status)
+                    }
+                }
+
+                Op::Or => {
+                    let status = run_condition(compound.left, shell)?;
+
+                    if !status.success() && !leaving(shell) {
+                        compound.right.run(shell)
+                    } else {
+                        Ok(status)
+                    }
+                }
+            },
+        }
+    }
+}
+
+impl<'a> Cmd<'a> {
+    pub fn run(self, shell: &mut Shell) -> Result<Status, Error> {
+        match self {
+            Cmd::Assign(assignments) => {
+                for Assignment { name, key, value } in assignments {
+                    let value = expand_string(value, shell)?;
+
+                    match key {
+                        Some(key) => {
+                            let key = expand_string(key, shell)?;
+                            if shell.options.xtrace {
+                                let text = format!("{}[{}]={}", name, key, quote_if_needed(&value));
+                                trace(&text, shell);
+                            }
+                            shell.set_element(name, key, value);
+                        }
+                        None => {
+                            if shell.options.xtrace {
+                                trace(&format!("{}={}", name, quote_if_needed(&value)), shell);
+                            }
+                            shell.set_var(name, value);
+                        }
+                    }
+                }
+                Ok(Status::Exited(0))
+            }
+
+            Cmd::WithEnv(assignments, cmd) => {
+                let mut saved = vec![];
+
+                // What was exported is taken back also when a value fails to
+                // expand
+                let status = export(assignments, &mut saved, shell).and_then(|_| cmd.run(shell));
+
+                for (name, value) in saved.into_iter().rev() {
+                    match value {
+                        Some(value) => env::set_var(name, value),
+                        None => env::remove_var(name),
+                    }
+                }
+
+                status
+            }
+
+            Cmd::Invoke(Invoke { binary, args }) => {
+                let mut words = expand(binary.to_str().into_iter().chain(args), shell)?;
+
+                if words.is_empty() {
+                    return Ok(Status::Exited(0));
+                }
+
+                if shell.options.xtrace {
+                    trace(&quote_words(&words), shell);
+                }
+
+                // With `shopt -s autocd` a directory that isn't also a command
+                // is changed to
+                if shell.shopts.autocd
+                    && words.len() == 1
+                    && Path::new(&words[0]).is_dir()
+                    && path::find_all_executables(&words[0]).is_empty()
+                    && !shell.functions.contains_key(&words[0])
+                {
+                    let changed = builtins::change_dir("cd", Path::new(&words[0]), shell);
+                    return Ok(Status::from(changed));
+                }
+
+                if shell.functions.contains_key(&words[0]) {
+                    let name = words.remove(0);
+                    return Ok(run_function(&name, words, shell).unwrap_or(Status::Exited(127)));
+                }
+
+                if let Some(builtin) = shell.builtins.get(&words[0]) {
+                    let name = words.remove(0);
+                    return Ok(builtins::run(builtin, &name, words, shell));
+                }
+
+                spawn_external(words, false, shell)
+            }
+        }
+    }
+}
+
+// Put the assignments of `NAME=value cmd` in the environment, with the values
+// they replace in `saved`
+fn export<'a>(
+    assignments: Vec<Assignment<'a>>,
+    saved: &mut Vec<(&'a str, Option<OsString>)>,
+    shell: &mut Shell,
+) -> Result<(), Error> {
+    for Assignment { name, key, value } in assignments {
+        let value = expand_string(value, shell)?;
+
+        // Arrays aren't in the environment, they are set for good
+        if let Some(key) = key {
+            let key = expand_string(key, shell)?;
+            shell.set_element(name, key, value);
+            continue;
+        }
+        saved.push((name, env::var_os(name)));
+        env::set_var(name, value);
+    }
+
+    Ok(())
+}
+
+// Descriptors saved while a redirection is in place, -1 for those that
+// weren't open
+type Saved = Vec<(i32, i32)>;
+
+// Point the shell's own descriptors where the redirections say, so builtins
+// and the commands it spawns alike are redirected. Restoring is up to the
+// caller, unless one fails and the command mustn't run, then it's `None`
+fn redirect(redirections: &[Redirection], shell: &mut Shell) -> Result<Option<Saved>, Error> {
+    let mut saved = vec![];
+
+    for redirection in redirections {
+        // Those already in place are undone when a later target fails to expand
+        let target = match expand_string(redirection.target, shell) {
+            Ok(target) => target,
+            Err(e) => {
+                restore(saved);
+                return Err(e);
+            }
+        };
+
+        let (source, fds) = match redirection.kind {
+            Redirect::Dup if target == "-" => (None, vec![redirection.fd]),
+
+            Redirect::Dup => match target.parse::<i32>() {
+                Ok(fd) if unsafe { sys::fcntl(fd, sys::F_GETFD, 0) } != -1 => {
+                    (Some(fd), vec![redirection.fd])
+                }
+                _ => {
+                    report(format!("{}: {}", target, tr!("bad file descriptor")), shell);
+                    restore(saved);
+                    return Ok(None);
+                }
+            },
+
+            kind => {
+                let mut options = fs::OpenOptions::new();
+                match kind {
+                    Redirect::Read => options.read(true),
+                    Redirect::Append | Redirect::BothAppend => options.append(true).create(true),
+                    _ => options.write(true).truncate(true).create(true),
+                };
+
+                let file = match options.open(&target) {
+                    Ok(file) => file,
+                    Err(e) => {
+                        report(format!("{}: {}", target, e), shell);
+                        restore(saved);
+                        return Ok(None);
+                    }
+                };
+
+                let fds = match kind {
+                    Redirect::Both | Redirect::BothAppend => vec![1, 2],
+                    _ => vec![redirection.fd],
+                };
+
+                // Closed once duplicated where it's going
+                (Some(file.into_raw_fd()), fds)
+            }
+        };
+
+        let _ = io::stdout().flush();
+
+        for &fd in &fds {
+            let copy = unsafe { sys::fcntl(fd, sys::F_DUPFD_CLOEXEC, 10) };
+            saved.push((fd, copy));
+
+            unsafe {
+                match source {
+                    Some(source) => sys::dup2(source, fd),
+                    None => sys::close(fd),
+                };
+            }
+        }
+
+        // The file opened, a descriptor duplicated stays open
+        if redirection.kind != Redirect::Dup {
+            if let Some(source) = source {
+                unsafe { sys::close(source) };
+            }
+        }
+    }
+
+    Ok(Some(saved))
+}
+
+fn restore(saved: Saved) {
+    let _ = io::stdout().flush();
+
+    for (fd, copy) in saved.into_iter().rev() {
+        unsafe {
+            if copy == -1 {
+                sys::close(fd);
+            } else {
+                sys::dup2(copy, fd);
+                sys::close(copy);
+            }
+        }
+    }
+}
+
+// Run a command found on PATH, or on the default one instead
+pub fn spawn_external(
+    mut words: Vec<String>,
+    default_path: bool,
+    shell: &mut Shell,
+) -> Result<Status, Error> {
+    let cmdline = words.join(" ");
+    let name = words.remove(0);
+    let found = match name.contains('/') {
+        true => None,
+        false if default_path => path::executables_in(&name, OsStr::new(path::DEFAULT_PATH))
+            .into_iter()
+            .next(),
+        false => shell.hash.find(&name),
+    };
+
+    let mut command = match found {
+        Some(path) => {
+            let mut command = Command::new(path);
+            command.arg0(&name);
+            command
+        }
+        None => Command::new(&name),
+    };
+    command.args(words);
+    spawn_and_wait(command, &cmdline, shell)
+}
+
+// Expand the words of a command, with `set -u` expanding a variable that
+// isn't set fails the command before it runs
+fn expand<'a>(
+    words: impl Iterator<Item = &'a str>,
+    shell: &mut Shell,
+) -> Result<Vec<String>, Error> {
+    shell.unbound = None;
+    shell.failed_expansion = false;
+    let words = shell.expand(words);
+
+    match shell.unbound.take() {
+        Some(name) => Err(Error::Unbound(name)),
+        None if mem::take(&mut shell.failed_expansion) => Err(Error::Expansion),
+        None => Ok(words),
+    }
+}
+
+fn expand_string(word: &str, shell: &mut Shell) -> Result<String, Error> {
+    shell.unbound = None;
+    shell.failed_expansion = false;
+    let word = shell.expand_string(word);
+
+    match shell.unbound.take() {
+        Some(name) => Err(Error::Unbound(name)),
+        None if mem::take(&mut shell.failed_expansion) => Err(Error::Expansion),
+        None => Ok(word),
+    }
+}
+
+// `source file [args]...`: evaluate every line of the file in the current
+// shell, with `$1..$n` set to `args` while the file runs if any were given
+pub fn run_source(path: &Path, params: Vec<String>, shell: &mut Shell) -> Result<Status, Error> {
+    let path = find_source(path);
+    let contents = fs::read_to_string(&path).map_err(Error::Io)?;
+    let file = path.display().to_string();
+
+    // Interactive shells ask before sourcing files someone else could have
+    // put in place
+    if shell.job_control && !trust::allow(&path, &contents) {
+        report(tr!("source: {}: not trusted, not sourced", file), shell);
+        return Ok(Status::Exited(1));
+    }
+
+    let run_lines = |shell: &mut Shell| {
+        let mut status = Status::Exited(0);
+        shell.sourcing += 1;
+        let outer = shell.location.take();
+        let lines: Vec<&str> = contents.lines().collect();
+        let mut pending = String::new();
+        let mut first = 0;
+
+        for (i, line) in lines.iter().enumerate() {
+            if pending.is_empty() {
+                first = i;
+            }
+            pending.push_str(line);
+            pending.push('\n');
+
+            if i + 1 < lines.len() && is_incomplete(&pending, shell) {
+                continue;
+            }
+
+            shell.location = Some(Location {
+                file: file.clone(),
+                line: first + 1,
+            });
+
+            if run_line(&pending, shell).is_some() {
+                status = shell.status;
+            }
+            pending.clear();
+
+            if shell.returning {
+                shell.returning = false;
+                break;
+            }
+            if shell.jump.is_some() || shell.interrupted {
+                break;
+            }
+        }
+
+        shell.sourcing -= 1;
+        shell.location = outer;
+        status
+    };
+
+    if params.is_empty() {
+        Ok(run_lines(shell))
+    } else {
+        Ok(shell.with_positional(params, run_lines))
+    }
+}
+
+// Run the condition of an `if`, `&&` or `||`, which doesn't make `set -e`
+// exit. One that can't run is false, the `else` branch or the right of `||`
+// still run. A variable that isn't set with `set -u` aborts the command
+fn run_condition(cond: Expression, shell: &mut Shell) -> Result<Status, Error> {
+    shell.conditions += 1;
+    let status = cond.run(shell);
+    shell.conditions -= 1;
+
+    match status {
+        Err(Error::Unbound(name)) => Err(Error::Unbound(name)),
+        Err(e) => {
+            // Expansions report their own errors
+            if !matches!(e, Error::Expansion) {
+                report(&e, shell);
+            }
+            shell.status = Status::Exited(1);
+            Ok(shell.status)
+        }
+        status => status,
+    }
+}
+
+// Evaluate an arithmetic expression once its parameters are expanded,
+// succeeding when the result isn't 0
+fn run_arith(expr: &str, shell: &mut Shell) -> Status {
+    match eval_arith(expr, shell) {
+        Some(n) => Status::Exited((n == 0) as i32),
+        None => Status::Exited(1),
+    }
+}
+
+// The value of the expression, `None` once the error is reported
+fn eval_arith(expr: &str, shell: &mut Shell) -> Option<i64> {
+    let expanded = match expand_string(expr, shell) {
+        Ok(expanded) => expanded,
+        Err(Error::Expansion) => return None,
+        Err(e) => {
+            report(&e, shell);
+            check_nounset(shell);
+            return None;
+        }
+    };
+
+    match arith::eval(&expanded, shell) {
+        Ok(n) => Some(n),
+        Err(e) => {
+            report(format!("{}: {}", expanded.trim(), e), shell);
+
+            if let ArithError::Unbound(_) = e {
+                check_nounset(shell);
+            }
+            None
+        }
+    }
+}
+
+// Run `init`, then the body for as long as `cond` isn't 0 with `step` after
+// every time. The status is the body's last, or 1 if an expression fails
+fn run_arith_for(arith_for: ArithFor, shell: &mut Shell) -> Result<Status, Error> {
+    let ArithFor {
+        init,
+        cond,
+        step,
+        body,
+        body_line,
+    } = arith_for;
+    let mut status = Status::Exited(0);
+
+    // An empty expression does nothing, and an empty condition holds
+    let eval = |expr: &str, shell: &mut Shell| match expr.trim().is_empty() {
+        true => Some(1),
+        false => eval_arith(expr, shell),
+    };
+
+    if eval(init, shell).is_none() {
+        status = Status::Exited(1);
+    } else {
+        loop {
+            match eval(cond, shell) {
+                Some(0) => break,
+                Some(_) => {}
+                None => {
+                    status = Status::Exited(1);
+                    break;
+                }
+            }
+
+            status = run_loop_body(body, body_line, shell)?;
+            if leaves_loop(shell) {
+                break;
+            }
+
+            if eval(step, shell).is_none() {
+                status = Status::Exited(1);
+                break;
+            }
+        }
+    }
+
+    shell.status = status;
+    Ok(status)
+}
+
+fn run_loop_body(body: &str, body_line: usize, shell: &mut Shell) -> Result<Status, Error> {
+    shell.loops += 1;
+    let result = with_text_line(shell.text_line + body_line, shell, |shell| {
+        Expression::parse(body, max_depth()).and_then(|body| body.run(shell))
+    });
+    shell.loops -= 1;
+    result
+}
+
+// Wait for a line typed at the terminal, false when Ctrl-C interrupts the
+// wait. Reading it would go on past the interrupt
+fn wait_for_input(shell: &mut Shell) -> bool {
+    if !sys::is_terminal(0) {
+        return true;
+    }
+
+    let mut fd = sys::PollFd {
+        fd: 0,
+        events: sys::POLLIN,
+        revents: 0,
+    };
+    while unsafe { sys::poll(&mut fd, 1, -1) } < 0 {
+        if io::Error::last_os_error().kind() != io::ErrorKind::Interrupted {
+            break;
+        }
+        if leaving(shell) {
+            return false;
+        }
+    }
+    true
+}
+
+// Whether the loop is done after its body ran, with `break`, `return` or a
+// `continue` for an outer loop. The jump goes on to the outer loop while it
+// has levels left
+fn leaves_loop(shell: &mut Shell) -> bool {
+    match shell.jump.take() {
+        Some(Jump::Break(1)) => true,
+        Some(Jump::Break(n)) => {
+            shell.jump = Some(Jump::Break(n - 1));
+            true
+        }
+        Some(Jump::Continue(n)) if n > 1 => {
+            shell.jump = Some(Jump::Continue(n - 1));
+            true
+        }
+        _ => leaving(shell),
+    }
+}
+
+// Whether the commands left are skipped, taking a Ctrl-C that arrived while
+// builtins ran so the whole line stops
+fn leaving(shell: &mut Shell) -> bool {
+    if signal::is_pending(signal::SIGINT) {
+        run_pending_traps(shell);
+    }
+    shell.leaving()
+}
+
+// Show the words numbered and read which one to run the body with from
+// stdin after `PS3`, `#? ` by default, until it ends or the body breaks out.
+// The line read is in `REPLY` and the word picked in `name`, empty when the
+// line isn't one of the numbers. An empty line shows the words again
+fn run_select(select: Select, shell: &mut Shell) -> Result<Status, Error> {
+    let Select {
+        name,
+        words,
+        body,
+        body_line,
+    } = select;
+    let words = match words {
+        Some(words) => expand(words.into_iter(), shell)?,
+        None => shell.args[1..].to_vec(),
+    };
+    let mut status = Status::Exited(0);
+    let mut menu = true;
+
+    if words.is_empty() {
+        shell.status = status;
+        return Ok(status);
+    }
+
+    loop {
+        let mut stderr = io::stderr();
+        if menu {
+            for (i, word) in words.iter().enumerate() {
+                writeln!(stderr, "{}) {}", i + 1, word).map_err(Error::Io)?;
+            }
+        }
+
+        let ps3 = shell.lookup("PS3").unwrap_or_else(|| String::from("#? "));
+        write!(stderr, "{}", ps3).map_err(Error::Io)?;
+
+        if !wait_for_input(shell) {
+            writeln!(stderr).map_err(Error::Io)?;
+            status = Status::Exited(128 + signal::SIGINT);
+            break;
+        }
+
+        let mut reply = String::new();
+        if io::stdin().read_line(&mut reply).map_err(Error::Io)? == 0 {
+            writeln!(stderr).map_err(Error::Io)?;
+            status = Status::Exited(1);
+            break;
+        }
+
+        let reply = reply.trim_end_matches('\n');
+        menu = reply.trim().is_empty();
+        if menu {
+            continue;
+        }
+
+        let picked = match reply.trim().parse::<usize>() {
+            Ok(n) if n >= 1 && n <= words.len() => words[n - 1].clone(),
+            _ => String::new(),
+        };
+        shell.set_var("REPLY", reply.to_string());
+        shell.set_var(name, picked);
+
+        status = run_loop_body(body, body_line, shell)?;
+        if leaves_loop(shell) {
+            break;
+        }
+    }
+
+    shell.status = status;
+    Ok(status)
+}
+
+// The pattern a word of `case` stands for once expanded, what's quoted in it
+// matches itself only
+fn case_pattern(word: &str, shell: &mut Shell) -> Result<String, Error> {
+    let mut pattern = String::new();
+
+    for (part, quoted) in quoted_parts(word)? {
+        let expanded = expand_string(part, shell)?;
+        match quoted {
+            true => pattern.push_str(&glob::escape(&expanded)),
+            false => pattern.push_str(&expanded),
+        }
+    }
+    Ok(pattern)
+}
+
+// Evaluate the condition of `[[ ]]`, failing with 2 when it's malformed
+fn run_cond(expr: &str, shell: &mut Shell) -> Status {
+    match cond::eval(expr, shell) {
+        Ok(holds) => Status::Exited(!holds as i32),
+        Err(e) => {
+            report(format!("[[{}]]: {}", expr, e), shell);
+
+            if let CondError::Unbound(_) | CondError::Arith(ArithError::Unbound(_)) = e {
+                check_nounset(shell);
+            }
+            Status::Exited(2)
+        }
+    }
+}
+
+// Run the condition of `assert`, and when it fails report where along with
+// the message and exit with `ASSERT_STATUS`, 1 by default. Typed in at the
+// prompt the shell stays, the assertion just fails
+fn run_assert(assert: Assert, shell: &mut Shell) -> Result<Status, Error> {
+    let Assert {
+        cond,
+        text,
+        message,
+    } = assert;
+
+    shell.conditions += 1;
+    let status = cond.run(shell);
+    shell.conditions -= 1;
+    if status?.success() {
+        return Ok(Status::Exited(0));
+    }
+
+    let message = match message.is_empty() {
+        true => text.to_string(),
+        false => expand(message.into_iter(), shell)?.join(" "),
+    };
+    let status = shell
+        .lookup("ASSERT_STATUS")
+        .and_then(|status| status.parse().ok())
+        .unwrap_or(1);
+
+    report(tr!("assertion failed: {}", message), shell);
+
+    if shell.location.is_none() && shell.job_control {
+        return Ok(Status::Exited(status));
+    }
+    exit(status, shell)
+}
+
+// Names without a slash are looked up on PATH first, then in the current
+// directory
+pub fn find_source(path: &Path) -> PathBuf {
+    if path.components().count() == 1 {
+        if let Some(paths) = env::var_os("PATH") {
+            for dir in env::split_paths(&paths) {
+                let candidate = dir.join(path);
+                if candidate.is_file() {
+                    return candidate;
+                }
+            }
+        }
+    }
+
+    path.to_path_buf()
+}
+
+pub fn spawn_and_wait(
+    mut command: Command,
+    cmdline: &str,
+    shell: &mut Shell,
+) -> Result<Status, Error> {
+    if shell.job_control {
+        unsafe {
+            command.pre_exec(|| {
+                job::enter_job(0, true);
+                Ok(())
+            });
+        }
+    }
+
+    match command.spawn() {
+        Ok(child) => {
+            let pid = child.id() as i32;
+
+            if shell.job_control {
+                unsafe { sys::setpgid(pid, pid) };
+            }
+
+            let status = shell
+                .jobs
+                .wait_foreground(Job::new(pid, vec![pid], cmdline), shell.job_control)
+                .map_err(Error::Io)?;
+
+            // Interrupts and broken pipes are expected, don't report those
+            if let Status::Signaled { signum, .. } = status {
+                if signum != signal::SIGINT && signum != signal::SIGPIPE {
+                    eprintln!("{}  {}", status, cmdline);
+                }
+            }
+
+            Ok(status)
+        }
+        Err(_) => {
+            let program = command.get_program().to_string_lossy();
+            report(tr!("{}: command not found", program), shell);
+            Ok(Status::Exited(127))
+        }
+    }
+}
+
+// Parse and run a line of input reporting any error, returns `None` for lines
+// without commands
+pub fn run_line(line: &str, shell: &mut Shell) -> Option<bool> {
+    let line = shell.expand_aliases(line);
+
+    // Its lines count from the one the shell is at
+    let start = shell.location.as_ref().map_or(shell.text_line, |l| l.line);
+    with_text_line(start, shell, |shell| run_text(&line, shell))
+}
+
+// Run `f` for a text starting on `line` of the file being run, what's at the
+// lines the shell was at is put back after
+fn with_text_line<T>(line: usize, shell: &mut Shell, f: impl FnOnce(&mut Shell) -> T) -> T {
+    let outer = (shell.text_line, shell.location.as_ref().map(|l| l.line));
+    shell.text_line = line;
+    if let Some(location) = &mut shell.location {
+        location.line = line;
+    }
+
+    let result = f(shell);
+
+    shell.text_line = outer.0;
+    if let (Some(location), Some(line)) = (&mut shell.location, outer.1) {
+        location.line = line;
+    }
+    result
+}
+
+fn run_text(line: &str, shell: &mut Shell) -> Option<bool> {
+    let status = match Expression::parse(line, max_depth()) {
+        Ok(expr) => match expr.run(shell) {
+            Ok(status) => Some(status.success()),
+            Err(e) => {
+                // Expansions report their own errors
+                if !matches!(e, Error::Expansion) {
+                    report(&e, shell);
+                }
+                shell.status = Status::Exited(1);
+
+                if let Error::Unbound(_) = e {
+                    check_nounset(shell);
+                }
+                check_errexit(shell.status, shell);
+                Some(false)
+            }
+        },
+
+        Err(Error::EmptyLine) => None,
+
+        Err(e) => {
+            report(&e, shell);
+            shell.status = Status::Exited(2);
+            check_errexit(shell.status, shell);
+            Some(false)
+        }
+    };
+
+    run_pending_traps(shell);
+    status
+}
+
+// Errors of the commands run tell where they come from when it's a file being
+// sourced, the innermost one
+pub fn report(message: impl fmt::Display, shell: &Shell) {
+    match &shell.location {
+        Some(Location { file, line }) => eprintln!("rush: {}:{}: {}", file, line, message),
+        None => eprintln!("rush: {}", message),
+    }
+}
+
+// A variable that isn't set failing a command with `set -u` exits the shell,
+// unless it's interactive
+fn check_nounset(shell: &mut Shell) {
+    if !shell.job_control {
+        exit(1, shell);
+    }
+}
+
+// With `set -e` a command failing outside of a condition exits the shell
+fn check_errexit(status: Status, shell: &mut Shell) {
+    if shell.options.errexit && shell.conditions == 0 && !shell.returning && !status.success() {
+        exit(status.code(), shell);
+    }
+}
+
+// Fork the shell, the child runs `f` and exits with the status it returns
+pub fn fork(shell: &mut Shell, f: impl FnOnce(&mut Shell) -> Status) -> Result<i32, Error> {
+    io::stdout().flush().map_err(Error::Io)?;
+
+    match unsafe { sys::fork() } {
+        -1 => Err(Error::Io(io::Error::last_os_error())),
+
+        0 => {
+            // A subshell isn't interactive, Ctrl-C kills it unless trapped
+            if shell.job_control {
+                signal::release(signal::SIGINT);
+                if shell.traps.get(signal::SIGINT).is_none() {
+                    signal::reset(signal::SIGINT);
+                }
+            }
+            shell.job_control = false;
+            let status = f(shell);
+            let _ = io::stdout().flush();
+            unsafe { sys::_exit(status.code()) }
+        }
+
+        pid => Ok(pid),
+    }
+}
+
+// Fork a job, with job control it gets a process group of its own
+fn fork_job(
+    shell: &mut Shell,
+    foreground: bool,
+    f: impl FnOnce(&mut Shell) -> Status,
+) -> Result<i32, Error> {
+    fork_in_group(shell, 0, foreground, f)
+}
+
+// Fork a process of the job with process group `pgid`, 0 for the first one
+// which starts the group
+fn fork_in_group(
+    shell: &mut Shell,
+    pgid: i32,
+    foreground: bool,
+    f: impl FnOnce(&mut Shell) -> Status,
+) -> Result<i32, Error> {
+    let job_control = shell.job_control;
+
+    let pid = fork(shell, |shell| {
+        if job_control {
+            job::enter_job(pgid, foreground);
+        }
+        f(shell)
+    })?;
+
+    // Also done by the child, whichever runs first
+    if job_control {
+        unsafe { sys::setpgid(pid, if pgid == 0 { pid } else { pgid }) };
+    }
+
+    Ok(pid)
+}
+
+// Run in the background with the output going to a log file named after the
+// child's pid, and left out of the job table so the shell never waits for it
+// or hangs it up
+fn run_disowned(forked: Forked, shell: &mut Shell) -> Result<Status, Error> {
+    let Forked { body, text } = forked;
+
+    let dir = match job::log_dir() {
+        Some(dir) => dir,
+        None => {
+            report(tr!("&!: no directory for job logs, HOME isn't set"), shell);
+            return Ok(Status::Exited(1));
+        }
+    };
+    let started = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_secs());
+
+    let child_dir = dir.clone();
+    let pid = fork_job(shell, false, |shell| {
+        let path = job::log_path(&child_dir, started, unsafe { sys::getpid() });
+
+        let log = match job::create_log(&path, text) {
+            Ok(log) => log,
+            Err(e) => {
+                report(format!("{}: {}", path.display(), e), shell);
+                return Status::Exited(1);
+            }
+        };
+
+        unsafe {
+            if let Ok(null) = fs::File::open("/dev/null") {
+                sys::dup2(null.as_raw_fd(), 0);
+            }
+            sys::dup2(log.as_raw_fd(), 1);
+            sys::dup2(log.as_raw_fd(), 2);
+        }
+        run_forked(body, shell)
+    })?;
+    shell.last_background = Some(pid);
+
+    if shell.job_control {
+        let path = job::log_path(&dir, started, pid);
+        eprintln!("{}", tr!("{} disowned, output in {}", pid, path.display()));
+    }
+
+    Ok(Status::Exited(0))
+}
+
+// Every command of the pipeline gets its input from the one before through a
+// pipe. They share a process group, so Ctrl-C interrupts all of them. The
+// status is the last command's, or with `set -o pipefail` the last one that
+// failed
+fn run_pipeline(pipeline: Pipeline, shell: &mut Shell) -> Result<Status, Error> {
+    let Pipeline { stages, text } = pipeline;
+    let last = stages.len() - 1;
+    let mut pids = vec![];
+    let mut input = None;
+    let mut result = Ok(());
+
+    for (i, stage) in stages.into_iter().enumerate() {
+        let mut fds = [0; 2];
+
+        if i < last && unsafe { sys::pipe(fds.as_mut_ptr()) } != 0 {
+            result = Err(Error::Io(io::Error::last_os_error()));
+            break;
+        }
+
+        let [read_fd, write_fd] = fds;
+        let output = (i < last).then_some(write_fd);
+        let pgid = pids.first().copied().unwrap_or(0);
+
+        let pid = fork_in_group(shell, pgid, true, |shell| {
+            // Writing to a command that's done ends this one quietly
+            signal::reset(signal::SIGPIPE);
+
+            unsafe {
+                if let Some(input) = input {
+                    sys::dup2(input, 0);
+                    sys::close(input);
+                }
+
+                if let Some(output) = output {
+                    sys::dup2(output, 1);
+                    sys::close(output);
+                    sys::close(read_fd);
+                }
+            }
+
+            run_forked(stage, shell)
+        });
+
+        unsafe {
+            if let Some(input) = input {
+                sys::close(input);
+            }
+
+            if let Some(output) = output {
+                sys::close(output);
+            }
+        }
+
+        match pid {
+            Ok(pid) => pids.push(pid),
+            Err(e) => {
+                result = Err(e);
+                if output.is_some() {
+                    unsafe { sys::close(read_fd) };
+                }
+                break;
+            }
+        }
+
+        input = output.map(|_| read_fd);
+    }
+
+    if pids.is_empty() {
+        return result.map(|_| Status::Exited(1));
+    }
+
+    let mut job = Job::new(pids[0], pids, text);
+    job.pipefail = shell.options.pipefail;
+    let status = shell
+        .jobs
+        .wait_foreground(job, shell.job_control)
+        .map_err(Error::Io)?;
+
+    result?;
+
+    if let Status::Signaled { signum, .. } = status {
+        if signum != signal::SIGINT && signum != signal::SIGPIPE {
+            eprintln!("{}  {}", status, text);
+        }
+    }
+
+    Ok(status)
+}
+
+// `set -x` output after `PS4`, `+ ` by default. What external commands run
+// with is shown expanded but builtins are shown as typed
+fn trace(text: &str, shell: &mut Shell) {
+    // What `PS4` runs to expand isn't traced itself
+    shell.options.xtrace = false;
+    let ps4 = match shell.lookup("PS4") {
+        Some(ps4) => shell.expand_string(&ps4),
+        None => String::from("+ "),
+    };
+    shell.options.xtrace = true;
+
+    eprintln!("{}{}", ps4, text);
+}
+
+fn quote_words(words: &[String]) -> String {
+    let words: Vec<String> = words.iter().map(|word| quote_if_needed(word)).collect();
+    words.join(" ")
+}
+
+// Run the body of a function with `$1..$n` set to `args`, its status is the
+// status of the last command it ran
+// Run the function `name` with `args` as the positional parameters, `None`
+// when there's no such function
+pub fn run_function(name: &str, args: Vec<String>, shell: &mut Shell) -> Option<Status> {
+    let body = shell.functions.get(name).cloned()?;
+
+    // Its commands are at the lines it was defined on
+    let defined = shell.function_locations.get(name).cloned();
+    let outer = std::mem::replace(&mut shell.location, defined);
+
+    if let Some(profiler) = &mut shell.profiler {
+        profiler.enter(name);
+    }
+    shell.with_scope(|shell| shell.with_positional(args, |shell| run_line(&body, shell)));
+    if let Some(profiler) = &mut shell.profiler {
+        profiler.leave();
+    }
+
+    shell.returning = false;
+    shell.location = outer;
+    Some(shell.status)
+}
+
+fn run_forked(expr: Expression, shell: &mut Shell) -> Status {
+    match expr.run(shell) {
+        Ok(status) => status,
+        Err(e) => {
+            eprintln!("rush: {}", e);
+            Status::Exited(1)
+        }
+    }
+}
+
+pub fn wait_pid(pid: i32) -> Result<Status, Error> {
+    loop {
+        let mut raw = 0;
+
+        if unsafe { sys::waitpid(pid, &mut raw, 0) } != -1 {
+            return Ok(Status::from(ExitStatus::from_raw(raw)));
+        }
+
+        let e = io::Error::last_os_error();
+        if e.kind() != io::ErrorKind::Interrupted {
+            return Err(Error::Io(e));
+        }
+    }
+}
+
+// Run `body` in a subshell returning what it writes to stdout, without
+// trailing newlines
+pub fn substitute(body: &str, shell: &mut Shell) -> String {
+    let mut fds = [0; 2];
+
+    if unsafe { sys::pipe(fds.as_mut_ptr()) } != 0 {
+        eprintln!("rush: {}", sys::last_error());
+        return String::new();
+    }
+
+    let [read_fd, write_fd] = fds;
+
+    let child = fork(shell, |shell| {
+        unsafe {
+            sys::dup2(write_fd, 1);
+            sys::close(read_fd);
+            sys::close(write_fd);
+        }
+        run_line(body, shell);
+        shell.status
+    });
+
+    unsafe {
+        sys::close(write_fd);
+    }
+
+    let mut output = vec![];
+    let mut pipe = unsafe { fs::File::from_raw_fd(read_fd) };
+    let _ = pipe.read_to_end(&mut output);
+
+    match child.and_then(wait_pid) {
+        Ok(status) => shell.status = status,
+        Err(e) => eprintln!("rush: {}", e),
+    }
+
+    let mut output = String::from_utf8_lossy(&output).into_owned();
+    output.truncate(output.trim_end_matches('\n').len());
+    output
+}
+
+// Run before every simple command: the profiler and coverage, if any, are
+// told where the shell is at and the DEBUG trap runs
+fn run_debug_hook(shell: &mut Shell) {
+    if shell.in_debug_trap {
+        return;
+    }
+
+    if let Some(profiler) = &mut shell.profiler {
+        profiler.hit(shell.location.as_ref());
+    }
+
+    if let (Some(coverage), Some(location)) = (&mut shell.coverage, &shell.location) {
+        coverage.hit(location);
+    }
+
+    if let Some(action) = shell.traps.get(trap::DEBUG).map(str::to_string) {
+        // The trap doesn't change `$?` for the command it runs before
+        let status = shell.status;
+        shell.in_debug_trap = true;
+        run_line(&action, shell);
+        shell.in_debug_trap = false;
+        shell.status = status;
+    }
+}
+
+pub fn run_pending_traps(shell: &mut Shell) {
+    for signum in signal::pending() {
+        match shell.traps.get(signum).map(str::to_string) {
+            Some(action) => {
+                // The action doesn't change `$?`
+                let status = shell.status;
+                run_line(&action, shell);
+                shell.status = status;
+            }
+            // Without a trap Ctrl-C interrupts the line of an interactive
+            // shell, for a fresh prompt
+            None if signum == signal::SIGINT && shell.job_control => shell.interrupted = true,
+            None => {}
+        }
+    }
+}
+
+// An interactive shell with jobs left warns instead of exiting, unless that
+// was already the case for the previous command
+pub fn confirm_exit(shell: &mut Shell) -> bool {
+    if !shell.job_control || shell.exit_warned {
+        return true;
+    }
+
+    shell.jobs.reap();
+
+    let warning = if shell.jobs.iter().any(|job| job.stopped) {
+        tr!("There are stopped jobs.")
+    } else if shell.jobs.iter().next().is_some() {
+        tr!("There are running jobs.")
+    } else {
+        return true;
+    };
+
+    eprintln!("rush: {}", warning);
+    shell.exit_warned = true;
+    false
+}
+
+// Run the EXIT trap, if any, and terminate the shell
+pub fn exit(status: i32, shell: &mut Shell) -> ! {
+    if let Some(action) = shell.traps.get(trap::EXIT).map(str::to_string) {
+        shell.traps.reset(trap::EXIT);
+        run_line(&action, shell);
+    }
+
+    // Jobs would otherwise be left without a terminal, or stopped forever
+    if shell.job_control {
+        shell.jobs.hang_up();
+    }
+
+    process::exit(status)
+}
+
+// Single quote a string so the shell reads it back verbatim
+pub fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+// Words made of characters with no special meaning are left as they are, so
+// builtin names are still recognized
+pub fn quote_if_needed(s: &str) -> String {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "_-./,:=+@%".contains(c);
+
+    match !s.is_empty() && s.chars().all(plain) {
+        true => s.to_string(),
+        false => quote(s),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::convert::TryFrom;
+
+    use super::*;
+    use crate::cmd::{Compound, Op};
+
+    #[test]
+    fn test_status_builtins() {
+        let mut shell = Shell::new();
+
+        run_line("x=1; : $((x += 1))", &mut shell);
+        assert_eq!(shell.lookup("x").as_deref(), Some("2"));
+        assert_eq!(shell.status, Status::Exited(0));
+        run_line("false", &mut shell);
+        assert_eq!(shell.status, Status::Exited(1));
+        run_line("true", &mut shell);
+        assert_eq!(shell.status, Status::Exited(0));
+    }
+
+    #[test]
+    fn test_function_definition() {
+        match Expression::try_from("greet() {\n  echo hi $1\n}; greet").unwrap() {
+            Expression::Compound(compound) => match *compound {
+                Compound {
+                    op: Op::Semicolon,
+                    left: Expression::Function(function),
+                    right: Expression::Line(2, cmd),
+                } => {
+                    assert_eq!(function.name, "greet");
+                    assert_eq!(function.body, "{\n  echo hi $1\n}");
+                    assert_eq!(function.body_line, 0);
+                    assert!(matches!(*cmd, Expression::Cmd(_)));
+                }
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        }
+
+        for line in &["1x() { ls; }", "f() ls", "f() {"] {
+            assert!(Expression::try_from(*line).is_err(), "{}", line);
+        }
+
+        let shell = Shell::new();
+        assert!(is_incomplete("f() {\n", &shell));
+        assert!(is_incomplete("echo 'a\n", &shell));
+        assert!(!is_incomplete("f() { ls; }\n", &shell));
+        assert!(is_incomplete("echo a \\\n", &shell));
+        assert!(!is_incomplete("echo a \\\\\n", &shell));
+    }
+
+    #[test]
+    fn test_redirections() {
+        let parsed = match Expression::try_from("ls>out 2>&1 x <in") {
+            Ok(Expression::Redirected(redirected)) => redirected.redirections,
+            _ => unreachable!(),
+        };
+        assert_eq!(
+            parsed,
+            [
+                Redirection::new(">", "out"),
+                Redirection::new("2>&", "1"),
+                Redirection::new("<", "in"),
+            ]
+        );
+        assert_eq!((parsed[1].fd, parsed[1].kind), (2, Redirect::Dup));
+        assert_eq!((parsed[2].fd, parsed[2].kind), (0, Redirect::Read));
+        assert!(
+            Expression::try_from("echo 2 3").is_ok_and(|expr| matches!(expr, Expression::Cmd(_)))
+        );
+        assert!(Expression::try_from("echo >").is_err());
+
+        let dir = env::temp_dir().join(format!("rush-redirect-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let out = dir.join("out");
+        let mut shell = Shell::new();
+
+        run_line(&format!("getconf OS > {}", out.display()), &mut shell);
+        run_line(
+            &format!("{{ getconf ARCH; }} >> {}", out.display()),
+            &mut shell,
+        );
+        let expected = format!("{}\n{}\n", env::consts::OS, env::consts::ARCH);
+        assert_eq!(fs::read_to_string(&out).unwrap(), expected);
+
+        // A target failing to expand undoes the redirections before it
+        run_line(&format!("true 7>{} 8>$((1/0))", out.display()), &mut shell);
+        assert_eq!(shell.status, Status::Exited(1));
+        assert_eq!(unsafe { sys::fcntl(7, sys::F_GETFD, 0) }, -1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_if() {
+        match Expression::try_from("if a; then b; elif c\nthen d; else e; fi") {
+            Ok(Expression::If(conditional)) => {
+                assert_eq!(conditional.branches.len(), 2);
+                assert!(conditional.otherwise.is_some());
+            }
+            _ => unreachable!(),
+        }
+
+        for line in &[
+            "if a; then fi",
+            "if; then b; fi",
+            "then b",
+            "if a; then b; fi fi",
+        ] {
+            assert!(Expression::try_from(*line).is_err(), "{}", line);
+        }
+
+        let shell = Shell::new();
+        assert!(is_incomplete("if a; then\n", &shell));
+        assert!(is_incomplete("if a; then b; else\nc\n", &shell));
+        assert!(!is_incomplete("if a; then echo fi; fi\n", &shell));
+
+        let mut shell = Shell::new();
+        let line = "if false; then x=1; elif [[ a == a ]]; then x=2; else x=3; fi";
+        run_line(line, &mut shell);
+        assert_eq!(shell.lookup("x").as_deref(), Some("2"));
+
+        // Conditions that can't run are false
+        run_line("if cd /no-such-dir; then y=1; else y=2; fi", &mut shell);
+        assert_eq!(shell.lookup("y").as_deref(), Some("2"));
+        let line = "if case $((1 / 0)) in *) ;; esac; then z=1; elif true; then z=2; fi";
+        run_line(line, &mut shell);
+        assert_eq!(shell.lookup("z").as_deref(), Some("2"));
+        run_line("case $((1 / 0)) in *) ;; esac || w=1", &mut shell);
+        assert_eq!(shell.lookup("w").as_deref(), Some("1"));
+    }
+
+    #[test]
+    fn test_arith_for() {
+        match Expression::try_from("for ((i = 0; i < 3; i++))\ndo echo $i; done") {
+            Ok(Expression::ArithFor(arith_for)) => {
+                assert_eq!(arith_for.cond, " i < 3");
+                assert_eq!(arith_for.body, "echo $i;");
+            }
+            _ => unreachable!(),
+        }
+
+        for line in &[
+            "for ((i)); do ls; done",
+            "for ((;;)) ls; done",
+            "for i; do ls; done",
+        ] {
+            assert!(Expression::try_from(*line).is_err(), "{}", line);
+        }
+        assert!(is_incomplete("for ((;;)); do\n", &Shell::new()));
+
+        let mut shell = Shell::new();
+        run_line("for ((i = 0; i < 4; i++)); do s=$s$i; done", &mut shell);
+        assert_eq!(shell.lookup("s").as_deref(), Some("0123"));
+        assert_eq!(shell.status, Status::Exited(0));
+    }
+
+    #[test]
+    fn test_select() {
+        match Expression::try_from("select x in a 'b c'\ndo echo $x; break; done") {
+            Ok(Expression::Select(select)) => {
+                assert_eq!(select.name, "x");
+                assert_eq!(select.words, Some(vec!["a", "'b c'"]));
+                assert_eq!(select.body, "echo $x; break;");
+            }
+            _ => unreachable!(),
+        }
+        match Expression::try_from("select x; do ls; done") {
+            Ok(Expression::Select(select)) => assert_eq!(select.words, None),
+            _ => unreachable!(),
+        }
+
+        for line in &["select 1x in a; do ls; done", "select x in a; ls; done"] {
+            assert!(Expression::try_from(*line).is_err(), "{}", line);
+        }
+        assert!(is_incomplete("select x in a b\n", &Shell::new()));
+
+        // Nothing to pick from, so nothing is read
+        let mut shell = Shell::new();
+        run_line("select x in; do y=1; done", &mut shell);
+        assert_eq!(shell.status, Status::Exited(0));
+        assert_eq!(shell.lookup("y"), None);
+    }
+
+    #[test]
+    fn test_break_continue() {
+        let mut shell = Shell::new();
+        run_line(
+            "for ((i = 0; i < 5; i++)); do [[ $i == 1 ]] && continue; [[ $i == 3 ]] && break; s=$s$i; done",
+            &mut shell,
+        );
+        assert_eq!(shell.lookup("s").as_deref(), Some("02"));
+
+        run_line(
+            "for ((i = 0; i < 3; i++)); do for ((j = 0; j < 3; j++)); do [[ $j == 1 ]] && continue 2; [[ $i == 2 ]] && break 5; t=$t$i$j; done; t=$t-; done",
+            &mut shell,
+        );
+        assert_eq!(shell.lookup("t").as_deref(), Some("0010"));
+        assert_eq!(shell.jump, None);
+        assert_eq!(shell.loops, 0);
+
+        run_line("break", &mut shell);
+        assert_eq!(shell.status, Status::Exited(1));
+        run_line("continue 0", &mut shell);
+        assert_eq!(shell.status, Status::Exited(1));
+        assert_eq!(shell.jump, None);
+    }
+
+    #[test]
+    fn test_case() {
+        match Expression::try_from("case $x in\n(a|b) echo;;\n*) ;;\nesac") {
+            Ok(Expression::Case(case)) => {
+                assert_eq!(case.word, "$x");
+                assert_eq!(case.items[0].0, vec!["a", "b"]);
+                assert!(case.items[1].1.is_none());
+            }
+            _ => unreachable!(),
+        }
+
+        for line in &[
+            "case x; esac",
+            "case x in a) ls ls) ;; esac",
+            "esac",
+            "ls;;",
+        ] {
+            assert!(Expression::try_from(*line).is_err(), "{}", line);
+        }
+        assert!(is_incomplete("case x in\na) ls;;\n", &Shell::new()));
+
+        let mut shell = Shell::new();
+        let run = |word: &str, shell: &mut Shell| {
+            let line = format!("case {} in a*) r=1;; '*') r=2;; *) r=3;; esac", word);
+            run_line(&line, shell);
+            shell.lookup("r")
+        };
+        assert_eq!(run("abc", &mut shell).as_deref(), Some("1"));
+        assert_eq!(run("'*'", &mut shell).as_deref(), Some("2"));
+        assert_eq!(run("b", &mut shell).as_deref(), Some("3"));
+    }
+
+    #[test]
+    fn test_conditions() {
+        assert!(matches!(
+            Expression::try_from("[[ a < b && (c) ]]"),
+            Ok(Expression::Cond(" a < b && (c) "))
+        ));
+        assert!(matches!(
+            Expression::try_from("echo [[ x"),
+            Ok(Expression::Cmd(_))
+        ));
+        assert!(matches!(
+            Expression::try_from("[[ x == y"),
+            Err(Error::Incomplete)
+        ));
+        match Expression::try_from("assert [[ -f \"$f\" ]] missing \"$f\"") {
+            Ok(Expression::Assert(assert)) => {
+                assert_eq!(assert.text, "[[ -f \"$f\" ]]");
+                assert_eq!(assert.message, vec!["missing", "\"$f\""]);
+            }
+            _ => unreachable!(),
+        }
+
+        let mut shell = Shell::new();
+        assert_eq!(run_cond(" a != b ", &mut shell), Status::Exited(0));
+        assert_eq!(run_cond(" -n '' ", &mut shell), Status::Exited(1));
+        assert_eq!(run_cond(" ( a ", &mut shell), Status::Exited(2));
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        assert!(matches!(
+            Expression::try_from("(( x > 3 ))"),
+            Ok(Expression::Arith(" x > 3 "))
+        ));
+        assert!(matches!(
+            Expression::try_from("((ls) )"),
+            Ok(Expression::Subshell(_))
+        ));
+
+        let mut shell = Shell::new();
+        assert_eq!(run_arith("x = 6", &mut shell), Status::Exited(0));
+        assert_eq!(run_arith("x == 6", &mut shell), Status::Exited(0));
+        assert_eq!(run_arith("x / 0", &mut shell), Status::Exited(1));
+        assert_eq!(shell.expand_string("$((x + 1))$(( (1) ))"), "71");
+
+        // A failed expansion fails the command without running it
+        run_line("y=$((x / 0)); z=1", &mut shell);
+        assert_eq!(shell.lookup("y"), None);
+        assert_eq!(shell.lookup("z").as_deref(), Some("1"));
+        run_line("true $((1 / 0))", &mut shell);
+        assert_eq!(shell.status, Status::Exited(1));
+        run_line("true $((1 / 0)) || w=1", &mut shell);
+        assert_eq!(shell.lookup("w").as_deref(), Some("1"));
+        // Nor leaves the variables exported before it in the environment
+        run_line("RUSH_EXPORTED=1 b=$((1 / 0)) true", &mut shell);
+        assert_eq!(env::var_os("RUSH_EXPORTED"), None);
+    }
+
+    #[test]
+    fn test_pipeline() {
+        match Expression::try_from("echo a | tr a b && ls").unwrap() {
+            Expression::Compound(compound) => match compound.left {
+                Expression::Pipeline(pipeline) => {
+                    assert_eq!(pipeline.stages.len(), 2);
+                    assert_eq!(pipeline.text, "echo a | tr a b");
+                }
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        }
+
+        assert!(Expression::try_from("| ls").is_err());
+        assert!(Expression::try_from("ls | | ls").is_err());
+        assert!(matches!(
+            Expression::try_from("ls |"),
+            Err(Error::Incomplete)
+        ));
+
+        let mut shell = Shell::new();
+        run_line("x=$(echo abc | tr a-c x-z | tr z Z)", &mut shell);
+        assert_eq!(shell.vars["x"], "xyZ");
+    }
+
+    #[test]
+    fn test_exit_status() {
+        let mut shell = Shell::new();
+
+        run_line("(false; exit)", &mut shell);
+        assert_eq!(shell.status, Status::Exited(1));
+        run_line("(x=3; exit $x)", &mut shell);
+        assert_eq!(shell.status, Status::Exited(3));
+        run_line("(false; exit $?)", &mut shell);
+        assert_eq!(shell.status, Status::Exited(1));
+
+        run_line("exit abc", &mut shell);
+        assert_eq!(shell.status, Status::Exited(2));
+    }
+
+    #[test]
+    fn test_return() {
+        let mut shell = Shell::new();
+
+        run_line("f() { x=1; return 3; x=2; }", &mut shell);
+        run_line("f; y=$?", &mut shell);
+        assert_eq!(shell.vars["x"], "1");
+        assert_eq!(shell.vars["y"], "3");
+        assert!(!shell.returning);
+
+        run_line("return 1", &mut shell);
+        assert_eq!(shell.status, Status::Exited(1));
+        assert!(!shell.returning);
+    }
+
+    #[test]
+    fn test_source_params() {
+        let path = env::temp_dir().join(format!("rush-params-{}", std::process::id()));
+        fs::write(&path, "a=$0:$#:$1\nshift\nb=$1\nset -- x y\nc=$*:$#\n").unwrap();
+
+        let mut shell = Shell::new();
+        shell.args[0] = String::from("script");
+        let params = vec![String::from("one"), String::from("two")];
+        assert_eq!(
+            run_source(&path, params, &mut shell).unwrap(),
+            Status::Exited(0)
+        );
+        assert_eq!(shell.lookup("a").as_deref(), Some("script:2:one"));
+        assert_eq!(shell.lookup("b").as_deref(), Some("two"));
+        assert_eq!(shell.lookup("c").as_deref(), Some("x y:2"));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_or_and_not() {
+        let mut shell = Shell::new();
+        run_line("false || x=1; true || y=1; false && z=1 || w=1", &mut shell);
+        assert_eq!(shell.lookup("x").as_deref(), Some("1"));
+        assert_eq!(shell.lookup("y"), None);
+        assert_eq!(shell.lookup("z"), None);
+        assert_eq!(shell.lookup("w").as_deref(), Some("1"));
+
+        run_line("! true", &mut shell);
+        assert_eq!(shell.status, Status::Exited(1));
+        run_line("! [[ a == b ]] && v=1", &mut shell);
+        assert_eq!(shell.lookup("v").as_deref(), Some("1"));
+        assert!(is_incomplete("true ||\n", &shell));
+
+        // Only a failure that isn't tested exits with `set -e`
+        run_line(
+            "(set -e; false || true; ! true; false && true; true)",
+            &mut shell,
+        );
+        assert_eq!(shell.status, Status::Exited(0));
+        run_line("(set -e; false || false; true)", &mut shell);
+        assert_eq!(shell.status, Status::Exited(1));
+    }
+
+    #[test]
+    fn test_failing_builtins() {
+        let mut shell = Shell::new();
+
+        // A builtin that fails goes on with the rest of the line
+        run_line(
+            "cd /no-such-dir || a=1; source /no-such-file && b=1",
+            &mut shell,
+        );
+        assert_eq!(shell.lookup("a").as_deref(), Some("1"));
+        assert_eq!(shell.lookup("b"), None);
+        assert_eq!(shell.status, Status::Exited(1));
+        run_line("cd || c=1; pushd /no-such-dir; d=$?", &mut shell);
+        assert_eq!(shell.lookup("c").as_deref(), Some("1"));
+        assert_eq!(shell.lookup("d").as_deref(), Some("1"));
+    }
+
+    #[test]
+    fn test_element_assignment() {
+        match Cmd::try_from("m[a=b]=c x=[y]") {
+            Ok(Cmd::Assign(assignments)) => {
+                assert_eq!(assignments[0].name, "m");
+                assert_eq!(assignments[0].key, Some("a=b"));
+                assert_eq!(assignments[0].value, "c");
+                assert_eq!(assignments[1].name, "x");
+                assert_eq!(assignments[1].key, None);
+                assert_eq!(assignments[1].value, "[y]");
+            }
+            _ => unreachable!(),
+        }
+
+        let mut shell = Shell::new();
+        run_line("declare -A m; k=key; m[$k]=v; x=${m[key]}", &mut shell);
+        assert_eq!(shell.lookup("x").as_deref(), Some("v"));
+        run_line("unset 'm[key]'", &mut shell);
+        assert!(shell.maps["m"].is_empty());
+    }
+}
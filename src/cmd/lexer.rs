@@ -0,0 +1,334 @@
+// Splitting a command line into words and operators for the parser
+
+use std::ops::Range;
+
+use super::{opens_list, Error};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Token<'a> {
+    // A word keeps its quotes, they are removed when the word gets expanded
+    Word(&'a str),
+    Semicolon,
+    // `;;` ends an item of `case`
+    DoubleSemicolon,
+    Newline,
+    And,
+    Or,
+    Pipe,
+    Background,
+    // `&!`
+    Disown,
+    // A redirection operator along with the descriptor in front of it, the
+    // target is the word after it
+    Redirect(&'a str),
+    LParen,
+    // `(( expr ))` is arithmetic rather than a subshell in a subshell
+    Arith(&'a str),
+    // `[[ expr ]]` where a command starts
+    Cond(&'a str),
+    RParen,
+}
+
+// Split the commandline into words and operators, returning the byte range
+// every token spans
+pub fn tokenize(line: &str) -> Result<Vec<(Range<usize>, Token<'_>)>, Error> {
+    let bytes = line.as_bytes();
+    let mut tokens = vec![];
+    let mut i = 0;
+    let mut unclosed = false;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b' ' | b'\t' | b'\r' => i += 1,
+            b'\\' if bytes.get(i + 1) == Some(&b'\n') => i += 2,
+
+            b'\n' => {
+                tokens.push((i..i + 1, Token::Newline));
+                i += 1;
+            }
+
+            b';' if bytes.get(i + 1) == Some(&b';') => {
+                tokens.push((i..i + 2, Token::DoubleSemicolon));
+                i += 2;
+            }
+
+            b';' => {
+                tokens.push((i..i + 1, Token::Semicolon));
+                i += 1;
+            }
+
+            b'&' if bytes.get(i + 1) == Some(&b'>') => {
+                let len = if bytes.get(i + 2) == Some(&b'>') {
+                    3
+                } else {
+                    2
+                };
+                tokens.push((i..i + len, Token::Redirect(&line[i..i + len])));
+                i += len;
+            }
+
+            b'0'..=b'9' | b'<' | b'>' if redirection_len(&line[i..]).is_some() => {
+                let len = redirection_len(&line[i..]).unwrap();
+                tokens.push((i..i + len, Token::Redirect(&line[i..i + len])));
+                i += len;
+            }
+
+            b'&' if bytes.get(i + 1) == Some(&b'&') => {
+                tokens.push((i..i + 2, Token::And));
+                i += 2;
+            }
+
+            b'&' if bytes.get(i + 1) == Some(&b'!') => {
+                tokens.push((i..i + 2, Token::Disown));
+                i += 2;
+            }
+
+            b'&' => {
+                tokens.push((i..i + 1, Token::Background));
+                i += 1;
+            }
+
+            b'|' if bytes.get(i + 1) == Some(&b'|') => {
+                tokens.push((i..i + 2, Token::Or));
+                i += 2;
+            }
+
+            b'|' => {
+                tokens.push((i..i + 1, Token::Pipe));
+                i += 1;
+            }
+
+            b'(' => {
+                let body = &line[i + 1..];
+                let mut arith = None;
+
+                // Once a `(` isn't closed there is no point looking for the
+                // end of the ones after it, the line can't be complete
+                if !unclosed && body.starts_with('(') {
+                    match find_closing_paren(body) {
+                        Ok(len) => arith = arithmetic_body(&body[..len]).map(|e| (e, len + 2)),
+                        Err(_) => unclosed = true,
+                    }
+                }
+
+                match arith {
+                    Some((expr, len)) => {
+                        tokens.push((i..i + len, Token::Arith(expr)));
+                        i += len;
+                    }
+                    None => {
+                        tokens.push((i..i + 1, Token::LParen));
+                        i += 1;
+                    }
+                }
+            }
+
+            b')' => {
+                tokens.push((i..i + 1, Token::RParen));
+                i += 1;
+            }
+
+            // A comment, up to the end of the line
+            b'#' => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+
+            b'[' if starts_command(&tokens) && is_cond_start(&line[i..]) => {
+                let len = find_cond_end(&line[i + 2..])?;
+                tokens.push((i..i + len + 4, Token::Cond(&line[i + 2..i + 2 + len])));
+                i += len + 4;
+            }
+
+            _ => {
+                let start = i;
+
+                while i < bytes.len() {
+                    match bytes[i] {
+                        b' ' | b'\t' | b'\n' | b'\r' | b';' | b'&' | b'|' | b'(' | b')' | b'<'
+                        | b'>' => break,
+
+                        b'$' if bytes.get(i + 1) == Some(&b'(') => {
+                            i += 2 + find_closing_paren(&line[i + 2..])? + 1;
+                        }
+
+                        _ => i = skip_quoted(line, i)?,
+                    }
+                }
+
+                let end = i.min(bytes.len());
+                tokens.push((start..end, Token::Word(&line[start..end])));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+// Whether the next token is where a command starts, or the condition of
+// `assert`
+fn starts_command(tokens: &[(Range<usize>, Token)]) -> bool {
+    matches!(
+        tokens.last(),
+        None | Some((_, Token::Word("assert")))
+            | Some((_, Token::Semicolon))
+            | Some((_, Token::DoubleSemicolon))
+            | Some((_, Token::RParen))
+            | Some((_, Token::Newline))
+            | Some((_, Token::And))
+            | Some((_, Token::Or))
+            | Some((_, Token::Pipe))
+            | Some((_, Token::Background))
+            | Some((_, Token::Disown))
+            | Some((_, Token::LParen))
+    ) || matches!(tokens.last(), Some((_, Token::Word(word))) if opens_list(word))
+}
+
+fn is_cond_start(s: &str) -> bool {
+    s.strip_prefix("[[")
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace))
+}
+
+// The length of the condition after `[[`, up to the `]]` word closing it
+fn find_cond_end(s: &str) -> Result<usize, Error> {
+    let bytes = s.as_bytes();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b' ' | b'\t' | b'\n' if s[i + 1..].starts_with("]]") => {
+                let after = bytes.get(i + 3);
+                if matches!(
+                    after,
+                    None | Some(b' ' | b'\t' | b'\n' | b'\r' | b';' | b'&' | b'|' | b')')
+                ) {
+                    return Ok(i + 1);
+                }
+                i += 1;
+            }
+
+            b'$' if bytes.get(i + 1) == Some(&b'(') => {
+                i += 2 + find_closing_paren(&s[i + 2..])? + 1;
+            }
+
+            _ => i = skip_quoted(s, i)?,
+        }
+    }
+
+    Err(Error::Incomplete)
+}
+
+// The length of the redirection operator `s` starts with, if any: `<`,
+// `>`, `>>`, `<&` or `>&` with an optional descriptor in front
+fn redirection_len(s: &str) -> Option<usize> {
+    let digits = s.bytes().take_while(u8::is_ascii_digit).count();
+    let rest = &s.as_bytes()[digits..];
+
+    let len = match rest {
+        [b'>', b'>', ..] | [b'>', b'&', ..] | [b'<', b'&', ..] => 2,
+        [b'>', ..] | [b'<', ..] => 1,
+        _ => return None,
+    };
+    Some(digits + len)
+}
+
+// Index past the quoted string or escaped character at `i`, or just past `i`
+// for any other character
+pub(super) fn skip_quoted(line: &str, mut i: usize) -> Result<usize, Error> {
+    let bytes = line.as_bytes();
+
+    match bytes[i] {
+        b'\\' => Ok(i + 2),
+
+        b'\'' => {
+            let close = line[i + 1..].find('\'').ok_or(Error::UnclosedQuote)?;
+            Ok(i + close + 2)
+        }
+
+        b'"' => {
+            i += 1;
+            loop {
+                match bytes.get(i) {
+                    None => return Err(Error::UnclosedQuote),
+                    Some(b'"') => return Ok(i + 1),
+                    Some(b'\\') => i += 2,
+                    Some(b'$') if bytes.get(i + 1) == Some(&b'(') => {
+                        i += 2 + find_closing_paren(&line[i + 2..])? + 1;
+                    }
+                    Some(_) => i += 1,
+                }
+            }
+        }
+
+        _ => Ok(i + 1),
+    }
+}
+
+// The expression of a `$(...)` body that is `(expr)`, making it `$((expr))`
+pub fn arithmetic_body(body: &str) -> Option<&str> {
+    let inner = body.strip_prefix('(')?;
+
+    match find_closing_paren(inner) {
+        Ok(len) if len == inner.len() - 1 => Some(&inner[..len]),
+        _ => None,
+    }
+}
+
+// The parts of a word and whether they're quoted, a command substitution
+// is left whole in the part it's in
+pub fn quoted_parts(word: &str) -> Result<Vec<(&str, bool)>, Error> {
+    let bytes = word.as_bytes();
+    let mut parts = vec![];
+    let mut start = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'$' if bytes.get(i + 1) == Some(&b'(') => {
+                i += 2 + find_closing_paren(&word[i + 2..])? + 1;
+            }
+
+            b'\\' | b'\'' | b'"' => {
+                parts.push((&word[start..i], false));
+                let end = skip_quoted(word, i)?.min(word.len());
+                parts.push((&word[i..end], true));
+                start = end;
+                i = end;
+            }
+
+            _ => i += 1,
+        }
+    }
+
+    parts.push((&word[start..], false));
+    Ok(parts)
+}
+
+// Offset of the `)` closing a substitution whose body starts `s`
+pub fn find_closing_paren(s: &str) -> Result<usize, Error> {
+    let bytes = s.as_bytes();
+    let mut depth = 1;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'(' => {
+                depth += 1;
+                i += 1;
+            }
+
+            b')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(i);
+                }
+                i += 1;
+            }
+
+            _ => i = skip_quoted(s, i)?,
+        }
+    }
+
+    Err(Error::UnclosedSubstitution)
+}
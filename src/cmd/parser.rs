@@ -0,0 +1,1165 @@
+// Building the expression a line stands for out of its tokens
+
+use std::convert::TryFrom;
+use std::env;
+use std::ffi::OsStr;
+use std::iter::Peekable;
+use std::ops::Range;
+use std::vec::IntoIter;
+
+use super::lexer::skip_quoted;
+use super::{
+    arithmetic_body, find_closing_paren, tokenize, ArithFor, Assert, Assignment, Case, Cmd,
+    Compound, Error, Expression, Forked, Function, If, Invoke, LineIter, Op, Pipeline, Redirect,
+    Redirected, Redirection, Select, Token,
+};
+use crate::shell::Shell;
+
+pub const KEYWORDS: &[&str] = &[
+    "{", "}", "[[", "]]", "if", "then", "elif", "else", "fi", "for", "select", "do", "done",
+    "case", "in", "esac",
+];
+
+// Keywords that can't start a command as they close or continue a compound
+// one
+const CLOSING: &[&str] = &["}", "then", "elif", "else", "fi", "do", "done", "esac"];
+
+// Whether the word opens a list, so the word after it starts a command
+pub fn opens_list(word: &str) -> bool {
+    matches!(word, "{" | "if" | "then" | "elif" | "else" | "do" | "!")
+}
+
+// Nesting allowed for subshells, substitutions and groups unless
+// `RUSH_MAX_DEPTH` says otherwise
+pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+impl<'a> TryFrom<&'a str> for Expression<'a> {
+    type Error = Error;
+
+    // Extract the expression from the commandline
+    fn try_from(line: &'a str) -> Result<Self, Self::Error> {
+        Expression::parse(line, DEFAULT_MAX_DEPTH)
+    }
+}
+
+struct Parser<'a> {
+    line: &'a str,
+    tokens: Peekable<IntoIter<(Range<usize>, Token<'a>)>>,
+    depth: usize,
+    max_depth: usize,
+}
+
+// What closes the list being parsed
+#[derive(Clone, Copy, PartialEq)]
+enum Terminator {
+    End,
+    Paren,
+    Brace,
+    // The condition of `if` or `elif`
+    Then,
+    // What runs when the condition succeeds
+    Else,
+    // What runs when none does
+    Fi,
+    // The body of a loop
+    Done,
+    // What runs when a pattern of `case` matches
+    Esac,
+}
+
+impl<'a> Parser<'a> {
+    fn new(line: &'a str, depth: usize, max_depth: usize) -> Result<Self, Error> {
+        Ok(Parser {
+            line,
+            tokens: tokenize(line)?.into_iter().peekable(),
+            depth,
+            max_depth,
+        })
+    }
+
+    fn at_terminator(&mut self, terminator: Terminator) -> bool {
+        matches!(
+            (self.tokens.peek(), terminator),
+            (None, _)
+                | (Some((_, Token::RParen)), Terminator::Paren)
+                | (Some((_, Token::Word("}"))), Terminator::Brace)
+                | (Some((_, Token::Word("then"))), Terminator::Then)
+                | (
+                    Some((_, Token::Word("elif" | "else" | "fi"))),
+                    Terminator::Else
+                )
+                | (Some((_, Token::Word("fi"))), Terminator::Fi)
+                | (Some((_, Token::Word("done"))), Terminator::Done)
+                | (Some((_, Token::DoubleSemicolon)), Terminator::Esac)
+                | (Some((_, Token::Word("esac"))), Terminator::Esac)
+        )
+    }
+
+    // list := and_or ((';' | '&') and_or)* [';' | '&']
+    fn parse_list(&mut self, terminator: Terminator) -> Result<Expression<'a>, Error> {
+        let mut stmts = vec![];
+
+        while !self.at_terminator(terminator) {
+            if let Some((_, Token::Newline)) = self.tokens.peek() {
+                self.tokens.next();
+                continue;
+            }
+
+            let start = self.tokens.peek().map_or(0, |(span, _)| span.start);
+            let stmt = self.parse_and_or()?;
+            let mut last = false;
+
+            let stmt = match self.tokens.peek() {
+                Some((_, Token::Semicolon)) | Some((_, Token::Newline)) => {
+                    self.tokens.next();
+                    stmt
+                }
+
+                Some((span, Token::Background)) => {
+                    let text = self.line[start..span.start].trim_end();
+                    self.tokens.next();
+                    Expression::Background(Box::new(Forked { body: stmt, text }))
+                }
+
+                Some((span, Token::Disown)) => {
+                    let text = self.line[start..span.start].trim_end();
+                    self.tokens.next();
+                    Expression::Disowned(Box::new(Forked { body: stmt, text }))
+                }
+
+                _ => {
+                    last = true;
+                    stmt
+                }
+            };
+
+            stmts.push(match self.line_of(start) {
+                0 => stmt,
+                line => Expression::Line(line, Box::new(stmt)),
+            });
+            if last {
+                break;
+            }
+        }
+
+        if stmts.is_empty() {
+            return Err(match terminator {
+                Terminator::End => Error::EmptyLine,
+                _ if self.tokens.peek().is_none() => Error::Incomplete,
+                Terminator::Paren => Error::Syntax(String::from(")")),
+                Terminator::Brace => Error::Syntax(String::from("}")),
+                _ => {
+                    let (span, _) = self.tokens.peek().unwrap();
+                    Error::Syntax(self.line[span.clone()].to_string())
+                }
+            });
+        }
+
+        Ok(Expression::build_semicolon_expression(stmts.into_iter()))
+    }
+
+    // and_or := pipeline (('&&' | '||') newline* pipeline)*
+    fn parse_and_or(&mut self) -> Result<Expression<'a>, Error> {
+        let mut expr = self.parse_pipeline()?;
+
+        loop {
+            let op = match self.tokens.peek() {
+                Some((_, Token::And)) => Op::And,
+                Some((_, Token::Or)) => Op::Or,
+                _ => break,
+            };
+            self.tokens.next();
+            self.skip_newlines();
+
+            if self.tokens.peek().is_none() {
+                return Err(Error::Incomplete);
+            }
+
+            // They group to the left, `a || b && c` runs `c` after either
+            expr = Expression::Compound(Box::new(Compound {
+                op,
+                left: expr,
+                right: self.parse_pipeline()?,
+            }));
+        }
+
+        Ok(expr)
+    }
+
+    // pipeline := ['!'] command ('|' newline* command)*
+    fn parse_pipeline(&mut self) -> Result<Expression<'a>, Error> {
+        if let Some((_, Token::Word("!"))) = self.tokens.peek() {
+            self.tokens.next();
+            return Ok(Expression::Not(Box::new(self.parse_pipeline()?)));
+        }
+
+        let start = self.tokens.peek().map_or(0, |(span, _)| span.start);
+        let mut stages = vec![self.parse_command()?];
+
+        while let Some((_, Token::Pipe)) = self.tokens.peek() {
+            self.tokens.next();
+
+            while let Some((_, Token::Newline)) = self.tokens.peek() {
+                self.tokens.next();
+            }
+
+            if self.tokens.peek().is_none() {
+                return Err(Error::Incomplete);
+            }
+            stages.push(self.parse_command()?);
+        }
+
+        if stages.len() == 1 {
+            return Ok(stages.pop().unwrap());
+        }
+
+        let end = self
+            .tokens
+            .peek()
+            .map_or(self.line.len(), |(span, _)| span.start);
+
+        Ok(Expression::Pipeline(Box::new(Pipeline {
+            stages,
+            text: self.line[start..end].trim_end(),
+        })))
+    }
+
+    // command := function | ('(' list ')' | '{' list '}') redirection*
+    //          | (word | redirection)+
+    fn parse_command(&mut self) -> Result<Expression<'a>, Error> {
+        if self.at_function() {
+            return self.parse_function();
+        }
+
+        let mut redirections = vec![];
+        let body = self.parse_unredirected(&mut redirections)?;
+
+        while let Some((_, Token::Redirect(_))) = self.tokens.peek() {
+            redirections.push(self.parse_redirection()?);
+        }
+
+        match redirections.is_empty() {
+            true => Ok(body),
+            false => Ok(Expression::Redirected(Box::new(Redirected {
+                body,
+                redirections,
+            }))),
+        }
+    }
+
+    // redirection := ('<' | '>' | '>>' | '>&' | '<&' | '&>' | '&>>') word
+    fn parse_redirection(&mut self) -> Result<Redirection<'a>, Error> {
+        let op = match self.tokens.next() {
+            Some((_, Token::Redirect(op))) => op,
+            _ => unreachable!(),
+        };
+
+        match self.tokens.next() {
+            Some((_, Token::Word(target))) => {
+                self.check_substitutions(target)?;
+                Ok(Redirection::new(op, target))
+            }
+            Some((span, _)) => Err(Error::Syntax(self.line[span].to_string())),
+            None => Err(Error::Syntax(String::from("newline"))),
+        }
+    }
+
+    fn parse_unredirected(
+        &mut self,
+        redirections: &mut Vec<Redirection<'a>>,
+    ) -> Result<Expression<'a>, Error> {
+        if self.at_assert() {
+            return self.parse_assert();
+        }
+
+        match self.tokens.peek() {
+            Some((span, Token::LParen)) => {
+                let start = span.start;
+                self.tokens.next();
+                let body = self.parse_nested(Terminator::Paren)?;
+                match self.tokens.next() {
+                    Some((span, Token::RParen)) => Ok(Expression::Subshell(Box::new(Forked {
+                        body,
+                        text: &self.line[start..span.end],
+                    }))),
+                    None => Err(Error::Incomplete),
+                    _ => Err(Error::Syntax(String::from("("))),
+                }
+            }
+
+            Some((_, Token::Word("{"))) => {
+                self.tokens.next();
+                let list = self.parse_nested(Terminator::Brace)?;
+                match self.tokens.next() {
+                    Some((_, Token::Word("}"))) => Ok(Expression::Group(Box::new(list))),
+                    None => Err(Error::Incomplete),
+                    _ => Err(Error::Syntax(String::from("{"))),
+                }
+            }
+
+            Some((_, Token::Word("if"))) => {
+                self.enter()?;
+                let conditional = self.parse_if();
+                self.depth -= 1;
+                conditional
+            }
+
+            Some((_, Token::Word("select"))) => {
+                self.enter()?;
+                let what = self.parse_select();
+                self.depth -= 1;
+                what
+            }
+
+            Some((_, Token::Word("for"))) => {
+                self.enter()?;
+                let what = self.parse_for();
+                self.depth -= 1;
+                what
+            }
+
+            Some((_, Token::Word("case"))) => {
+                self.enter()?;
+                let what = self.parse_case();
+                self.depth -= 1;
+                what
+            }
+
+            Some((_, Token::Word(word))) if CLOSING.contains(word) => {
+                Err(Error::Syntax(word.to_string()))
+            }
+
+            Some((_, Token::Arith(expr))) => {
+                let expr = *expr;
+                self.tokens.next();
+                self.check_substitutions(expr)?;
+                Ok(Expression::Arith(expr))
+            }
+
+            Some((_, Token::Cond(expr))) => {
+                let expr = *expr;
+                self.tokens.next();
+                self.check_substitutions(expr)?;
+                Ok(Expression::Cond(expr))
+            }
+
+            Some((_, Token::Word(_))) | Some((_, Token::Redirect(_))) => {
+                let mut words = vec![];
+
+                loop {
+                    match self.tokens.peek() {
+                        Some((_, Token::Word(word))) => {
+                            let word = *word;
+                            self.tokens.next();
+                            self.check_substitutions(word)?;
+                            words.push(word);
+                        }
+                        Some((_, Token::Redirect(_))) => {
+                            redirections.push(self.parse_redirection()?);
+                        }
+                        _ => break,
+                    }
+                }
+
+                // Just redirections, like `> file` to empty it
+                if words.is_empty() {
+                    return Ok(Expression::Cmd(Cmd::Assign(vec![])));
+                }
+
+                Ok(Expression::Cmd(Cmd::from_words(words)?))
+            }
+
+            Some((_, Token::Semicolon)) => Err(Error::Syntax(String::from(";"))),
+            Some((_, Token::DoubleSemicolon)) => Err(Error::Syntax(String::from(";;"))),
+            Some((_, Token::Newline)) => Err(Error::Syntax(String::from("newline"))),
+            Some((_, Token::And)) | None => Err(Error::Syntax(String::from("&&"))),
+            Some((_, Token::Or)) => Err(Error::Syntax(String::from("||"))),
+            Some((_, Token::Background)) => Err(Error::Syntax(String::from("&"))),
+            Some((_, Token::Disown)) => Err(Error::Syntax(String::from("&!"))),
+            Some((_, Token::Pipe)) => Err(Error::Syntax(String::from("|"))),
+            Some((_, Token::RParen)) => Err(Error::Syntax(String::from(")"))),
+        }
+    }
+
+    // if := 'if' list 'then' list ('elif' list 'then' list)* ['else' list] 'fi'
+    fn parse_if(&mut self) -> Result<Expression<'a>, Error> {
+        let mut branches = vec![];
+        let mut otherwise = None;
+        self.tokens.next();
+
+        loop {
+            let cond = self.parse_list(Terminator::Then)?;
+            self.expect("then")?;
+            branches.push((cond, self.parse_list(Terminator::Else)?));
+
+            match self.tokens.next() {
+                Some((_, Token::Word("elif"))) => {}
+                Some((_, Token::Word("else"))) => {
+                    otherwise = Some(self.parse_list(Terminator::Fi)?);
+                    self.expect("fi")?;
+                    break;
+                }
+                Some((_, Token::Word("fi"))) => break,
+                Some((span, _)) => return Err(Error::Syntax(self.line[span].to_string())),
+                None => return Err(Error::Incomplete),
+            }
+        }
+
+        Ok(Expression::If(Box::new(If {
+            branches,
+            otherwise,
+        })))
+    }
+
+    // for := 'for' '((' expr ';' expr ';' expr '))' [';'] newline* 'do' list 'done'
+    fn parse_for(&mut self) -> Result<Expression<'a>, Error> {
+        self.tokens.next();
+
+        let expr = match self.tokens.next() {
+            Some((_, Token::Arith(expr))) => expr,
+            Some((span, _)) => return Err(Error::Syntax(self.line[span].to_string())),
+            None => return Err(Error::Incomplete),
+        };
+        self.check_substitutions(expr)?;
+
+        let (init, cond, step) = match expr.split(';').collect::<Vec<_>>()[..] {
+            [init, cond, step] => (init, cond, step),
+            _ => return Err(Error::Syntax(format!("(({}))", expr))),
+        };
+
+        if let Some((_, Token::Semicolon)) = self.tokens.peek() {
+            self.tokens.next();
+        }
+        let (body, body_line) = self.parse_do()?;
+
+        Ok(Expression::ArithFor(Box::new(ArithFor {
+            init,
+            cond,
+            step,
+            body,
+            body_line,
+        })))
+    }
+
+    // select := 'select' word [newline* 'in' word* (';' | newline)] do
+    fn parse_select(&mut self) -> Result<Expression<'a>, Error> {
+        self.tokens.next();
+
+        let name = self.word()?;
+        if !is_name(name) {
+            return Err(Error::Syntax(name.to_string()));
+        }
+
+        self.skip_newlines();
+        let words = match self.tokens.peek() {
+            Some((_, Token::Word("in"))) => {
+                self.tokens.next();
+                let mut words = vec![];
+                while let Some((_, Token::Word(_))) = self.tokens.peek() {
+                    words.push(self.word()?);
+                }
+                Some(words)
+            }
+            _ => None,
+        };
+
+        if let Some((_, Token::Semicolon)) = self.tokens.peek() {
+            self.tokens.next();
+        }
+        let (body, body_line) = self.parse_do()?;
+
+        Ok(Expression::Select(Box::new(Select {
+            name,
+            words,
+            body,
+            body_line,
+        })))
+    }
+
+    // do := newline* 'do' list 'done', the text of the list is kept for the
+    // loop to parse every time around
+    fn parse_do(&mut self) -> Result<(&'a str, usize), Error> {
+        self.skip_newlines();
+        self.expect("do")?;
+
+        let start = self
+            .tokens
+            .peek()
+            .map_or(self.line.len(), |(span, _)| span.start);
+        self.parse_list(Terminator::Done)?;
+        let end = self
+            .tokens
+            .peek()
+            .map_or(self.line.len(), |(span, _)| span.start);
+        self.expect("done")?;
+
+        Ok((self.line[start..end].trim_end(), self.line_of(start)))
+    }
+
+    // How many lines into the text the byte at `offset` is
+    fn line_of(&self, offset: usize) -> usize {
+        self.line[..offset].matches('\n').count()
+    }
+
+    // case := 'case' word newline* 'in' newline* item* 'esac'
+    // item := ['('] word ('|' word)* ')' [list] [';;' newline*]
+    fn parse_case(&mut self) -> Result<Expression<'a>, Error> {
+        self.tokens.next();
+
+        let word = self.word()?;
+        self.skip_newlines();
+        self.expect("in")?;
+        self.skip_newlines();
+
+        let mut items = vec![];
+
+        loop {
+            match self.tokens.peek() {
+                Some((_, Token::Word("esac"))) => {
+                    self.tokens.next();
+                    break;
+                }
+                Some((_, Token::LParen)) => {
+                    self.tokens.next();
+                }
+                _ => {}
+            }
+
+            let mut patterns = vec![self.word()?];
+            while let Some((_, Token::Pipe)) = self.tokens.peek() {
+                self.tokens.next();
+                patterns.push(self.word()?);
+            }
+
+            match self.tokens.next() {
+                Some((_, Token::RParen)) => {}
+                Some((span, _)) => return Err(Error::Syntax(self.line[span].to_string())),
+                None => return Err(Error::Incomplete),
+            }
+
+            self.skip_newlines();
+            let body = match self.at_terminator(Terminator::Esac) {
+                true => None,
+                false => Some(self.parse_list(Terminator::Esac)?),
+            };
+            items.push((patterns, body));
+
+            match self.tokens.peek() {
+                Some((_, Token::DoubleSemicolon)) => {
+                    self.tokens.next();
+                    self.skip_newlines();
+                }
+                Some((_, Token::Word("esac"))) => {}
+                Some((span, _)) => return Err(Error::Syntax(self.line[span.clone()].to_string())),
+                None => return Err(Error::Incomplete),
+            }
+        }
+
+        Ok(Expression::Case(Box::new(Case { word, items })))
+    }
+
+    // A word that's part of the syntax of a compound command
+    fn word(&mut self) -> Result<&'a str, Error> {
+        match self.tokens.next() {
+            Some((_, Token::Word(word))) => {
+                self.check_substitutions(word)?;
+                Ok(word)
+            }
+            Some((span, _)) => Err(Error::Syntax(self.line[span].to_string())),
+            None => Err(Error::Incomplete),
+        }
+    }
+
+    fn skip_newlines(&mut self) {
+        while let Some((_, Token::Newline)) = self.tokens.peek() {
+            self.tokens.next();
+        }
+    }
+
+    // The keyword closing a list, which is all there can be after the list
+    fn expect(&mut self, keyword: &str) -> Result<(), Error> {
+        match self.tokens.next() {
+            Some((_, Token::Word(word))) if word == keyword => Ok(()),
+            Some((span, _)) => Err(Error::Syntax(self.line[span].to_string())),
+            None => Err(Error::Incomplete),
+        }
+    }
+
+    // `assert` followed by a condition, otherwise it's a command like any other
+    fn at_assert(&self) -> bool {
+        let mut ahead = self.tokens.clone();
+
+        matches!(
+            (ahead.next(), ahead.next()),
+            (
+                Some((_, Token::Word("assert"))),
+                Some((_, Token::Cond(_))) | Some((_, Token::Arith(_)))
+            )
+        )
+    }
+
+    // assert := 'assert' ('[[' expr ']]' | '((' expr '))') word*
+    fn parse_assert(&mut self) -> Result<Expression<'a>, Error> {
+        self.tokens.next();
+        let (span, cond) = match self.tokens.next() {
+            Some((span, Token::Cond(expr))) => (span, Expression::Cond(expr)),
+            Some((span, Token::Arith(expr))) => (span, Expression::Arith(expr)),
+            _ => unreachable!(),
+        };
+        if let Expression::Cond(expr) | Expression::Arith(expr) = cond {
+            self.check_substitutions(expr)?;
+        }
+
+        let mut message = vec![];
+        while let Some((_, Token::Word(word))) = self.tokens.peek() {
+            let word = *word;
+            self.tokens.next();
+            self.check_substitutions(word)?;
+            message.push(word);
+        }
+
+        Ok(Expression::Assert(Box::new(Assert {
+            cond,
+            text: &self.line[span],
+            message,
+        })))
+    }
+
+    // A word followed by `()`
+    fn at_function(&self) -> bool {
+        let mut ahead = self.tokens.clone();
+
+        matches!(
+            (ahead.next(), ahead.next(), ahead.next()),
+            (
+                Some((_, Token::Word(_))),
+                Some((_, Token::LParen)),
+                Some((_, Token::RParen))
+            )
+        )
+    }
+
+    // function := word '(' ')' newline* ('{' list '}' | '(' list ')')
+    fn parse_function(&mut self) -> Result<Expression<'a>, Error> {
+        let name = match self.tokens.next() {
+            Some((_, Token::Word(name))) if is_name(name) => name,
+            Some((_, Token::Word(name))) => return Err(Error::Syntax(name.to_string())),
+            _ => unreachable!(),
+        };
+        self.tokens.next();
+        self.tokens.next();
+
+        while let Some((_, Token::Newline)) = self.tokens.peek() {
+            self.tokens.next();
+        }
+
+        let start = match self.tokens.peek() {
+            Some((span, Token::Word("{"))) | Some((span, Token::LParen)) => span.start,
+            Some((span, _)) => return Err(Error::Syntax(self.line[span.clone()].to_string())),
+            None => return Err(Error::Incomplete),
+        };
+        self.parse_command()?;
+        let end = self
+            .tokens
+            .peek()
+            .map_or(self.line.len(), |(span, _)| span.start);
+
+        Ok(Expression::Function(Box::new(Function {
+            name,
+            body: self.line[start..end].trim_end(),
+            body_line: self.line_of(start),
+        })))
+    }
+
+    fn parse_nested(&mut self, terminator: Terminator) -> Result<Expression<'a>, Error> {
+        self.enter()?;
+        let list = self.parse_list(terminator);
+        self.depth -= 1;
+        list
+    }
+
+    fn enter(&mut self) -> Result<(), Error> {
+        if self.depth >= self.max_depth {
+            return Err(Error::TooDeep(self.max_depth));
+        }
+
+        self.depth += 1;
+        Ok(())
+    }
+
+    // Parse the body of every `$(...)` in the word so syntax errors and
+    // excessive nesting get reported before anything runs
+    fn check_substitutions(&mut self, word: &str) -> Result<(), Error> {
+        let mut i = 0;
+
+        while i < word.len() {
+            let rest = &word[i..];
+
+            if let Some(body) = rest.strip_prefix("$(") {
+                let len = find_closing_paren(body)?;
+
+                if let Some(expr) = arithmetic_body(&body[..len]) {
+                    self.check_substitutions(expr)?;
+                    i += 2 + len + 1;
+                    continue;
+                }
+
+                self.enter()?;
+                let body = &body[..len];
+
+                match Parser::new(body, self.depth, self.max_depth)?.parse_list(Terminator::End) {
+                    Ok(_) | Err(Error::EmptyLine) => {}
+                    Err(e) => return Err(e),
+                }
+
+                self.depth -= 1;
+                i += 2 + len + 1;
+            } else if rest.starts_with('\'') {
+                i = skip_quoted(word, i)?;
+            } else {
+                i += rest.chars().next().map_or(1, char::len_utf8);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> Expression<'a> {
+    pub fn parse(line: &'a str, max_depth: usize) -> Result<Self, Error> {
+        let mut parser = Parser::new(line, 0, max_depth)?;
+        let expr = parser.parse_list(Terminator::End)?;
+
+        match parser.tokens.next() {
+            None => Ok(expr),
+            Some((span, _)) => Err(Error::Syntax(line[span].to_string())),
+        }
+    }
+
+    fn build_semicolon_expression(mut exprs: IntoIter<Self>) -> Self {
+        assert!(exprs.len() >= 1);
+        let expr_left = exprs.next().unwrap();
+
+        if exprs.len() == 0 {
+            expr_left
+        } else {
+            Expression::Compound(Box::new(Compound {
+                op: Op::Semicolon,
+                left: expr_left,
+                right: Expression::build_semicolon_expression(exprs),
+            }))
+        }
+    }
+}
+
+impl<'a> Redirection<'a> {
+    pub(super) fn new(op: &str, target: &'a str) -> Self {
+        let digits = op.bytes().take_while(u8::is_ascii_digit).count();
+        let kind = match &op[digits..] {
+            "<" => Redirect::Read,
+            ">" => Redirect::Write,
+            ">>" => Redirect::Append,
+            "&>" => Redirect::Both,
+            "&>>" => Redirect::BothAppend,
+            _ => Redirect::Dup,
+        };
+
+        let fd = match op[..digits].parse() {
+            Ok(fd) => fd,
+            Err(_) if op.starts_with('<') => 0,
+            Err(_) => 1,
+        };
+
+        Redirection { fd, kind, target }
+    }
+}
+
+// The name and the key of `name[key]`
+pub fn subscript(name: &str) -> Option<(&str, &str)> {
+    let (name, key) = name.strip_suffix(']')?.split_once('[')?;
+
+    match is_name(name) {
+        true => Some((name, key)),
+        false => None,
+    }
+}
+
+// Where the `=` of `name=value` or `name[key]=value` is, when the word is an
+// assignment
+pub fn assignment_eq(word: &str) -> Option<usize> {
+    let eq = match word.find('[') {
+        Some(open) if is_name(&word[..open]) => open + word[open..].find("]=")? + 1,
+        _ => word.find('=')?,
+    };
+
+    match is_name(&word[..eq]) || subscript(&word[..eq]).is_some() {
+        true => Some(eq),
+        false => None,
+    }
+}
+
+pub fn is_name(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        _ => false,
+    }
+}
+
+// Whether the line leaves a group or a quote open so the next lines are part
+// of the same command
+pub fn is_incomplete(line: &str, shell: &Shell) -> bool {
+    // A backslash escaping the end of the line
+    let line_end = line.strip_suffix('\n').unwrap_or(line);
+    if (line_end.len() - line_end.trim_end_matches('\\').len()) % 2 == 1 {
+        return true;
+    }
+
+    matches!(
+        Expression::parse(&shell.expand_aliases(line), max_depth()),
+        Err(Error::Incomplete) | Err(Error::UnclosedQuote)
+    )
+}
+
+pub fn max_depth() -> usize {
+    env::var("RUSH_MAX_DEPTH")
+        .ok()
+        .and_then(|depth| depth.parse().ok())
+        .unwrap_or(DEFAULT_MAX_DEPTH)
+}
+
+impl<'a> TryFrom<&'a str> for Cmd<'a> {
+    type Error = Error;
+
+    // Extract the command and its arguments from the commandline
+    fn try_from(line: &'a str) -> Result<Self, Self::Error> {
+        let mut words = vec![];
+
+        for (_, token) in tokenize(line)? {
+            match token {
+                Token::Word(word) => words.push(word),
+                Token::Newline => {}
+                Token::Semicolon => return Err(Error::Syntax(String::from(";"))),
+                Token::DoubleSemicolon => return Err(Error::Syntax(String::from(";;"))),
+                Token::And => return Err(Error::Syntax(String::from("&&"))),
+                Token::Or => return Err(Error::Syntax(String::from("||"))),
+                Token::Background => return Err(Error::Syntax(String::from("&"))),
+                Token::Disown => return Err(Error::Syntax(String::from("&!"))),
+                Token::Redirect(op) => return Err(Error::Syntax(op.to_string())),
+                Token::Pipe => return Err(Error::Syntax(String::from("|"))),
+                Token::LParen | Token::Arith(_) => return Err(Error::Syntax(String::from("("))),
+                Token::Cond(_) => return Err(Error::Syntax(String::from("[["))),
+                Token::RParen => return Err(Error::Syntax(String::from(")"))),
+            }
+        }
+
+        Cmd::from_words(words)
+    }
+}
+
+impl<'a> Cmd<'a> {
+    fn from_words(mut words: Vec<&'a str>) -> Result<Self, Error> {
+        let count = words
+            .iter()
+            .take_while(|word| assignment_eq(word).is_some())
+            .count();
+
+        if count > 0 {
+            let assignments = words
+                .drain(..count)
+                .map(|word| {
+                    let eq = assignment_eq(word).unwrap();
+                    let (name, key) = match subscript(&word[..eq]) {
+                        Some((name, key)) => (name, Some(key)),
+                        None => (&word[..eq], None),
+                    };
+                    Assignment {
+                        name,
+                        key,
+                        value: &word[eq + 1..],
+                    }
+                })
+                .collect();
+
+            return if words.is_empty() {
+                Ok(Cmd::Assign(assignments))
+            } else {
+                Ok(Cmd::WithEnv(assignments, Box::new(Cmd::from_words(words)?)))
+            };
+        }
+
+        let mut args = LineIter(words.into_iter());
+        let binary = args.next().map(OsStr::new).ok_or(Error::EmptyLine)?;
+
+        match binary.to_str() {
+            Some(_) => Ok(Cmd::Invoke(Invoke { binary, args })),
+            None => Err(Error::NoCmd),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::assertions_on_constants)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_empty_line() {
+        match Cmd::try_from("") {
+            Err(Error::EmptyLine) => assert!(true),
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn test_comments() {
+        assert!(matches!(
+            Cmd::try_from("#!/bin/rush"),
+            Err(Error::EmptyLine)
+        ));
+
+        if let Ok(Cmd::Invoke(Invoke { args, .. })) = Cmd::try_from("echo a#b '#' # rest") {
+            assert_eq!(args.collect::<Vec<_>>(), vec!["a#b", "'#'"]);
+        } else {
+            unreachable!();
+        }
+    }
+
+    #[test]
+    fn test_single_binary() {
+        let cmd = Cmd::try_from("echo").unwrap();
+
+        if let Cmd::Invoke(Invoke { binary, mut args }) = cmd {
+            assert_eq!(binary, OsStr::new("echo"));
+            assert_eq!(args.next(), None);
+        } else {
+            assert!(false);
+        }
+    }
+
+    #[test]
+    fn test_binary_with_arguments() {
+        let cmd = Cmd::try_from("echo 1 2 3").unwrap();
+
+        if let Cmd::Invoke(Invoke { binary, args }) = cmd {
+            assert_eq!(binary, OsStr::new("echo"));
+            assert_eq!(args.collect::<Vec<_>>(), vec!["1", "2", "3"]);
+        } else {
+            assert!(false);
+        }
+    }
+
+    #[test]
+    fn test_exit_builtin() {
+        let cmd = Cmd::try_from("exit").unwrap();
+
+        if let Cmd::Invoke(Invoke { binary, mut args }) = cmd {
+            assert_eq!(binary, OsStr::new("exit"));
+            assert_eq!(args.next(), None);
+        } else {
+            assert!(false);
+        }
+    }
+
+    #[test]
+    fn test_cd_builtin() {
+        let cmd = Cmd::try_from("cd /home").unwrap();
+
+        if let Cmd::Invoke(Invoke { binary, args }) = cmd {
+            assert_eq!(binary, OsStr::new("cd"));
+            assert_eq!(args.collect::<Vec<_>>(), vec!["/home"]);
+        } else {
+            assert!(false);
+        }
+    }
+
+    #[test]
+    fn test_env_builtin() {
+        let cmd = Cmd::try_from("env -i FOO=bar ls -l").unwrap();
+
+        if let Cmd::Invoke(Invoke { binary, args }) = cmd {
+            assert_eq!(binary, OsStr::new("env"));
+            assert_eq!(args.collect::<Vec<_>>(), vec!["-i", "FOO=bar", "ls", "-l"]);
+        } else {
+            unreachable!();
+        }
+    }
+
+    #[test]
+    fn test_quoted_arguments() {
+        let cmd = Cmd::try_from("alias ll='ls -la' x=\"a;b && c\" y\\ z").unwrap();
+
+        if let Cmd::Invoke(Invoke { args, .. }) = cmd {
+            assert_eq!(
+                args.collect::<Vec<_>>(),
+                vec!["ll='ls -la'", "x=\"a;b && c\"", "y\\ z"]
+            );
+        } else {
+            unreachable!();
+        }
+
+        match Cmd::try_from("echo 'unclosed") {
+            Err(Error::UnclosedQuote) => {}
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_nested_expressions() {
+        match Expression::try_from("(cd /tmp && ls); { echo $(echo hi); }").unwrap() {
+            Expression::Compound(compound) => match *compound {
+                Compound {
+                    op: Op::Semicolon,
+                    left: Expression::Subshell(_),
+                    right: Expression::Group(_),
+                } => {}
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        }
+
+        for line in &["(ls", "ls )", "{ ls }", "echo $(ls", "( )"] {
+            assert!(Expression::try_from(*line).is_err(), "{}", line);
+        }
+    }
+
+    #[test]
+    fn test_disowned() {
+        match Expression::try_from("make all &! ls") {
+            Ok(Expression::Compound(compound)) => match compound.left {
+                Expression::Disowned(forked) => assert_eq!(forked.text, "make all"),
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        }
+
+        assert!(Expression::try_from("&! ls").is_err());
+    }
+
+    #[test]
+    fn test_nesting_limit() {
+        // Spaced out since `((` starts an arithmetic command
+        let nested = |depth| "( ".repeat(depth) + "ls" + &" )".repeat(depth);
+
+        assert!(Expression::parse(&nested(8), 8).is_ok());
+        match Expression::parse(&nested(9), 8) {
+            Err(Error::TooDeep(8)) => {}
+            _ => unreachable!(),
+        }
+
+        match Expression::parse("echo $(echo $(echo $(ls)))", 2) {
+            Err(Error::TooDeep(2)) => {}
+            _ => unreachable!(),
+        }
+
+        match Expression::try_from("(".repeat(100_000).as_str()) {
+            Err(Error::TooDeep(DEFAULT_MAX_DEPTH)) => {}
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_assignments() {
+        match Cmd::try_from("a=1 b='x y'").unwrap() {
+            Cmd::Assign(assignments) => {
+                assert_eq!(assignments[0].name, "a");
+                assert_eq!(assignments[0].value, "1");
+                assert_eq!(assignments[1].name, "b");
+                assert_eq!(assignments[1].value, "'x y'");
+            }
+            _ => unreachable!(),
+        }
+
+        match Cmd::try_from("LANG=C ls a=b").unwrap() {
+            Cmd::WithEnv(assignments, cmd) => {
+                assert_eq!(assignments.len(), 1);
+                match *cmd {
+                    Cmd::Invoke(Invoke { binary, args }) => {
+                        assert_eq!(binary, OsStr::new("ls"));
+                        assert_eq!(args.collect::<Vec<_>>(), vec!["a=b"]);
+                    }
+                    _ => unreachable!(),
+                }
+            }
+            _ => unreachable!(),
+        }
+
+        match Cmd::try_from("=a 1a=b").unwrap() {
+            Cmd::Invoke(Invoke { binary, .. }) => assert_eq!(binary, OsStr::new("=a")),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_semicolon_expression() {
+        match Expression::try_from("echo 1 2 3; ls").unwrap() {
+            Expression::Compound(compound) => match *compound {
+                Compound {
+                    op: Op::Semicolon,
+                    left:
+                        Expression::Cmd(Cmd::Invoke(Invoke {
+                            binary: binary_left,
+                            args: mut args_left,
+                        })),
+
+                    right:
+                        Expression::Cmd(Cmd::Invoke(Invoke {
+                            binary: binary_right,
+                            args: mut args_right,
+                        })),
+                } => {
+                    assert_eq!(binary_left, OsStr::new("echo"));
+                    assert_eq!(args_left.next(), Some("1"));
+                    assert_eq!(args_left.next(), Some("2"));
+                    assert_eq!(args_left.next(), Some("3"));
+                    assert_eq!(args_left.next(), None);
+
+                    assert_eq!(binary_right, OsStr::new("ls"));
+                    assert_eq!(args_right.next(), None);
+                }
+
+                _ => assert!(false),
+            },
+
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn test_and_expression() {
+        match Expression::try_from("echo 1 2 3 && ls").unwrap() {
+            Expression::Compound(compound) => match *compound {
+                Compound {
+                    op: Op::And,
+                    left:
+                        Expression::Cmd(Cmd::Invoke(Invoke {
+                            binary: binary_left,
+                            args: mut args_left,
+                        })),
+
+                    right:
+                        Expression::Cmd(Cmd::Invoke(Invoke {
+                            binary: binary_right,
+                            args: mut args_right,
+                        })),
+                } => {
+                    assert_eq!(binary_left, OsStr::new("echo"));
+                    assert_eq!(args_left.next(), Some("1"));
+                    assert_eq!(args_left.next(), Some("2"));
+                    assert_eq!(args_left.next(), Some("3"));
+                    assert_eq!(args_left.next(), None);
+
+                    assert_eq!(binary_right, OsStr::new("ls"));
+                    assert_eq!(args_right.next(), None);
+                }
+
+                _ => assert!(false),
+            },
+
+            _ => assert!(false),
+        }
+    }
+}
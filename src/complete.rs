@@ -0,0 +1,556 @@
+// Tab completion: the word under the cursor is completed by the words given
+// with `complete -W` or the completer registered for the command it's an
+// argument of, or as a file name when that doesn't complete it. The first
+// word is completed as a command name
+
+mod commands;
+mod dirs;
+mod files;
+mod jobs;
+mod tools;
+mod values;
+mod variables;
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::bookmarks;
+use crate::cmd::is_name;
+use crate::shell::Shell;
+
+// What a completer gets to look at
+pub struct Context<'a> {
+    pub shell: &'a Shell,
+    // The words of the command before the one being completed, the command
+    // name first, with quotes removed
+    pub words: &'a [String],
+    // The partial word being completed
+    pub word: &'a str,
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Candidate {
+    pub text: String,
+    // Shown next to the candidate when they're listed
+    pub description: Option<String>,
+    // Put on the line as it is rather than escaped
+    pub verbatim: bool,
+}
+
+impl Candidate {
+    pub fn described(text: String, description: String) -> Self {
+        Candidate {
+            text,
+            description: Some(description),
+            verbatim: false,
+        }
+    }
+
+    pub fn verbatim(text: String) -> Self {
+        Candidate {
+            text,
+            description: None,
+            verbatim: true,
+        }
+    }
+
+    // The text, or the prefix of it the candidates share, as it goes on the
+    // line
+    pub fn escaped(&self, text: &str) -> String {
+        match self.verbatim {
+            true => text.to_string(),
+            false => escape(text),
+        }
+    }
+}
+
+impl From<String> for Candidate {
+    fn from(text: String) -> Self {
+        Candidate {
+            text,
+            description: None,
+            verbatim: false,
+        }
+    }
+}
+
+// Returns every candidate for the context, those not starting with the word
+// being completed are filtered out afterwards
+pub type Completer = fn(&Context) -> Vec<Candidate>;
+
+pub struct Completions {
+    completers: HashMap<String, Completer>,
+    // By the name of the variable whose value they complete
+    values: HashMap<String, Completer>,
+    // Those of `complete -W`, by command
+    words: BTreeMap<String, Vec<String>>,
+    executables: commands::Executables,
+}
+
+impl Completions {
+    pub fn register(&mut self, command: &str, completer: Completer) {
+        self.completers.insert(command.to_string(), completer);
+    }
+
+    pub fn register_value(&mut self, name: &str, completer: Completer) {
+        self.values.insert(name.to_string(), completer);
+    }
+
+    // The arguments of `command` are completed from `words` rather than by
+    // its completer
+    pub fn set_words(&mut self, command: &str, words: Vec<String>) {
+        self.words.insert(command.to_string(), words);
+    }
+
+    pub fn remove_words(&mut self, command: &str) -> bool {
+        self.words.remove(command).is_some()
+    }
+
+    pub fn words(&self) -> impl Iterator<Item = (&String, &Vec<String>)> {
+        self.words.iter()
+    }
+
+    // Sorted candidates for the last of `words`
+    pub fn complete(&self, shell: &Shell, words: &[String]) -> Vec<Candidate> {
+        let (word, words) = match words.split_last() {
+            Some(split) => split,
+            None => return vec![],
+        };
+
+        if let Some(candidates) = variables::variables(shell, word) {
+            return matching(candidates, word);
+        }
+
+        if let Some((name, value)) = assignment(words, word) {
+            let completer = match self.values.get(name) {
+                Some(completer) => completer,
+                None => return vec![],
+            };
+
+            let context = Context {
+                shell,
+                words,
+                word: value,
+            };
+            let mut candidates = matching(completer(&context), value);
+            for candidate in &mut candidates {
+                candidate.text = format!("{}={}", name, candidate.text);
+            }
+            return candidates;
+        }
+
+        let context = Context { shell, words, word };
+
+        if words.is_empty() && !word.contains('/') {
+            return matching(self.executables.commands(&context), word);
+        }
+
+        // Only the words given are offered, not files
+        if let Some(given) = words.first().and_then(|name| self.words.get(name)) {
+            return matching(plain(given.clone()), word);
+        }
+
+        let candidates = match words.first().and_then(|name| self.completers.get(name)) {
+            Some(completer) => matching(completer(&context), word),
+            None => vec![],
+        };
+
+        if !candidates.is_empty() {
+            return candidates;
+        }
+        matching(files::files(&context), word)
+    }
+}
+
+// The name and the partial value when the word is an assignment, before a
+// command or as an argument of `export` or `local`
+fn assignment<'a>(words: &[String], word: &'a str) -> Option<(&'a str, &'a str)> {
+    let (name, value) = word.split_once('=').filter(|(name, _)| is_name(name))?;
+    let is_assignment = |word: &String| word.split_once('=').is_some_and(|(n, _)| is_name(n));
+
+    match words.split_first() {
+        Some((first, _)) if first == "export" || first == "local" => Some((name, value)),
+        _ if words.iter().all(is_assignment) => Some((name, value)),
+        _ => None,
+    }
+}
+
+// The candidates starting with the word, sorted and without duplicates
+fn matching(candidates: Vec<Candidate>, word: &str) -> Vec<Candidate> {
+    let mut candidates: Vec<Candidate> = candidates
+        .into_iter()
+        .filter(|candidate| candidate.text.starts_with(word))
+        .collect();
+
+    candidates.sort();
+    candidates.dedup_by(|a, b| a.text == b.text);
+    candidates
+}
+
+impl Default for Completions {
+    fn default() -> Self {
+        let mut completions = Completions {
+            completers: HashMap::new(),
+            values: HashMap::new(),
+            words: BTreeMap::new(),
+            executables: commands::Executables::default(),
+        };
+
+        completions.register("make", |context| plain(tools::make(context)));
+        completions.register("cargo", |context| plain(tools::cargo(context)));
+        completions.register("git", |context| plain(tools::git(context)));
+        completions.register("ssh", |context| plain(tools::ssh(context)));
+        completions.register("scp", |context| plain(tools::scp(context)));
+        completions.register("rsync", |context| plain(tools::scp(context)));
+
+        for command in &["fg", "bg", "disown"] {
+            completions.register(command, jobs::jobs);
+        }
+        completions.register("kill", jobs::kill);
+
+        for command in &["cd", "pushd", "popd", "dirs"] {
+            completions.register(command, dirs::stack);
+        }
+        completions.register("wait", jobs::wait);
+        completions.register("run", bookmarks);
+        completions.register("save-cmd", bookmarks);
+
+        for name in &["EDITOR", "VISUAL", "PAGER", "BROWSER"] {
+            completions.register_value(name, values::programs);
+        }
+        for name in &[
+            "LANG",
+            "LANGUAGE",
+            "LC_ALL",
+            "LC_COLLATE",
+            "LC_CTYPE",
+            "LC_MESSAGES",
+        ] {
+            completions.register_value(name, values::locales);
+        }
+        for name in &["LC_MONETARY", "LC_NUMERIC", "LC_TIME"] {
+            completions.register_value(name, values::locales);
+        }
+        completions.register_value("SHELL", values::shells);
+        completions
+    }
+}
+
+// The names of the saved commands, described by the command
+fn bookmarks(context: &Context) -> Vec<Candidate> {
+    if context.words.len() > 1 && context.words[0] == "run" {
+        return vec![];
+    }
+
+    let path = match bookmarks::default_path() {
+        Some(path) => path,
+        None => return vec![],
+    };
+
+    bookmarks::load(&path)
+        .into_iter()
+        .map(|(name, command)| Candidate::described(name, command))
+        .collect()
+}
+
+fn plain(candidates: Vec<String>) -> Vec<Candidate> {
+    candidates.into_iter().map(Candidate::from).collect()
+}
+
+// Split the line up to the cursor into the words of the command being typed,
+// with quotes removed, along with where the last one starts. The last word is
+// empty when the line ends in blanks
+pub fn split(line: &str) -> (Vec<String>, usize) {
+    let mut words = vec![];
+    let mut word = String::new();
+    let mut start = 0;
+    let mut quote = None;
+    let mut chars = line.char_indices();
+
+    while let Some((i, c)) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"'), '\\') | (None, '\\') => {
+                if let Some((_, c)) = chars.next() {
+                    word.push(c);
+                }
+            }
+            (Some(_), c) => word.push(c),
+            (None, '\'') | (None, '"') => quote = Some(c),
+
+            (None, ' ') | (None, '\t') | (None, '\n') => {
+                if !word.is_empty() {
+                    words.push(word.split_off(0));
+                }
+                start = i + 1;
+            }
+
+            // A new command starts after an operator
+            (None, ';') | (None, '&') | (None, '|') | (None, '(') | (None, ')') => {
+                words.clear();
+                word.clear();
+                start = i + 1;
+            }
+
+            (None, c) => word.push(c),
+        }
+    }
+
+    words.push(word);
+    (words, start)
+}
+
+// Backslash the characters the shell would otherwise interpret, but for a
+// `~` at the start that stands for the home directory
+pub fn escape(word: &str) -> String {
+    let mut escaped = String::new();
+
+    for (i, c) in word.char_indices() {
+        if " \t\n'\"\\$&;|()<>*?#`".contains(c) || (c == '~' && i > 0) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+
+    escaped
+}
+
+// The candidates as listed below the line, one per line next to their
+// descriptions if they have any
+pub fn listing(candidates: &[Candidate], width: usize) -> String {
+    if candidates
+        .iter()
+        .all(|candidate| candidate.description.is_none())
+    {
+        let texts: Vec<String> = candidates.iter().map(|c| c.text.clone()).collect();
+        return columns(&texts, width);
+    }
+
+    let column = candidates
+        .iter()
+        .map(|c| c.text.chars().count())
+        .max()
+        .unwrap_or(0);
+    let mut out = String::new();
+
+    for candidate in candidates {
+        match &candidate.description {
+            Some(description) => out.push_str(&format!(
+                "{:<width$}  -- {}\n",
+                candidate.text,
+                description,
+                width = column
+            )),
+            None => out.push_str(&format!("{}\n", candidate.text)),
+        }
+    }
+
+    out
+}
+
+// Lay the candidates out in columns that fit the width
+fn columns(candidates: &[String], width: usize) -> String {
+    let column = candidates
+        .iter()
+        .map(|c| c.chars().count())
+        .max()
+        .unwrap_or(0)
+        + 2;
+    let per_row = (width / column).max(1);
+    let rows = candidates.len().div_ceil(per_row);
+    let mut out = String::new();
+
+    for row in 0..rows {
+        let mut line = String::new();
+
+        // Filled column by column, like `ls`
+        for candidate in candidates.iter().skip(row).step_by(rows) {
+            line.push_str(&format!("{:<width$}", candidate, width = column));
+        }
+
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+
+    out
+}
+
+// The longest prefix all the candidates share
+pub fn common_prefix(candidates: &[Candidate]) -> &str {
+    let first = match candidates.first() {
+        Some(first) => &first.text,
+        None => return "",
+    };
+
+    let mut len = first.len();
+
+    for candidate in candidates[1..].iter().map(|c| &c.text) {
+        len = first
+            .char_indices()
+            .zip(candidate.chars())
+            .take_while(|((_, a), b)| a == b)
+            .last()
+            .map_or(0, |((i, a), _)| i + a.len_utf8())
+            .min(len);
+    }
+
+    &first[..len]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn strings(words: &[&str]) -> Vec<String> {
+        words.iter().map(|word| word.to_string()).collect()
+    }
+
+    #[test]
+    fn test_split() {
+        assert_eq!(split("git che"), (strings(&["git", "che"]), 4));
+        assert_eq!(split("cd /tmp; make "), (strings(&["make", ""]), 14));
+        assert_eq!(
+            split("cat 'a b' my\\ fi"),
+            (strings(&["cat", "a b", "my fi"]), 10)
+        );
+    }
+
+    #[test]
+    fn test_values() {
+        let shell = Shell::new();
+        let mut completions = Completions::default();
+        completions.register_value("COLOR", |_| plain(strings(&["red", "green", "grey"])));
+
+        let complete = |words: &[&str]| -> Vec<String> {
+            let candidates = completions.complete(&shell, &strings(words));
+            candidates.into_iter().map(|c| c.text).collect()
+        };
+
+        assert_eq!(
+            complete(&["COLOR=g"]),
+            strings(&["COLOR=green", "COLOR=grey"])
+        );
+        assert_eq!(complete(&["A=1", "COLOR=r"]), strings(&["COLOR=red"]));
+        assert_eq!(complete(&["export", "COLOR="]).len(), 3);
+        assert!(complete(&["echo", "COLOR=r"]).is_empty());
+        assert!(complete(&["OTHER=r"]).is_empty());
+    }
+
+    #[test]
+    fn test_words() {
+        let shell = Shell::new();
+        let mut completions = Completions::default();
+        completions.set_words("cargo", strings(&["build", "bench", "test"]));
+        completions.set_words("deploy", strings(&["staging", "production"]));
+
+        let complete = |completions: &Completions, words: &[&str]| -> Vec<String> {
+            let candidates = completions.complete(&shell, &strings(words));
+            candidates.into_iter().map(|c| c.text).collect()
+        };
+
+        assert_eq!(
+            complete(&completions, &["cargo", "b"]),
+            strings(&["bench", "build"])
+        );
+        assert_eq!(
+            complete(&completions, &["deploy", "--x", "s"]),
+            strings(&["staging"])
+        );
+        assert!(complete(&completions, &["deploy", "/"]).is_empty());
+        assert!(completions.remove_words("deploy"));
+        assert!(!completions.remove_words("deploy"));
+        assert_eq!(completions.words().count(), 1);
+    }
+
+    #[test]
+    fn test_variables() {
+        let mut shell = Shell::new();
+        shell.vars.insert("RUSH_ONE".into(), "1".into());
+        shell.vars.insert("RUSH_TWO".into(), "2".into());
+        let completions = Completions::default();
+
+        let complete = |words: &[&str]| -> Vec<String> {
+            let candidates = completions.complete(&shell, &strings(words));
+            candidates.into_iter().map(|c| c.text).collect()
+        };
+
+        assert_eq!(
+            complete(&["echo", "$RUSH_"]),
+            strings(&["$RUSH_ONE", "$RUSH_TWO"])
+        );
+        assert_eq!(complete(&["echo", "a${RUSH_O"]), strings(&["a${RUSH_ONE}"]));
+        assert_eq!(complete(&["x=$RUSH_T"]), strings(&["x=$RUSH_TWO"]));
+        assert!(complete(&["$HOM"]).contains(&String::from("$HOME")));
+        assert!(complete(&["echo", "$RUSH_-"]).is_empty());
+    }
+
+    #[test]
+    fn test_files() {
+        let shell = Shell::new();
+        let completions = Completions::default();
+        let dir = std::env::temp_dir().join(format!("rush-files-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("sub dir")).unwrap();
+        std::fs::write(dir.join("some file"), "").unwrap();
+        std::fs::write(dir.join(".hidden"), "").unwrap();
+        let dir = dir.to_str().unwrap();
+
+        let complete = |words: &[&str]| -> Vec<String> {
+            let candidates = completions.complete(&shell, &strings(words));
+            candidates.into_iter().map(|c| c.text).collect()
+        };
+
+        let typed = format!("{}/s", dir);
+        assert_eq!(
+            complete(&["cat", &typed]),
+            vec![format!("{}/some file", dir), format!("{}/sub dir/", dir)]
+        );
+        let typed = format!("{}/.", dir);
+        assert_eq!(complete(&["cat", &typed]), vec![format!("{}/.hidden", dir)]);
+        let typed = format!("{}/", dir);
+        assert_eq!(complete(&[&typed]).len(), 2);
+        assert!(complete(&["no-such-command-prefix"]).is_empty());
+        assert!(complete(&["s"]).contains(&String::from("sh")));
+
+        assert_eq!(escape("~/a b~"), "~/a\\ b\\~");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_commands() {
+        let mut shell = Shell::new();
+        shell.aliases.insert("rush-ll".into(), "ls -l".into());
+        shell.functions.insert("rush-fn".into(), "{ :; }".into());
+        let completions = Completions::default();
+
+        let complete = |words: &[&str]| -> Vec<String> {
+            let candidates = completions.complete(&shell, &strings(words));
+            candidates.into_iter().map(|c| c.text).collect()
+        };
+
+        assert_eq!(complete(&["rush-"]), strings(&["rush-fn", "rush-ll"]));
+        assert!(complete(&["ali"]).contains(&String::from("alias")));
+        assert!(complete(&["strf"]).contains(&String::from("strftime")));
+        assert_eq!(complete(&["sh"]).iter().filter(|c| *c == "sh").count(), 1);
+    }
+
+    #[test]
+    fn test_common_prefix() {
+        let prefix = |words: &[&str]| common_prefix(&plain(strings(words))).to_string();
+
+        assert_eq!(prefix(&["build", "bench", "b"]), "b");
+        assert_eq!(prefix(&["check", "checkout"]), "check");
+        assert_eq!(prefix(&["añob", "añoc"]), "año");
+        assert_eq!(prefix(&[]), "");
+    }
+
+    #[test]
+    fn test_columns() {
+        let candidates = strings(&["a", "bb", "c", "d", "e"]);
+        assert_eq!(columns(&candidates, 8), "a   d\nbb  e\nc\n");
+
+        let candidates = vec![
+            Candidate::described(String::from("%1"), String::from("sleep 10")),
+            Candidate::described(String::from("%12"), String::from("vim")),
+        ];
+        assert_eq!(listing(&candidates, 80), "%1   -- sleep 10\n%12  -- vim\n");
+    }
+}
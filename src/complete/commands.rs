@@ -0,0 +1,67 @@
+// Completion of command names, for the first word of a command
+
+use std::cell::RefCell;
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::time::SystemTime;
+
+use super::{Candidate, Context};
+use crate::path;
+
+// The names of the executables on PATH, scanned again only when PATH or one
+// of its directories changed since the last time
+#[derive(Default)]
+pub struct Executables {
+    scan: RefCell<Scan>,
+}
+
+#[derive(Default)]
+struct Scan {
+    path: Option<OsString>,
+    modified: Vec<Option<SystemTime>>,
+    names: Vec<String>,
+}
+
+impl Executables {
+    // The builtins, aliases and functions along with the executables
+    pub fn commands(&self, context: &Context) -> Vec<Candidate> {
+        let shell = context.shell;
+        let mut candidates: Vec<Candidate> = shell
+            .builtins
+            .names()
+            .map(String::from)
+            .chain(shell.aliases.keys().cloned())
+            .chain(shell.functions.keys().cloned())
+            .filter(|name| name.starts_with(context.word))
+            .map(Candidate::from)
+            .collect();
+
+        let path = env::var_os("PATH");
+        let modified: Vec<Option<SystemTime>> = path
+            .iter()
+            .flat_map(env::split_paths)
+            .map(|dir| fs::metadata(dir).and_then(|meta| meta.modified()).ok())
+            .collect();
+
+        let mut scan = self.scan.borrow_mut();
+        if scan.path != path || scan.modified != modified {
+            let mut names = path::executables_with_prefix("");
+            names.sort();
+            names.dedup();
+            *scan = Scan {
+                path,
+                modified,
+                names,
+            };
+        }
+
+        candidates.extend(
+            scan.names
+                .iter()
+                .filter(|name| name.starts_with(context.word))
+                .map(|name| Candidate::from(name.clone())),
+        );
+        candidates
+    }
+}
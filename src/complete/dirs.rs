@@ -0,0 +1,23 @@
+// Completion of directory stack entries for `cd`, `pushd`, `popd` and `dirs`
+
+use super::{Candidate, Context};
+use crate::dirs;
+
+// `+n` or `-n` for every entry, as numbered by `dirs -v`, described by the
+// directory
+pub fn stack(context: &Context) -> Vec<Candidate> {
+    let full = context.shell.dirs.full();
+    let last = full.len() - 1;
+
+    full.iter()
+        .enumerate()
+        .filter_map(|(i, dir)| {
+            let text = match context.word.chars().next() {
+                Some('+') => format!("+{}", i),
+                Some('-') => format!("-{}", last - i),
+                _ => return None,
+            };
+            Some(Candidate::described(text, dirs::tilde(dir)))
+        })
+        .collect()
+}
@@ -0,0 +1,48 @@
+// Completion of file names, for the words nothing else completes
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use super::{Candidate, Context};
+
+// The entries of the directory the word is in starting with the rest of it,
+// directories with a `/` after them. Hidden ones only when the word starts
+// with a dot, and a `~` at the start stands for the home directory
+pub fn files(context: &Context) -> Vec<Candidate> {
+    let (dir, prefix) = match context.word.rfind('/') {
+        Some(slash) => context.word.split_at(slash + 1),
+        None => ("", context.word),
+    };
+
+    let path = match dir.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => match env::var_os("HOME") {
+            Some(home) => PathBuf::from(home).join(rest.trim_start_matches('/')),
+            None => return vec![],
+        },
+        _ if dir.is_empty() => PathBuf::from("."),
+        _ => PathBuf::from(dir),
+    };
+
+    let entries = match fs::read_dir(&path) {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+
+    entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            if !name.starts_with(prefix) || (name.starts_with('.') && !prefix.starts_with('.')) {
+                return None;
+            }
+
+            // Following links, a link to a directory is completed like one
+            let slash = match fs::metadata(entry.path()) {
+                Ok(meta) if meta.is_dir() => "/",
+                _ => "",
+            };
+            Some(Candidate::from(format!("{}{}{}", dir, name, slash)))
+        })
+        .collect()
+}
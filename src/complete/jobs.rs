@@ -0,0 +1,64 @@
+// Completion of job specs and process ids for the builtins taking them
+
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+
+use super::{Candidate, Context};
+use crate::sys;
+
+// `%n` for every job, described by its command
+pub fn jobs(context: &Context) -> Vec<Candidate> {
+    context
+        .shell
+        .jobs
+        .iter()
+        .map(|job| Candidate::described(format!("%{}", job.id), job.cmdline.clone()))
+        .collect()
+}
+
+// Jobs and any process of the user, described by its name
+pub fn kill(context: &Context) -> Vec<Candidate> {
+    if context.word.starts_with('%') {
+        return jobs(context);
+    }
+
+    let uid = unsafe { sys::getuid() };
+    let mut candidates = jobs(context);
+
+    let entries = match fs::read_dir("/proc") {
+        Ok(entries) => entries,
+        Err(_) => return candidates,
+    };
+
+    for entry in entries.flatten() {
+        let pid = entry.file_name().to_string_lossy().into_owned();
+
+        if !pid.bytes().all(|b| b.is_ascii_digit())
+            || !entry.metadata().is_ok_and(|meta| meta.uid() == uid)
+        {
+            continue;
+        }
+
+        if let Ok(name) = fs::read_to_string(entry.path().join("comm")) {
+            candidates.push(Candidate::described(pid, name.trim_end().to_string()));
+        }
+    }
+
+    candidates
+}
+
+// Only children of the shell can be waited for, so jobs and their processes
+pub fn wait(context: &Context) -> Vec<Candidate> {
+    let mut candidates = jobs(context);
+
+    for job in context.shell.jobs.iter() {
+        for process in &job.processes {
+            candidates.push(Candidate::described(
+                process.pid.to_string(),
+                job.cmdline.clone(),
+            ));
+        }
+    }
+
+    candidates
+}
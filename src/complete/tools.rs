@@ -0,0 +1,416 @@
+// Completers for a few tools common enough to be worth knowing out of the box
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use super::Context;
+use crate::path;
+
+// The arguments given so far after the command name, options left out
+fn operands<'a>(context: &'a Context) -> Vec<&'a str> {
+    context.words[1..]
+        .iter()
+        .map(String::as_str)
+        .filter(|word| !word.starts_with('-'))
+        .collect()
+}
+
+fn previous<'a>(context: &'a Context) -> &'a str {
+    context.words.last().map_or("", String::as_str)
+}
+
+// Lines of the command's output, nothing if it fails
+pub fn output_lines(program: &str, args: &[&str]) -> Vec<String> {
+    let output = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output();
+
+    match output {
+        Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(String::from)
+            .collect(),
+        _ => vec![],
+    }
+}
+
+pub fn make(_: &Context) -> Vec<String> {
+    ["GNUmakefile", "makefile", "Makefile"]
+        .iter()
+        .find_map(|name| fs::read_to_string(name).ok())
+        .map_or_else(Vec::new, |makefile| make_targets(&makefile))
+}
+
+// Explicit targets of the rules in a makefile, pattern rules and special
+// targets like `.PHONY` aren't something to type
+fn make_targets(makefile: &str) -> Vec<String> {
+    let mut targets = vec![];
+
+    for line in makefile.lines() {
+        if line.starts_with('\t') || line.trim_start().starts_with('#') {
+            continue;
+        }
+
+        let colon = match line.find(':') {
+            Some(colon) => colon,
+            None => continue,
+        };
+
+        // `NAME := value` and `NAME = a:b` are assignments
+        if line[colon + 1..].starts_with('=') || line[..colon].contains('=') {
+            continue;
+        }
+
+        targets.extend(
+            line[..colon]
+                .split_whitespace()
+                .filter(|target| !target.starts_with('.') && !target.contains(['%', '$']))
+                .map(String::from),
+        );
+    }
+
+    targets
+}
+
+const CARGO_COMMANDS: &[&str] = &[
+    "add",
+    "bench",
+    "build",
+    "check",
+    "clean",
+    "clippy",
+    "doc",
+    "fetch",
+    "fix",
+    "fmt",
+    "init",
+    "install",
+    "metadata",
+    "new",
+    "package",
+    "publish",
+    "remove",
+    "run",
+    "rustc",
+    "search",
+    "test",
+    "tree",
+    "uninstall",
+    "update",
+    "vendor",
+];
+
+pub fn cargo(context: &Context) -> Vec<String> {
+    if matches!(previous(context), "--features" | "-F") {
+        return fs::read_to_string("Cargo.toml").map_or_else(|_| vec![], |t| cargo_features(&t));
+    }
+
+    if !operands(context).is_empty() {
+        return vec![];
+    }
+
+    // Subcommands installed as `cargo-name` binaries
+    let installed = path::executables_with_prefix("cargo-")
+        .into_iter()
+        .map(|name| name["cargo-".len()..].to_string());
+
+    CARGO_COMMANDS
+        .iter()
+        .map(|command| command.to_string())
+        .chain(installed)
+        .collect()
+}
+
+// The keys of the `[features]` table of a manifest
+fn cargo_features(manifest: &str) -> Vec<String> {
+    let mut features = vec![];
+    let mut in_features = false;
+
+    for line in manifest.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_features = line == "[features]";
+        } else if let (true, Some(eq)) = (in_features, line.find('=')) {
+            features.push(line[..eq].trim().trim_matches('"').to_string());
+        }
+    }
+
+    features
+}
+
+const GIT_COMMANDS: &[&str] = &[
+    "add",
+    "bisect",
+    "blame",
+    "branch",
+    "checkout",
+    "cherry-pick",
+    "clean",
+    "clone",
+    "commit",
+    "config",
+    "diff",
+    "fetch",
+    "grep",
+    "init",
+    "log",
+    "merge",
+    "mv",
+    "pull",
+    "push",
+    "rebase",
+    "reflog",
+    "remote",
+    "reset",
+    "restore",
+    "revert",
+    "rm",
+    "show",
+    "stash",
+    "status",
+    "switch",
+    "tag",
+    "worktree",
+];
+
+// Subcommands taking a branch, tag or other ref
+const GIT_REF_COMMANDS: &[&str] = &[
+    "branch",
+    "checkout",
+    "cherry-pick",
+    "diff",
+    "log",
+    "merge",
+    "rebase",
+    "reset",
+    "revert",
+    "show",
+    "switch",
+    "tag",
+];
+
+// Subcommands taking a remote and then a ref
+const GIT_REMOTE_COMMANDS: &[&str] = &["fetch", "pull", "push"];
+
+pub fn git(context: &Context) -> Vec<String> {
+    let operands = operands(context);
+
+    let subcommand = match operands.first() {
+        Some(subcommand) => *subcommand,
+        None => {
+            return GIT_COMMANDS
+                .iter()
+                .map(|command| command.to_string())
+                .chain(git_aliases())
+                .collect()
+        }
+    };
+
+    if GIT_REF_COMMANDS.contains(&subcommand) {
+        git_refs()
+    } else if GIT_REMOTE_COMMANDS.contains(&subcommand) {
+        match operands.len() {
+            1 => output_lines("git", &["remote"]),
+            _ => git_refs(),
+        }
+    } else if subcommand == "remote" && operands.len() >= 2 {
+        output_lines("git", &["remote"])
+    } else {
+        vec![]
+    }
+}
+
+fn git_refs() -> Vec<String> {
+    output_lines(
+        "git",
+        &[
+            "for-each-ref",
+            "--format=%(refname:short)",
+            "refs/heads",
+            "refs/remotes",
+            "refs/tags",
+        ],
+    )
+}
+
+fn git_aliases() -> Vec<String> {
+    output_lines(
+        "git",
+        &["config", "--name-only", "--get-regexp", "^alias\\."],
+    )
+    .into_iter()
+    .map(|name| name["alias.".len()..].to_string())
+    .collect()
+}
+
+// Options of ssh that take an argument
+const SSH_OPTIONS_WITH_ARGUMENT: &str = "BbcDEeFIiJLlmOoPpQRSWw";
+
+pub fn ssh(context: &Context) -> Vec<String> {
+    let mut args = context.words[1..].iter();
+
+    while let Some(arg) = args.next() {
+        match arg.strip_prefix('-') {
+            Some(option) if option.len() == 1 && SSH_OPTIONS_WITH_ARGUMENT.contains(option) => {
+                args.next();
+            }
+            Some(_) => {}
+            // Whatever follows the host is a command to run
+            None => return vec![],
+        }
+    }
+
+    // `user@host` completes the host part
+    let user = match context.word.find('@') {
+        Some(at) => &context.word[..=at],
+        None => "",
+    };
+
+    ssh_hosts()
+        .into_iter()
+        .map(|host| format!("{}{}", user, host))
+        .collect()
+}
+
+// Any argument of scp and rsync may be a `host:path`
+pub fn scp(context: &Context) -> Vec<String> {
+    if context.word.contains(':') || context.word.starts_with(['/', '.', '~']) {
+        return vec![];
+    }
+
+    ssh_hosts()
+        .into_iter()
+        .map(|host| format!("{}:", host))
+        .collect()
+}
+
+fn ssh_hosts() -> Vec<String> {
+    let dir = match env::var_os("HOME") {
+        Some(home) => PathBuf::from(home).join(".ssh"),
+        None => return vec![],
+    };
+
+    let mut hosts = vec![];
+
+    if let Ok(config) = fs::read_to_string(dir.join("config")) {
+        hosts.extend(ssh_config_hosts(&config));
+    }
+
+    if let Ok(known_hosts) = fs::read_to_string(dir.join("known_hosts")) {
+        hosts.extend(known_hosts_hosts(&known_hosts));
+    }
+
+    hosts
+}
+
+// The names given to `Host`, patterns can't be connected to
+fn ssh_config_hosts(config: &str) -> Vec<String> {
+    config
+        .lines()
+        .filter_map(|line| {
+            let mut words = line.split_whitespace();
+            match words.next() {
+                Some(keyword) if keyword.eq_ignore_ascii_case("host") => Some(words),
+                _ => None,
+            }
+        })
+        .flatten()
+        .filter(|host| !host.contains(['*', '?', '!']))
+        .map(String::from)
+        .collect()
+}
+
+// Hosts of a `known_hosts` file, hashed entries can't be told apart
+fn known_hosts_hosts(known_hosts: &str) -> Vec<String> {
+    let mut hosts = vec![];
+
+    for line in known_hosts.lines() {
+        let mut fields = line.split_whitespace();
+
+        let names = match fields.next() {
+            Some(marker) if marker.starts_with('@') => fields.next(),
+            names => names,
+        };
+
+        let names = match names {
+            Some(names) if !names.starts_with('#') && !names.starts_with('|') => names,
+            _ => continue,
+        };
+
+        for name in names.split(',') {
+            // `[host]:port` for hosts on a non-standard port
+            let name = match name.strip_prefix('[') {
+                Some(rest) => rest.split(']').next().unwrap_or(rest),
+                None => name,
+            };
+
+            if !name.contains(['*', '?', '!']) {
+                hosts.push(name.to_string());
+            }
+        }
+    }
+
+    hosts
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_make_targets() {
+        let makefile = "\
+CC := gcc
+FLAGS = -a:b
+.PHONY: all clean
+all: rush docs
+%.o: %.c
+\t$(CC) -c $<
+clean:
+\trm -rf target
+";
+        assert_eq!(make_targets(makefile), vec!["all", "clean"]);
+    }
+
+    #[test]
+    fn test_cargo_features() {
+        let manifest = "\
+[package]
+name = \"rush\"
+
+[features]
+default = [\"color\"]
+color = []
+
+[dependencies]
+libc = \"0.2\"
+";
+        assert_eq!(cargo_features(manifest), vec!["default", "color"]);
+    }
+
+    #[test]
+    fn test_ssh_hosts() {
+        let config = "\
+Host *
+    ServerAliveInterval 60
+Host build build.local
+    User ci
+host !bastion gw
+";
+        assert_eq!(ssh_config_hosts(config), vec!["build", "build.local", "gw"]);
+
+        let known_hosts = "\
+github.com,140.82.121.4 ssh-ed25519 AAAA
+|1|F1E1KeoE/eEWhi10WpGv4OdiO6Y=|3988QV0VE8wmZL7suNrYQLITLCg= ssh-rsa AAAA
+[git.example.org]:2222 ssh-rsa AAAA
+@cert-authority *.example.com ssh-rsa AAAA
+";
+        assert_eq!(
+            known_hosts_hosts(known_hosts),
+            vec!["github.com", "140.82.121.4", "git.example.org"]
+        );
+    }
+}
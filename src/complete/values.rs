@@ -0,0 +1,29 @@
+// Completion of the values of variables being assigned, like `EDITOR=`
+
+use std::fs;
+
+use super::tools::output_lines;
+use super::{plain, Candidate, Context};
+use crate::path;
+
+// The commands on PATH, for variables naming a program to run
+pub fn programs(context: &Context) -> Vec<Candidate> {
+    plain(path::executables_with_prefix(context.word))
+}
+
+// The locales installed, for `LANG` and the `LC_*` variables
+pub fn locales(_: &Context) -> Vec<Candidate> {
+    plain(output_lines("locale", &["-a"]))
+}
+
+// The login shells listed in `/etc/shells`
+pub fn shells(_: &Context) -> Vec<Candidate> {
+    let shells = fs::read_to_string("/etc/shells").unwrap_or_default();
+
+    shells
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| Candidate::from(line.to_string()))
+        .collect()
+}
@@ -0,0 +1,42 @@
+// Completion of the names of variables, for a word ending in a `$`
+// reference
+
+use std::env;
+
+use super::{escape, Candidate};
+use crate::cmd::is_name;
+use crate::shell::Shell;
+
+// The variables whose names start with the partial name after the last `$`
+// or `${` of the word, as the word completed with the reference. `None` when
+// the word doesn't end in one, or what comes before it would need escaping
+// as the candidates are put on the line as they are
+pub fn variables(shell: &Shell, word: &str) -> Option<Vec<Candidate>> {
+    let dollar = word.rfind('$')?;
+    let (braced, name) = match word[dollar + 1..].strip_prefix('{') {
+        Some(name) => (true, name),
+        None => (false, &word[dollar + 1..]),
+    };
+
+    let before = &word[..dollar];
+    if (!name.is_empty() && !is_name(name)) || escape(before) != before {
+        return None;
+    }
+
+    let names = shell
+        .vars
+        .keys()
+        .chain(shell.maps.keys())
+        .cloned()
+        .chain(env::vars_os().filter_map(|(name, _)| name.into_string().ok()))
+        .filter(|var| var.starts_with(name));
+
+    let candidates = names
+        .map(|var| match braced {
+            true => format!("{}${{{}}}", before, var),
+            false => format!("{}${}", before, var),
+        })
+        .map(Candidate::verbatim)
+        .collect();
+    Some(candidates)
+}
@@ -0,0 +1,375 @@
+// Conditions of `[[ ... ]]`: file tests, string and integer comparisons,
+// joined with `!`, `&&`, `||` and parentheses. The words are expanded but
+// not split, the operands of the integer comparisons are arithmetic and the
+// right of `==` and `!=` is a pattern unless quoted
+
+use std::fmt;
+use std::fs;
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
+
+use crate::arith::{self, ArithError};
+use crate::cmd::{self, Token};
+use crate::glob;
+use crate::shell::Shell;
+use crate::sys;
+
+#[derive(Debug, PartialEq)]
+pub enum CondError {
+    Syntax(String),
+    Arith(ArithError),
+    Unbound(String),
+}
+
+impl fmt::Display for CondError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CondError::Syntax(token) => write!(f, "{}", tr!("syntax error near `{}`", token)),
+            CondError::Arith(e) => write!(f, "{}", e),
+            CondError::Unbound(name) => write!(f, "{}", tr!("{}: unbound variable", name)),
+        }
+    }
+}
+
+const UNARY: &[&str] = &[
+    "-a", "-b", "-c", "-d", "-e", "-f", "-h", "-L", "-n", "-p", "-r", "-s", "-S", "-v", "-w", "-x",
+    "-z",
+];
+
+const BINARY: &[&str] = &[
+    "=", "==", "!=", "<", ">", "-eq", "-ne", "-lt", "-le", "-gt", "-ge", "-nt", "-ot",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Item<'a> {
+    // Still quoted, so a quoted `!` or `-f` is just a string
+    Word(&'a str),
+    Op(&'static str),
+}
+
+enum Node<'a> {
+    Not(Box<Node<'a>>),
+    And(Box<Node<'a>>, Box<Node<'a>>),
+    Or(Box<Node<'a>>, Box<Node<'a>>),
+    Unary(&'a str, &'a str),
+    Binary(&'a str, &'a str, &'a str),
+    // A word alone holds when it isn't empty
+    Word(&'a str),
+}
+
+pub fn eval(expr: &str, shell: &mut Shell) -> Result<bool, CondError> {
+    let items = items(expr)?;
+    let mut parser = Parser {
+        items: &items,
+        i: 0,
+    };
+    let node = parser.parse_or()?;
+
+    match parser.items.get(parser.i) {
+        None => node.eval(shell),
+        Some(item) => Err(CondError::Syntax(item.to_string())),
+    }
+}
+
+// The words and operators of the condition, `<` and `>` compare strings
+// rather than redirect
+fn items(expr: &str) -> Result<Vec<Item<'_>>, CondError> {
+    let tokens = cmd::tokenize(expr).map_err(|_| CondError::Syntax(expr.trim().to_string()))?;
+    let mut items = vec![];
+
+    for (span, token) in tokens {
+        items.push(match token {
+            Token::Word(word) => Item::Word(word),
+            Token::And => Item::Op("&&"),
+            Token::Or => Item::Op("||"),
+            Token::LParen => Item::Op("("),
+            Token::RParen => Item::Op(")"),
+            Token::Redirect("<") => Item::Word("<"),
+            Token::Redirect(">") => Item::Word(">"),
+            _ => return Err(CondError::Syntax(expr[span].to_string())),
+        });
+    }
+
+    Ok(items)
+}
+
+impl fmt::Display for Item<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Item::Word(word) => write!(f, "{}", word),
+            Item::Op(op) => write!(f, "{}", op),
+        }
+    }
+}
+
+struct Parser<'a, 'b> {
+    items: &'b [Item<'a>],
+    i: usize,
+}
+
+impl<'a, 'b> Parser<'a, 'b> {
+    fn peek(&self) -> Option<Item<'a>> {
+        self.items.get(self.i).copied()
+    }
+
+    fn word(&mut self) -> Result<&'a str, CondError> {
+        match self.peek() {
+            Some(Item::Word(word)) => {
+                self.i += 1;
+                Ok(word)
+            }
+            Some(item) => Err(CondError::Syntax(item.to_string())),
+            None => Err(CondError::Syntax(String::from("]]"))),
+        }
+    }
+
+    // or := and ('||' and)*
+    fn parse_or(&mut self) -> Result<Node<'a>, CondError> {
+        let mut node = self.parse_and()?;
+
+        while self.peek() == Some(Item::Op("||")) {
+            self.i += 1;
+            node = Node::Or(Box::new(node), Box::new(self.parse_and()?));
+        }
+        Ok(node)
+    }
+
+    // and := not ('&&' not)*
+    fn parse_and(&mut self) -> Result<Node<'a>, CondError> {
+        let mut node = self.parse_not()?;
+
+        while self.peek() == Some(Item::Op("&&")) {
+            self.i += 1;
+            node = Node::And(Box::new(node), Box::new(self.parse_not()?));
+        }
+        Ok(node)
+    }
+
+    // not := '!' not | '(' or ')' | unary word | word binary word | word
+    fn parse_not(&mut self) -> Result<Node<'a>, CondError> {
+        match self.peek() {
+            Some(Item::Word("!")) => {
+                self.i += 1;
+                Ok(Node::Not(Box::new(self.parse_not()?)))
+            }
+
+            Some(Item::Op("(")) => {
+                self.i += 1;
+                let node = self.parse_or()?;
+                match self.peek() {
+                    Some(Item::Op(")")) => {
+                        self.i += 1;
+                        Ok(node)
+                    }
+                    Some(item) => Err(CondError::Syntax(item.to_string())),
+                    None => Err(CondError::Syntax(String::from("("))),
+                }
+            }
+
+            Some(Item::Word(op)) if UNARY.contains(&op) => {
+                self.i += 1;
+                match self.peek() {
+                    Some(Item::Word(_)) => Ok(Node::Unary(op, self.word()?)),
+                    _ => Ok(Node::Word(op)),
+                }
+            }
+
+            Some(Item::Word(_)) => {
+                let left = self.word()?;
+                match self.peek() {
+                    Some(Item::Word(op)) if BINARY.contains(&op) => {
+                        self.i += 1;
+                        Ok(Node::Binary(op, left, self.word()?))
+                    }
+                    _ => Ok(Node::Word(left)),
+                }
+            }
+
+            Some(item) => Err(CondError::Syntax(item.to_string())),
+            None => Err(CondError::Syntax(String::from("]]"))),
+        }
+    }
+}
+
+impl<'a> Node<'a> {
+    fn eval(&self, shell: &mut Shell) -> Result<bool, CondError> {
+        match self {
+            Node::Not(node) => Ok(!node.eval(shell)?),
+            Node::And(left, right) => Ok(left.eval(shell)? && right.eval(shell)?),
+            Node::Or(left, right) => Ok(left.eval(shell)? || right.eval(shell)?),
+            Node::Word(word) => Ok(!expand(word, shell)?.is_empty()),
+
+            Node::Unary(op, word) => {
+                let word = expand(word, shell)?;
+                Ok(unary(op, &word, shell))
+            }
+
+            Node::Binary(op, left, right) => {
+                let left = expand(left, shell)?;
+                let right = match *op {
+                    "=" | "==" | "!=" => pattern(right, shell)?,
+                    _ => expand(right, shell)?,
+                };
+                binary(op, &left, &right, shell)
+            }
+        }
+    }
+}
+
+fn expand(word: &str, shell: &mut Shell) -> Result<String, CondError> {
+    shell.unbound = None;
+    let word = shell.expand_string(word);
+
+    match shell.unbound.take() {
+        Some(name) => Err(CondError::Unbound(name)),
+        None => Ok(word),
+    }
+}
+
+// The quoted parts of a pattern match themselves
+fn pattern(word: &str, shell: &mut Shell) -> Result<String, CondError> {
+    let parts = cmd::quoted_parts(word).map_err(|_| CondError::Syntax(word.to_string()))?;
+    let mut pattern = String::new();
+
+    for (part, quoted) in parts {
+        let expanded = expand(part, shell)?;
+        match quoted {
+            true => pattern.push_str(&glob::escape(&expanded)),
+            false => pattern.push_str(&expanded),
+        }
+    }
+    Ok(pattern)
+}
+
+fn unary(op: &str, word: &str, shell: &Shell) -> bool {
+    match op {
+        "-n" => !word.is_empty(),
+        "-z" => word.is_empty(),
+        "-v" => shell.lookup(word).is_some(),
+        "-r" => sys::can_access(word, sys::R_OK),
+        "-w" => sys::can_access(word, sys::W_OK),
+        "-x" => sys::can_access(word, sys::X_OK),
+        "-h" | "-L" => fs::symlink_metadata(word).is_ok_and(|meta| meta.file_type().is_symlink()),
+
+        _ => {
+            let meta = match fs::metadata(word) {
+                Ok(meta) => meta,
+                Err(_) => return false,
+            };
+            let kind = meta.file_type();
+
+            match op {
+                "-b" => kind.is_block_device(),
+                "-c" => kind.is_char_device(),
+                "-d" => kind.is_dir(),
+                "-f" => kind.is_file(),
+                "-p" => kind.is_fifo(),
+                "-S" => kind.is_socket(),
+                "-s" => meta.size() > 0,
+                // `-a` and `-e`
+                _ => true,
+            }
+        }
+    }
+}
+
+fn binary(op: &str, left: &str, right: &str, shell: &mut Shell) -> Result<bool, CondError> {
+    let number = |expr: &str, shell: &mut Shell| arith::eval(expr, shell).map_err(CondError::Arith);
+    let modified = |path: &str| fs::metadata(path).map(|meta| (meta.mtime(), meta.mtime_nsec()));
+
+    Ok(match op {
+        "=" | "==" => glob::matches(right, left),
+        "!=" => !glob::matches(right, left),
+        "<" => left < right,
+        ">" => left > right,
+        "-nt" => match (modified(left), modified(right)) {
+            (Ok(left), Ok(right)) => left > right,
+            (left, right) => left.is_ok() && right.is_err(),
+        },
+        "-ot" => match (modified(left), modified(right)) {
+            (Ok(left), Ok(right)) => left < right,
+            (left, right) => left.is_err() && right.is_ok(),
+        },
+
+        _ => {
+            let (left, right) = (number(left, shell)?, number(right, shell)?);
+
+            match op {
+                "-eq" => left == right,
+                "-ne" => left != right,
+                "-lt" => left < right,
+                "-le" => left <= right,
+                "-gt" => left > right,
+                _ => left >= right,
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn test_strings_and_numbers() {
+        let mut shell = Shell::new();
+        shell.vars.insert("x".into(), "5".into());
+        shell.vars.insert("empty".into(), "".into());
+
+        let mut eval = |expr| eval(expr, &mut shell);
+        assert_eq!(eval(" $x == 5 "), Ok(true));
+        assert_eq!(eval(" \"$x\" != 5 "), Ok(false));
+        assert_eq!(eval(" -z $empty && -n $x "), Ok(true));
+        assert_eq!(eval(" a < b || ! ( b > a ) "), Ok(true));
+        assert_eq!(eval(" $x -gt 3 && x+1 -eq 6 "), Ok(true));
+        assert_eq!(eval(" -v x && ! -v nope "), Ok(true));
+        assert_eq!(eval(" \"!\" "), Ok(true));
+        assert_eq!(eval(" -f "), Ok(true));
+        assert_eq!(eval(" 1 -lt "), Err(CondError::Syntax("]]".into())));
+        assert_eq!(eval(" a b "), Err(CondError::Syntax("b".into())));
+        assert!(matches!(eval(" 1 -eq 1/0 "), Err(CondError::Arith(_))));
+
+        shell.options.nounset = true;
+        assert_eq!(super::eval(" -z $x ", &mut shell), Ok(false));
+        assert_eq!(
+            super::eval(" -z $nope ", &mut shell),
+            Err(CondError::Unbound("nope".into()))
+        );
+    }
+
+    #[test]
+    fn test_patterns() {
+        let mut shell = Shell::new();
+        shell.vars.insert("p".into(), "a*".into());
+
+        let mut eval = |expr| eval(expr, &mut shell);
+        assert_eq!(eval(" abc == a* "), Ok(true));
+        assert_eq!(eval(" abc = a?[a-c] "), Ok(true));
+        assert_eq!(eval(" abc == \"a*\" "), Ok(false));
+        assert_eq!(eval(" 'a*' == \"a*\" "), Ok(true));
+        assert_eq!(eval(" 'a*' == a\\* "), Ok(true));
+        assert_eq!(eval(" x != ? "), Ok(false));
+        assert_eq!(eval(" abc == $p && abc != \"$p\" "), Ok(true));
+        assert_eq!(eval(" abc == a'*' "), Ok(false));
+    }
+
+    #[test]
+    fn test_files() {
+        let mut shell = Shell::new();
+        let dir = env::temp_dir().join(format!("rush-cond-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("file"), "x").unwrap();
+        fs::write(dir.join("empty"), "").unwrap();
+
+        let mut eval = |expr: &str| eval(&expr.replace("DIR", dir.to_str().unwrap()), &mut shell);
+        assert_eq!(eval(" -d DIR && ! -f DIR "), Ok(true));
+        assert_eq!(
+            eval(" -f DIR/file && -s DIR/file && -r DIR/file "),
+            Ok(true)
+        );
+        assert_eq!(eval(" -s DIR/empty || -e DIR/missing "), Ok(false));
+        assert_eq!(eval(" DIR/missing -ot DIR/file "), Ok(true));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
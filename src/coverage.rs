@@ -0,0 +1,137 @@
+// `rush --coverage script`: which lines of the script, and of the files it
+// sources, ran and how many times, reported as an annotated listing
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::fs;
+
+use crate::cmd::{tokenize, Token};
+use crate::shell::Location;
+
+#[derive(Default)]
+pub struct Coverage {
+    // Commands run on every line, by file
+    hits: HashMap<String, BTreeMap<usize, usize>>,
+}
+
+impl Coverage {
+    pub fn hit(&mut self, location: &Location) {
+        let lines = self.hits.entry(location.file.clone()).or_default();
+        *lines.entry(location.line).or_default() += 1;
+    }
+
+    pub fn report(self) -> String {
+        let mut files: Vec<(String, BTreeMap<usize, usize>)> = self.hits.into_iter().collect();
+        files.sort();
+
+        let mut report = String::new();
+
+        for (file, hits) in files {
+            let contents = fs::read_to_string(&file).unwrap_or_default();
+            let _ = writeln!(report, "{}", annotate(&file, &contents, &hits));
+        }
+
+        report
+    }
+}
+
+// Lines that ran are prefixed with their hit count, the ones with commands
+// that never ran with `#####` and those with nothing to run with `-`
+fn annotate(file: &str, contents: &str, hits: &BTreeMap<usize, usize>) -> String {
+    let mut covered = 0;
+    let mut total = 0;
+    let mut listing = String::new();
+
+    for (i, line) in contents.lines().enumerate() {
+        let count = if let Some(count) = hits.get(&(i + 1)) {
+            covered += 1;
+            total += 1;
+            count.to_string()
+        } else if runs_nothing(line) {
+            String::from("-")
+        } else {
+            total += 1;
+            String::from("#####")
+        };
+
+        let _ = writeln!(listing, "{:>9}: {:>4}: {}", count, i + 1, line);
+    }
+
+    let percent = if total == 0 {
+        100.0
+    } else {
+        covered as f64 * 100.0 / total as f64
+    };
+
+    format!(
+        "{}: {}\n{}",
+        file,
+        tr!(
+            "{} of {} lines run ({}%)",
+            covered,
+            total,
+            format!("{:.1}", percent)
+        ),
+        listing
+    )
+}
+
+// Whether a line is blank, a comment or only closes compound commands, like
+// `fi` or `done`
+fn runs_nothing(line: &str) -> bool {
+    let tokens = match tokenize(line) {
+        Ok(tokens) => tokens,
+        Err(_) => return false,
+    };
+
+    tokens.iter().all(|(_, token)| match token {
+        Token::Word(word) => ["then", "else", "fi", "do", "done", "esac", "{", "}"].contains(word),
+        Token::Semicolon | Token::DoubleSemicolon | Token::Newline | Token::RParen => true,
+        _ => false,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::env;
+
+    use super::*;
+    use crate::cmd::run_source;
+    use crate::shell::Shell;
+
+    #[test]
+    fn test_annotate() {
+        let mut hits = BTreeMap::new();
+        hits.insert(1, 2);
+        hits.insert(4, 1);
+
+        let listing = annotate("script", "echo a\n\nfalse && echo b\necho c\n", &hits);
+        let lines: Vec<&str> = listing.lines().collect();
+        assert_eq!(lines[0], "script: 2 of 3 lines run (66.7%)");
+        assert_eq!(lines[1], "        2:    1: echo a");
+        assert_eq!(lines[2], "        -:    2: ");
+        assert_eq!(lines[3], "    #####:    3: false && echo b");
+        assert_eq!(lines[4], "        1:    4: echo c");
+    }
+
+    #[test]
+    fn test_lines_run() {
+        let path = env::temp_dir().join(format!("rush-coverage-{}", std::process::id()));
+        let script = "f() {\n  true\n}\nfor ((i = 0; i < 2; i++)); do\n  f\ndone\n\
+                      if false; then\n  true\nelse\n  true\nfi\n";
+        fs::write(&path, script).unwrap();
+
+        let mut shell = Shell::new();
+        shell.coverage = Some(Coverage::default());
+        run_source(&path, vec![], &mut shell).unwrap();
+
+        let coverage = shell.coverage.take().unwrap();
+        let hits = &coverage.hits[&path.display().to_string()];
+        let expected = [(1, 1), (2, 2), (4, 1), (5, 2), (7, 1), (10, 1)];
+        assert_eq!(hits, &expected.iter().copied().collect());
+
+        let lines = annotate("script", script, hits);
+        assert!(lines.starts_with("script: 6 of 7 lines run"), "{}", lines);
+        fs::remove_file(&path).unwrap();
+    }
+}
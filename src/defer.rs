@@ -0,0 +1,175 @@
+// `async-source file` sources a file in a forked shell so the prompt doesn't
+// wait for it. Once done the child sends back the aliases, functions and
+// variables that changed, which are merged before the next prompt.
+// Each change is a `kind\tname\tlength` line followed by the value
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::os::unix::io::AsRawFd;
+
+use crate::shell::Shell;
+use crate::sys;
+
+pub struct Deferred {
+    pub pid: i32,
+    pub pipe: File,
+}
+
+pub struct Snapshot {
+    aliases: BTreeMap<String, String>,
+    functions: BTreeMap<String, String>,
+    vars: BTreeMap<String, String>,
+    env: BTreeMap<String, String>,
+}
+
+impl Snapshot {
+    pub fn take(shell: &Shell) -> Self {
+        Snapshot {
+            aliases: shell.aliases.clone(),
+            functions: shell.functions.clone(),
+            vars: shell.vars.clone(),
+            env: env::vars().collect(),
+        }
+    }
+
+    // What changed since the snapshot was taken, removals have no value
+    pub fn changes(&self, shell: &Shell) -> Vec<u8> {
+        let mut out = vec![];
+        let env = env::vars().collect();
+
+        diff(&mut out, "alias", &self.aliases, &shell.aliases);
+        diff(&mut out, "function", &self.functions, &shell.functions);
+        diff(&mut out, "var", &self.vars, &shell.vars);
+        diff(&mut out, "export", &self.env, &env);
+        out
+    }
+}
+
+fn diff(
+    out: &mut Vec<u8>,
+    kind: &str,
+    before: &BTreeMap<String, String>,
+    after: &BTreeMap<String, String>,
+) {
+    for (name, value) in after {
+        if before.get(name) != Some(value) {
+            let _ = write!(out, "{}\t{}\t{}\n{}", kind, name, value.len(), value);
+        }
+    }
+
+    for name in before.keys().filter(|name| !after.contains_key(*name)) {
+        let _ = writeln!(out, "-{}\t{}\t0", kind, name);
+    }
+}
+
+pub fn apply(changes: &str, shell: &mut Shell) {
+    let mut rest = changes;
+
+    while let Some(newline) = rest.find('\n') {
+        let mut fields = rest[..newline].splitn(3, '\t');
+        let (kind, name, len) = match (fields.next(), fields.next(), fields.next()) {
+            (Some(kind), Some(name), Some(len)) => (kind, name, len.parse().unwrap_or(0)),
+            _ => return,
+        };
+
+        let value = match rest.get(newline + 1..newline + 1 + len) {
+            Some(value) => value.to_string(),
+            None => return,
+        };
+        rest = &rest[newline + 1 + len..];
+
+        match kind {
+            "alias" => {
+                shell.aliases.insert(name.to_string(), value);
+            }
+            "function" => {
+                shell.functions.insert(name.to_string(), value);
+                shell.function_locations.remove(name);
+            }
+            "var" => {
+                shell.vars.insert(name.to_string(), value);
+            }
+            "export" => env::set_var(name, value),
+            "-alias" => {
+                shell.aliases.remove(name);
+            }
+            "-function" => {
+                shell.functions.remove(name);
+                shell.function_locations.remove(name);
+            }
+            "-var" => {
+                shell.vars.remove(name);
+            }
+            "-export" => env::remove_var(name),
+            _ => {}
+        }
+    }
+}
+
+// Merge the changes of the files that are done sourcing. The child only
+// writes once it's done, so when there's something to read the rest follows
+// right away
+pub fn merge_finished(shell: &mut Shell) {
+    let mut i = 0;
+
+    while i < shell.deferred.len() {
+        let mut fd = sys::PollFd {
+            fd: shell.deferred[i].pipe.as_raw_fd(),
+            events: sys::POLLIN,
+            revents: 0,
+        };
+
+        if unsafe { sys::poll(&mut fd, 1, 0) } <= 0 {
+            i += 1;
+            continue;
+        }
+
+        let mut deferred = shell.deferred.remove(i);
+        let mut changes = vec![];
+
+        if let Err(e) = deferred.pipe.read_to_end(&mut changes) {
+            eprintln!("rush: async-source: {}", e);
+        }
+
+        let mut raw = 0;
+        unsafe {
+            sys::waitpid(deferred.pid, &mut raw, 0);
+        }
+
+        apply(&String::from_utf8_lossy(&changes), shell);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_changes_round_trip() {
+        let mut shell = Shell::new();
+        shell.aliases.insert("gone".into(), "true".into());
+        shell.vars.insert("KEEP".into(), "same".into());
+
+        let snapshot = Snapshot::take(&shell);
+        shell.aliases.remove("gone");
+        shell.aliases.insert("ll".into(), "ls -l".into());
+        shell
+            .functions
+            .insert("f".into(), "echo a\n\techo b\n".into());
+        shell.vars.insert("NEW".into(), "x\ty".into());
+        let changes = snapshot.changes(&shell);
+
+        let mut other = Shell::new();
+        other.aliases.insert("gone".into(), "true".into());
+        other.vars.insert("KEEP".into(), "mine".into());
+        apply(&String::from_utf8(changes).unwrap(), &mut other);
+
+        assert_eq!(other.aliases.get("ll").unwrap(), "ls -l");
+        assert!(!other.aliases.contains_key("gone"));
+        assert_eq!(other.functions.get("f").unwrap(), "echo a\n\techo b\n");
+        assert_eq!(other.vars.get("NEW").unwrap(), "x\ty");
+        assert_eq!(other.vars.get("KEEP").unwrap(), "mine");
+    }
+}
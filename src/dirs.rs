@@ -0,0 +1,109 @@
+// The directory stack of `pushd`, `popd` and `dirs`. Entry 0 is always the
+// working directory, the stack only keeps the ones below it
+
+use std::env;
+use std::path::{Path, PathBuf};
+
+#[derive(Default)]
+pub struct DirStack {
+    entries: Vec<PathBuf>,
+}
+
+impl DirStack {
+    // Every entry, the working directory first
+    pub fn full(&self) -> Vec<PathBuf> {
+        let cwd = env::current_dir().unwrap_or_default();
+        let mut full = vec![cwd];
+        full.extend(self.entries.iter().cloned());
+        full
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    // `+n` counts from the working directory and `-n` from the bottom of the
+    // stack, `None` for anything else or entries past the end
+    pub fn index(&self, arg: &str) -> Option<usize> {
+        let len = self.entries.len() + 1;
+        let n: usize = arg.get(1..)?.parse().ok()?;
+
+        match arg.as_bytes()[0] {
+            b'+' if n < len => Some(n),
+            b'-' if n < len => Some(len - 1 - n),
+            _ => None,
+        }
+    }
+
+    // What the stack becomes when entry `n` is rotated to the top, the first
+    // entry of the result is the directory to change to
+    pub fn rotated(&self, n: usize) -> Vec<PathBuf> {
+        let mut full = self.full();
+        full.rotate_left(n);
+        full
+    }
+
+    // Set from a full stack once its first entry is the working directory
+    pub fn replace(&mut self, mut full: Vec<PathBuf>) {
+        full.remove(0);
+        self.entries = full;
+    }
+
+    pub fn push(&mut self, dir: PathBuf) {
+        self.entries.insert(0, dir);
+    }
+
+    // Remove entry `n`, 0 being the working directory, which `popd` can't
+    // remove without changing to the entry below it
+    pub fn remove(&mut self, n: usize) -> PathBuf {
+        self.entries.remove(n - 1)
+    }
+}
+
+// The home directory as `~`, as `dirs` shows entries without `-l`
+pub fn tilde(path: &Path) -> String {
+    let path = path.display().to_string();
+
+    if let Some(home) = env::var("HOME").ok().filter(|home| !home.is_empty()) {
+        if let Some(rest) = path.strip_prefix(&home) {
+            if rest.is_empty() || rest.starts_with('/') {
+                return format!("~{}", rest);
+            }
+        }
+    }
+
+    path
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_stack_addressing() {
+        let mut stack = DirStack::default();
+        stack.push(PathBuf::from("/c"));
+        stack.push(PathBuf::from("/b"));
+        stack.push(PathBuf::from("/a"));
+
+        assert_eq!(stack.index("+0"), Some(0));
+        assert_eq!(stack.index("+3"), Some(3));
+        assert_eq!(stack.index("-0"), Some(3));
+        assert_eq!(stack.index("-3"), Some(0));
+        assert_eq!(stack.index("+4"), None);
+        assert_eq!(stack.index("2"), None);
+        assert_eq!(stack.index("+"), None);
+
+        let rotated = stack.rotated(2);
+        assert_eq!(rotated[0], Path::new("/b"));
+        assert_eq!(rotated[1], Path::new("/c"));
+        assert_eq!(rotated[3], Path::new("/a"));
+
+        assert_eq!(stack.remove(2), Path::new("/b"));
+        assert_eq!(stack.full().len(), 3);
+    }
+}
@@ -0,0 +1,1559 @@
+// A line editor for interactive input: the terminal is put in raw mode while
+// a line is read, key presses are looked up in the shell's keymap and the
+// line is redrawn after every edit. A command left incomplete goes on in
+// more lines of the same buffer, after the `PS2` prompt
+
+mod highlight;
+mod keymap;
+mod keys;
+mod terminal;
+mod vi;
+
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, Write};
+use std::mem;
+use std::ops::Range;
+use std::process;
+
+pub use self::keymap::{Action, Binding, Keymap, ACTIONS, PRESETS};
+pub use self::keys::Key;
+pub use self::terminal::{columns, is_dumb};
+
+use self::terminal::RawMode;
+use self::vi::Vi;
+use crate::cmd::{self, tokenize, Token};
+use crate::complete;
+use crate::shell::Shell;
+
+// Read a line with the prompt in front of it, `None` at end of input. Ctrl-C
+// drops the line with an `Interrupted` error instead of killing the shell,
+// leaving the interrupt pending for a trap on it
+pub fn read_line(prompt: &str, shell: &mut Shell) -> io::Result<Option<String>> {
+    let buffer = shell.pushed_lines.pop().unwrap_or_default();
+    let mut kept = Kept {
+        cursor: buffer.len(),
+        buffer,
+        ..Kept::default()
+    };
+
+    loop {
+        let raw = RawMode::enable()?;
+        let mut editor = Editor::new(prompt, shell, String::new());
+        editor.resume(kept);
+        let mut read = editor.run();
+
+        // The line is left as typed with `^C` after it
+        if read
+            .as_ref()
+            .is_err_and(|e| e.kind() == io::ErrorKind::Interrupted)
+        {
+            editor.search_prompt = None;
+            editor.cursor = editor.buffer.len();
+            if let Err(e) = editor.refresh().and_then(|_| write_out("\x1b[J^C\r\n")) {
+                read = Err(e);
+            }
+        }
+
+        let pushed = mem::take(&mut editor.pushed);
+        let command = match read {
+            Ok(Read::Command(command)) => Some(command),
+            Ok(Read::Edit) => None,
+            read => {
+                shell.pushed_lines.extend(pushed);
+                return read.map(|read| match read {
+                    Read::Line(line) => Some(line),
+                    _ => None,
+                });
+            }
+        };
+
+        // What it runs gets the terminal as it was
+        kept = editor.keep();
+        shell.pushed_lines.extend(pushed);
+        drop(raw);
+        match command {
+            Some(command) => run_bound(&command, &mut kept, shell),
+            None => edit_externally(&mut kept, shell),
+        }
+    }
+}
+
+// Run a command bound to a key with the line and the cursor in
+// `READLINE_LINE` and `READLINE_POINT`, what it leaves there is the line
+// edited from then on. It doesn't change `$?`
+fn run_bound(command: &str, kept: &mut Kept, shell: &mut Shell) {
+    let status = shell.status;
+    shell.set_var("READLINE_LINE", kept.buffer.clone());
+    shell.set_var("READLINE_POINT", kept.cursor.to_string());
+
+    cmd::run_line(command, shell);
+
+    if let Some(line) = shell.lookup("READLINE_LINE") {
+        kept.replace(line);
+    }
+
+    kept.cursor = shell
+        .lookup("READLINE_POINT")
+        .and_then(|point| point.parse().ok())
+        .unwrap_or(kept.buffer.len())
+        .min(kept.buffer.len());
+    while !kept.buffer.is_char_boundary(kept.cursor) {
+        kept.cursor -= 1;
+    }
+
+    shell.unset_var("READLINE_LINE");
+    shell.unset_var("READLINE_POINT");
+    shell.status = status;
+}
+
+// Edit the line in `$VISUAL` or `$EDITOR`, vi without them, and take what's
+// saved unless it fails. It doesn't change `$?`
+fn edit_externally(kept: &mut Kept, shell: &mut Shell) {
+    let path = env::temp_dir().join(format!("rush-line-{}", process::id()));
+    if let Err(e) = fs::write(&path, format!("{}\n", kept.buffer)) {
+        cmd::report(format!("{}: {}", path.display(), e), shell);
+        return;
+    }
+
+    let editor = shell
+        .lookup("VISUAL")
+        .or_else(|| shell.lookup("EDITOR"))
+        .unwrap_or_else(|| String::from("vi"));
+    let status = shell.status;
+
+    // The words of the variable split, like `code -w`
+    let command = format!("{} {}", editor, cmd::quote(&path.display().to_string()));
+    if cmd::run_line(&command, shell) == Some(true) {
+        if let Ok(line) = fs::read_to_string(&path) {
+            kept.replace(line.trim_end_matches('\n').to_string());
+            kept.cursor = kept.buffer.len();
+        }
+    }
+
+    let _ = fs::remove_file(&path);
+    shell.status = status;
+}
+
+// What reading keys ended with
+enum Read {
+    Line(String),
+    Eof,
+    // The command bound to a key typed, run before the line goes on
+    Command(String),
+    // The line is edited in another editor, before going on with it
+    Edit,
+}
+
+// The words a token search goes through, the one typed first, and which of
+// them is shown where
+struct TokenSearch {
+    words: Vec<String>,
+    shown: usize,
+    range: Range<usize>,
+}
+
+// What's kept of the line being edited while a command bound to a key runs
+#[derive(Default)]
+struct Kept {
+    buffer: String,
+    cursor: usize,
+    killed: String,
+    undo: Vec<(String, usize)>,
+}
+
+impl Kept {
+    // Edit the line into `line`, which can be undone
+    fn replace(&mut self, line: String) {
+        if line != self.buffer {
+            let old = mem::replace(&mut self.buffer, line);
+            self.undo.push((old, self.cursor));
+        }
+    }
+}
+
+struct Editor<'a> {
+    prompt: &'a str,
+    // In front of the lines after the first one
+    continuation: String,
+    shell: &'a Shell,
+    buffer: String,
+    // Byte offset of the cursor in `buffer`
+    cursor: usize,
+    // The history entry being shown, the number of entries while the line is
+    // a new one
+    history_index: usize,
+    // The new line as it was typed, shown again when navigating back past the
+    // newest entry
+    draft: String,
+    // The text being searched for while the history is cycled by prefix
+    search_prefix: Option<String>,
+    // The history entry the last yanked argument came from and where it was
+    // inserted, so repeated yanks replace it with older ones
+    yank: Option<(usize, Range<usize>)>,
+    // The screen row the cursor was left on, relative to the prompt
+    cursor_row: usize,
+    // Lines put aside with push-line while this one was read
+    pushed: Vec<String>,
+    // Whether typing a quote or an opening bracket inserts its closing pair
+    // too, with `shopt -s autopair`
+    auto_pair: bool,
+    // Shown instead of the prompt during an incremental search
+    search_prompt: Option<String>,
+    // Keys read but not handled yet, the first one next
+    pending: Vec<Key>,
+    // Characters other than letters and digits that word motions treat as
+    // part of a word
+    word_chars: String,
+    vi: Vi,
+    // What the last kill deleted, for yanking it back
+    killed: String,
+    // The words of the history the one at the cursor is being replaced with
+    token_search: Option<TokenSearch>,
+    // The lines as they were before each edit, the last one first to undo
+    undo: Vec<(String, usize)>,
+}
+
+// How long a key bound on its own waits for the rest of a longer sequence
+// starting with it, in milliseconds
+const KEYSEQ_TIMEOUT: i32 = 500;
+
+// Like zsh's but without `/`, so paths are taken a directory at a time
+const DEFAULT_WORDCHARS: &str = "*?_-.[]~=&;!#$%^(){}<>";
+
+// The characters typed in pairs when auto pairing is on
+const PAIRS: &[(char, char)] = &[
+    ('(', ')'),
+    ('[', ']'),
+    ('{', '}'),
+    ('"', '"'),
+    ('\'', '\''),
+    ('`', '`'),
+];
+
+impl<'a> Editor<'a> {
+    fn new(prompt: &'a str, shell: &'a Shell, buffer: String) -> Self {
+        Editor {
+            prompt,
+            continuation: shell.lookup("PS2").unwrap_or_else(|| String::from("> ")),
+            shell,
+            cursor: buffer.len(),
+            buffer,
+            history_index: shell.history.len(),
+            draft: String::new(),
+            search_prefix: None,
+            yank: None,
+            cursor_row: 0,
+            pushed: vec![],
+            auto_pair: shell.shopts.autopair,
+            search_prompt: None,
+            pending: vec![],
+            word_chars: shell
+                .lookup("WORDCHARS")
+                .unwrap_or_else(|| DEFAULT_WORDCHARS.to_string()),
+            vi: Vi::default(),
+            killed: String::new(),
+            token_search: None,
+            undo: vec![],
+        }
+    }
+
+    fn keep(self) -> Kept {
+        Kept {
+            buffer: self.buffer,
+            cursor: self.cursor,
+            killed: self.killed,
+            undo: self.undo,
+        }
+    }
+
+    fn resume(&mut self, kept: Kept) {
+        self.buffer = kept.buffer;
+        self.cursor = kept.cursor;
+        self.killed = kept.killed;
+        self.undo = kept.undo;
+    }
+
+    fn run(&mut self) -> io::Result<Read> {
+        self.refresh()?;
+
+        loop {
+            let key = match self.next_key()? {
+                Some(key) => key,
+                None => return Ok(Read::Eof),
+            };
+
+            // Other keys are bound the same in vi's normal mode
+            if let (true, Key::Char(c)) = (self.vi.normal, key) {
+                self.undoable(|editor| editor.vi_command(c));
+                self.refresh()?;
+                continue;
+            }
+
+            let shell = self.shell;
+            let action = match shell.keymap.get(&self.read_sequence(key)?) {
+                Some(Binding::Action(action)) => Some(*action),
+
+                Some(Binding::Command(command)) => {
+                    self.leave_line()?;
+                    return Ok(Read::Command(command.clone()));
+                }
+
+                None => None,
+            };
+
+            if !matches!(
+                action,
+                Some(Action::HistorySearchBackward) | Some(Action::HistorySearchForward)
+            ) {
+                self.search_prefix = None;
+            }
+
+            if action != Some(Action::YankLastArg) {
+                self.yank = None;
+            }
+
+            if !matches!(
+                action,
+                Some(Action::HistoryTokenSearchBackward) | Some(Action::HistoryTokenSearchForward)
+            ) {
+                self.token_search = None;
+            }
+
+            match action {
+                Some(Action::AcceptLine) if cmd::is_incomplete(&self.buffer, self.shell) => {
+                    self.insert('\n')
+                }
+
+                Some(Action::AcceptLine) => {
+                    self.cursor = self.buffer.len();
+                    self.refresh()?;
+                    // Without the suggestion left after it
+                    write_out("\x1b[J\r\n")?;
+                    return Ok(Read::Line(mem::take(&mut self.buffer)));
+                }
+
+                Some(Action::DeleteCharOrEof) if self.buffer.is_empty() => {
+                    write_out("\r\n")?;
+                    return Ok(Read::Eof);
+                }
+
+                Some(Action::Complete) => self.undoable(Self::complete)?,
+
+                Some(Action::ReverseSearch) => self.reverse_search()?,
+
+                Some(Action::ClearScreen) => {
+                    write_out("\x1b[H\x1b[2J")?;
+                    self.cursor_row = 0;
+                }
+
+                Some(Action::Undo) => self.perform(Action::Undo),
+
+                Some(Action::EditCommandLine) => {
+                    self.leave_line()?;
+                    return Ok(Read::Edit);
+                }
+
+                Some(action) => self.undoable(|editor| editor.perform(action)),
+
+                None => match key {
+                    Key::Char(c) => self.undoable(|editor| editor.type_char(c)),
+                    _ => continue,
+                },
+            }
+
+            self.refresh()?;
+        }
+    }
+
+    // Go below the line for what runs before it's drawn again, the cursor
+    // staying where it is in it
+    fn leave_line(&mut self) -> io::Result<()> {
+        let cursor = self.cursor;
+        self.cursor = self.buffer.len();
+        self.refresh()?;
+        write_out("\x1b[J\r\n")?;
+        self.cursor = cursor;
+        Ok(())
+    }
+
+    fn next_key(&mut self) -> io::Result<Option<Key>> {
+        match self.pending.is_empty() {
+            true => keys::read(),
+            false => Ok(Some(self.pending.remove(0))),
+        }
+    }
+
+    // The keys of the longest sequence bound that starts with `key`, or just
+    // `key`. Those read past it are handled next, and one bound on its own
+    // that a longer one starts with waits a while for the rest
+    fn read_sequence(&mut self, key: Key) -> io::Result<Vec<Key>> {
+        let keymap = &self.shell.keymap;
+        let mut keys = vec![key];
+
+        while keymap.is_prefix(&keys) {
+            if keymap.get(&keys).is_some()
+                && self.pending.is_empty()
+                && !terminal::input_pending(KEYSEQ_TIMEOUT)
+            {
+                break;
+            }
+
+            match self.next_key()? {
+                Some(key) => keys.push(key),
+                None => break,
+            }
+        }
+
+        let bound = (1..=keys.len())
+            .rev()
+            .find(|&n| keymap.get(&keys[..n]).is_some())
+            .unwrap_or(1);
+        self.pending.splice(0..0, keys.split_off(bound));
+        Ok(keys)
+    }
+
+    fn perform(&mut self, action: Action) {
+        match action {
+            Action::AcceptLine
+            | Action::Complete
+            | Action::ReverseSearch
+            | Action::ClearScreen
+            | Action::EditCommandLine => {}
+
+            Action::BackwardChar => self.cursor = self.prev_boundary(),
+
+            // At the end of the line they take the suggestion
+            Action::ForwardChar | Action::EndOfLine if self.suggestion().is_some() => {
+                let suggestion = self.suggestion().unwrap_or_default();
+                self.buffer.push_str(suggestion);
+                self.cursor = self.buffer.len();
+            }
+
+            // Or only its next word
+            Action::ForwardWord if self.suggestion().is_some() => {
+                let suggestion = self.suggestion().unwrap_or_default();
+                let start = suggestion.len() - suggestion.trim_start().len();
+                let end = suggestion[start..]
+                    .find(char::is_whitespace)
+                    .map_or(suggestion.len(), |i| start + i);
+                self.buffer.push_str(&suggestion[..end]);
+                self.cursor = self.buffer.len();
+            }
+
+            Action::ForwardChar => self.cursor = self.next_boundary(),
+            Action::BackwardWord => self.cursor = self.prev_word(),
+            Action::ForwardWord => self.cursor = self.next_word(),
+            Action::BeginningOfLine => self.cursor = self.line_start(),
+            Action::EndOfLine => self.cursor = self.line_end(),
+
+            Action::BackwardDeleteChar => {
+                let start = self.prev_boundary();
+                let mut end = self.cursor;
+
+                // Deleting the opening half of an empty pair deletes both
+                if self.auto_pair {
+                    let typed = self.buffer[start..self.cursor].chars().next();
+                    let next = self.buffer[self.cursor..].chars().next();
+
+                    if PAIRS
+                        .iter()
+                        .any(|&pair| (typed, next) == (Some(pair.0), Some(pair.1)))
+                    {
+                        end = self.next_boundary();
+                    }
+                }
+
+                self.buffer.replace_range(start..end, "");
+                self.cursor = start;
+            }
+
+            Action::BackwardKillWord => self.kill(self.prev_word()..self.cursor),
+            Action::BackwardKillLine => self.kill(self.line_start()..self.cursor),
+            Action::KillLine => self.kill(self.cursor..self.line_end()),
+            Action::KillWord => self.kill(self.cursor..self.next_word()),
+
+            Action::Yank => {
+                self.buffer.insert_str(self.cursor, &self.killed);
+                self.cursor += self.killed.len();
+            }
+
+            Action::TransposeChars => {
+                let cursor = self.cursor;
+                if self.cursor == self.line_end() {
+                    self.cursor = self.prev_boundary();
+                }
+
+                if self.cursor == self.line_start() || self.cursor == self.line_end() {
+                    self.cursor = cursor;
+                    return;
+                }
+
+                let (start, end) = (self.prev_boundary(), self.next_boundary());
+                let swapped = format!(
+                    "{}{}",
+                    &self.buffer[self.cursor..end],
+                    &self.buffer[start..self.cursor]
+                );
+                self.buffer.replace_range(start..end, &swapped);
+                self.cursor = end;
+            }
+
+            Action::Undo => {
+                if let Some((buffer, cursor)) = self.undo.pop() {
+                    self.buffer = buffer;
+                    self.cursor = cursor;
+                }
+            }
+
+            Action::DeleteChar | Action::DeleteCharOrEof => {
+                let end = self.next_boundary();
+                self.buffer.replace_range(self.cursor..end, "");
+            }
+
+            Action::PushLine => {
+                if !self.buffer.is_empty() {
+                    self.pushed.push(mem::take(&mut self.buffer));
+                    self.cursor = 0;
+                }
+            }
+
+            // Up and down go through the lines of the buffer before the
+            // history
+            Action::PreviousHistory | Action::HistorySearchBackward if self.line_start() > 0 => {
+                self.cursor = self.vertical(true)
+            }
+            Action::NextHistory | Action::HistorySearchForward
+                if self.line_end() < self.buffer.len() =>
+            {
+                self.cursor = self.vertical(false)
+            }
+
+            Action::PreviousHistory => {
+                if self.history_index > 0 {
+                    self.show_history(self.history_index - 1);
+                }
+            }
+
+            Action::NextHistory => {
+                if self.history_index < self.shell.history.len() {
+                    self.show_history(self.history_index + 1);
+                }
+            }
+
+            Action::HistorySearchBackward => {
+                let prefix = self.search_prefix();
+                let found = (0..self.history_index)
+                    .rev()
+                    .find(|&i| self.matches(i, &prefix));
+
+                if let Some(i) = found {
+                    self.show_history(i);
+                }
+            }
+
+            Action::HistorySearchForward => {
+                let prefix = self.search_prefix();
+                let found = (self.history_index + 1..self.shell.history.len())
+                    .find(|&i| self.matches(i, &prefix));
+
+                match found {
+                    Some(i) => self.show_history(i),
+                    // Past the newest match the typed prefix comes back
+                    None => {
+                        self.history_index = self.shell.history.len();
+                        self.set_buffer(prefix);
+                    }
+                }
+            }
+
+            Action::YankLastArg => self.yank_last_arg(),
+            Action::HistoryTokenSearchBackward => self.token_search(true),
+            Action::HistoryTokenSearchForward => self.token_search(false),
+
+            Action::ToggleQuote => self.toggle_quote(),
+
+            Action::ViCommandMode => self.vi_command_mode(),
+        }
+    }
+
+    // Insert a typed character, with auto pairing a closing character already
+    // in front of the cursor is typed over instead
+    fn type_char(&mut self, c: char) {
+        if !self.auto_pair {
+            return self.insert(c);
+        }
+
+        let prev = self.buffer[..self.cursor].chars().next_back();
+        let next = self.buffer[self.cursor..].chars().next();
+
+        if next == Some(c) && PAIRS.iter().any(|&(_, close)| close == c) {
+            self.cursor += c.len_utf8();
+            return;
+        }
+
+        // Only pair before blanks or closing characters, and quotes only at
+        // the start of words so apostrophes in words stay single
+        let at_boundary = next.is_none_or(|next| {
+            next.is_whitespace() || PAIRS.iter().any(|&(_, close)| close == next)
+        });
+        let in_word = prev.is_some_and(|prev| prev.is_alphanumeric() || prev == '\\');
+
+        match PAIRS.iter().find(|&&(open, _)| open == c) {
+            Some(&(open, close)) if at_boundary && !(open == close && in_word) => {
+                self.insert(open);
+                self.buffer.insert(self.cursor, close);
+            }
+            _ => self.insert(c),
+        }
+    }
+
+    fn toggle_quote(&mut self) {
+        let tokens = match tokenize(&self.buffer) {
+            Ok(tokens) => tokens,
+            Err(_) => return,
+        };
+
+        let span = tokens.into_iter().find_map(|(span, token)| match token {
+            Token::Word(_) if span.contains(&self.cursor) || span.end == self.cursor => Some(span),
+            _ => None,
+        });
+
+        if let Some(span) = span {
+            let word = &self.buffer[span.clone()];
+            let value = complete::split(word).0.pop().unwrap_or_default();
+
+            let toggled = match word.starts_with('\'') {
+                true => complete::escape(&value),
+                false => cmd::quote(&value),
+            };
+
+            self.buffer.replace_range(span.clone(), &toggled);
+            self.cursor = span.start + toggled.len();
+        }
+    }
+
+    fn token_search(&mut self, older: bool) {
+        let mut search = match self.token_search.take() {
+            Some(search) => search,
+            None => {
+                let is_space = |c: char| c.is_ascii_whitespace();
+                let start = self.buffer[..self.cursor]
+                    .rfind(is_space)
+                    .map_or(0, |i| i + 1);
+                let end = self.buffer[self.cursor..]
+                    .find(is_space)
+                    .map_or(self.buffer.len(), |i| self.cursor + i);
+                let typed = self.buffer[start..end].to_string();
+
+                // Newest first, each word once
+                let mut words = vec![typed.clone()];
+                for i in (0..self.shell.history.len()).rev() {
+                    let entry = self.shell.history.get(i).unwrap_or_default();
+                    for word in words_of(entry).into_iter().rev() {
+                        if word.contains(typed.as_str()) && !words.iter().any(|w| w == word) {
+                            words.push(word.to_string());
+                        }
+                    }
+                }
+
+                TokenSearch {
+                    words,
+                    shown: 0,
+                    range: start..end,
+                }
+            }
+        };
+
+        search.shown = match older {
+            true => (search.shown + 1).min(search.words.len() - 1),
+            false => search.shown.saturating_sub(1),
+        };
+
+        let word = &search.words[search.shown];
+        self.buffer.replace_range(search.range.clone(), word);
+        search.range.end = search.range.start + word.len();
+        self.cursor = search.range.end;
+        self.token_search = Some(search);
+    }
+
+    fn yank_last_arg(&mut self) {
+        let (before, range) = match self.yank.take() {
+            Some((i, range)) => (i, range),
+            None => (self.shell.history.len(), self.cursor..self.cursor),
+        };
+
+        let found = (0..before).rev().find_map(|i| {
+            let entry = self.shell.history.get(i)?;
+            last_word(entry).map(|word| (i, word.to_string()))
+        });
+
+        match found {
+            Some((i, word)) => {
+                self.buffer.replace_range(range.clone(), &word);
+                self.cursor = range.start + word.len();
+                self.yank = Some((i, range.start..self.cursor));
+            }
+            // Out of history, leave the oldest argument in place
+            None => self.yank = Some((before, range)),
+        }
+    }
+
+    // Every character typed narrows the search down and Ctrl-R goes on to
+    // older matches, Ctrl-F toggles looking only at the commands that failed.
+    // Ctrl-G and Escape bring the line back as it was, any other key ends the
+    // search with the match left in the line and is then handled as usual
+    fn reverse_search(&mut self) -> io::Result<()> {
+        let original = (self.buffer.clone(), self.cursor, self.history_index);
+        let mut query = String::new();
+        let mut found = true;
+        let mut failed_only = false;
+
+        loop {
+            let prefix = if found { "" } else { "failed " };
+            let filter = if failed_only { " ✗" } else { "" };
+            self.search_prompt = Some(format!(
+                "({}reverse-i-search{})`{}': ",
+                prefix, filter, query
+            ));
+            self.refresh()?;
+
+            let before = match self.next_key()? {
+                Some(Key::Ctrl('r')) => self.history_index,
+
+                // The match shown may be one that didn't fail
+                Some(Key::Ctrl('f')) => {
+                    failed_only = !failed_only;
+                    (self.history_index + 1).min(self.shell.history.len())
+                }
+
+                Some(Key::Char(c)) => {
+                    query.push(c);
+                    (self.history_index + 1).min(self.shell.history.len())
+                }
+
+                Some(Key::Backspace) => {
+                    query.pop();
+                    original.2
+                }
+
+                Some(Key::Ctrl('g')) | Some(Key::Escape) => {
+                    self.buffer = original.0;
+                    self.cursor = original.1;
+                    self.history_index = original.2;
+                    break;
+                }
+
+                key => {
+                    self.pending.splice(0..0, key);
+                    break;
+                }
+            };
+
+            match self.search_back(&query, before, failed_only) {
+                Some((i, offset)) => {
+                    self.show_history(i);
+                    self.cursor = offset;
+                    found = true;
+                }
+                None => found = false,
+            }
+        }
+
+        self.search_prompt = None;
+        Ok(())
+    }
+
+    // The newest entry before `before` containing `query`, and where in it
+    fn search_back(&self, query: &str, before: usize, failed_only: bool) -> Option<(usize, usize)> {
+        let history = &self.shell.history;
+
+        (0..before).rev().find_map(|i| {
+            let entry = history.entry(i)?;
+
+            if self.shell.shopts.histsession && entry.session != history.session() {
+                return None;
+            }
+
+            if failed_only && entry.status == 0 {
+                return None;
+            }
+
+            entry.command.find(query).map(|offset| (i, offset))
+        })
+    }
+
+    fn complete(&mut self) -> io::Result<()> {
+        let (words, start) = complete::split(&self.buffer[..self.cursor]);
+        let candidates = self.shell.completions.complete(self.shell, &words);
+        let word = words.last().map_or("", String::as_str);
+
+        match candidates.len() {
+            0 => {}
+
+            // A finished word gets a space after it, directories and remote
+            // hosts don't as there may be more path to type
+            1 => {
+                let mut completed = candidates[0].escaped(&candidates[0].text);
+                if !completed.ends_with(['/', ':']) {
+                    completed.push(' ');
+                }
+                self.replace_word(start, &completed);
+            }
+
+            _ => {
+                let prefix = complete::common_prefix(&candidates);
+
+                if prefix.len() > word.len() {
+                    let prefix = candidates[0].escaped(prefix);
+                    self.replace_word(start, &prefix);
+                } else {
+                    let listing = complete::listing(&candidates, terminal::columns());
+                    self.print_below(&listing)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn replace_word(&mut self, start: usize, text: &str) {
+        self.buffer.replace_range(start..self.cursor, text);
+        self.cursor = start + text.len();
+    }
+
+    // Print below the line being edited, which is drawn again after it
+    fn print_below(&mut self, text: &str) -> io::Result<()> {
+        let columns = terminal::columns();
+        let prompts = (
+            display_width(self.prompt),
+            display_width(&self.continuation),
+        );
+        let (end_row, _) = position(&self.buffer, self.buffer.len(), prompts, columns);
+
+        let mut out = String::new();
+
+        if end_row > self.cursor_row {
+            let _ = write!(out, "\x1b[{}B", end_row - self.cursor_row);
+        }
+
+        out.push_str("\r\n");
+        out.push_str(text);
+        write_out(&out)?;
+
+        self.cursor_row = 0;
+        Ok(())
+    }
+
+    // Run the edit, remembering the line as it was if it changes it
+    fn undoable<T>(&mut self, edit: impl FnOnce(&mut Self) -> T) -> T {
+        let (buffer, cursor) = (self.buffer.clone(), self.cursor);
+        let result = edit(self);
+
+        if self.buffer != buffer {
+            self.undo.push((buffer, cursor));
+        }
+        result
+    }
+
+    fn kill(&mut self, range: Range<usize>) {
+        self.cursor = range.start;
+        self.killed = self.buffer.drain(range).collect();
+    }
+
+    fn insert(&mut self, c: char) {
+        self.buffer.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
+    }
+
+    // The text before the cursor when the search started
+    fn search_prefix(&mut self) -> String {
+        let cursor = self.cursor;
+        let buffer = &self.buffer;
+
+        self.search_prefix
+            .get_or_insert_with(|| buffer[..cursor].to_string())
+            .clone()
+    }
+
+    // Entries equal to the line shown are skipped so repeated commands
+    // don't take several key presses to get past
+    fn matches(&self, i: usize, prefix: &str) -> bool {
+        self.shell
+            .history
+            .get(i)
+            .is_some_and(|entry| entry.starts_with(prefix) && entry != self.buffer)
+    }
+
+    fn show_history(&mut self, i: usize) {
+        if self.history_index == self.shell.history.len() {
+            self.draft = self.buffer.clone();
+        }
+
+        self.history_index = i;
+        let entry = match self.shell.history.get(i) {
+            Some(entry) => entry.to_string(),
+            None => self.draft.clone(),
+        };
+        self.set_buffer(entry);
+    }
+
+    // The rest of the newest history entry starting with the line, shown
+    // dimmed after it while the cursor is at the end
+    fn suggestion(&self) -> Option<&'a str> {
+        if self.buffer.is_empty() || self.cursor < self.buffer.len() {
+            return None;
+        }
+        self.shell.history.suggest(&self.buffer)
+    }
+
+    fn set_buffer(&mut self, buffer: String) {
+        self.buffer = buffer;
+        self.cursor = self.buffer.len();
+    }
+
+    fn prev_boundary(&self) -> usize {
+        self.buffer[..self.cursor]
+            .char_indices()
+            .next_back()
+            .map_or(0, |(i, _)| i)
+    }
+
+    fn next_boundary(&self) -> usize {
+        self.buffer[self.cursor..]
+            .chars()
+            .next()
+            .map_or(self.cursor, |c| self.cursor + c.len_utf8())
+    }
+
+    fn line_start(&self) -> usize {
+        self.buffer[..self.cursor].rfind('\n').map_or(0, |i| i + 1)
+    }
+
+    fn line_end(&self) -> usize {
+        self.buffer[self.cursor..]
+            .find('\n')
+            .map_or(self.buffer.len(), |i| self.cursor + i)
+    }
+
+    // The same column of the line above or below the cursor's, its end if
+    // it's not as long
+    fn vertical(&self, up: bool) -> usize {
+        let start = self.line_start();
+        let column = self.buffer[start..self.cursor].chars().count();
+
+        let (start, end) = match up {
+            true => (
+                self.buffer[..start - 1].rfind('\n').map_or(0, |i| i + 1),
+                start - 1,
+            ),
+            false => {
+                let start = self.line_end() + 1;
+                let end = self.buffer[start..]
+                    .find('\n')
+                    .map_or(self.buffer.len(), |i| start + i);
+                (start, end)
+            }
+        };
+
+        let line = &self.buffer[start..end];
+        start
+            + line
+                .char_indices()
+                .nth(column)
+                .map_or(line.len(), |(i, _)| i)
+    }
+
+    fn is_word_char(&self, c: char) -> bool {
+        c.is_alphanumeric() || self.word_chars.contains(c)
+    }
+
+    // The start of the word before the cursor, skipping what's between them
+    fn prev_word(&self) -> usize {
+        let mut chars = self.buffer[..self.cursor].char_indices().rev().peekable();
+
+        while chars.next_if(|&(_, c)| !self.is_word_char(c)).is_some() {}
+        while chars.next_if(|&(_, c)| self.is_word_char(c)).is_some() {}
+
+        chars.peek().map_or(0, |&(i, c)| i + c.len_utf8())
+    }
+
+    // The end of the word after the cursor
+    fn next_word(&self) -> usize {
+        let rest = &self.buffer[self.cursor..];
+        let mut chars = rest.char_indices().peekable();
+
+        while chars.next_if(|&(_, c)| !self.is_word_char(c)).is_some() {}
+        while chars.next_if(|&(_, c)| self.is_word_char(c)).is_some() {}
+
+        self.cursor + chars.peek().map_or(rest.len(), |&(i, _)| i)
+    }
+
+    // Redraw the prompt and the lines, which may wrap over several rows
+    fn refresh(&mut self) -> io::Result<()> {
+        let prompt_text = self.search_prompt.as_deref().unwrap_or(self.prompt);
+        let suggestion = match self.search_prompt {
+            Some(_) => "",
+            None => self.suggestion().unwrap_or_default(),
+        };
+        let columns = terminal::columns();
+        let prompts = (
+            display_width(prompt_text),
+            display_width(&self.continuation),
+        );
+        let shown = format!("{}{}", self.buffer, suggestion);
+        let (end_row, _) = position(&shown, shown.len(), prompts, columns);
+        let (row, column) = position(&self.buffer, self.cursor, prompts, columns);
+
+        let mut out = String::new();
+
+        if self.cursor_row > 0 {
+            let _ = write!(out, "\x1b[{}A", self.cursor_row);
+        }
+
+        out.push_str("\r\x1b[J");
+        out.push_str(prompt_text);
+
+        let highlighted = highlight::highlight(&self.buffer, self.cursor, self.shell);
+        let lines = highlighted.split('\n').zip(self.buffer.split('\n'));
+        let last = self.buffer.matches('\n').count();
+
+        for (i, (line, plain)) in lines.enumerate() {
+            let mut width = plain.chars().count();
+
+            if i == 0 {
+                width += prompts.0;
+            } else {
+                out.push_str("\x1b[0m\r\n");
+                out.push_str(&self.continuation);
+                width += prompts.1;
+            }
+            out.push_str(line);
+
+            if i == last && !suggestion.is_empty() {
+                let _ = write!(out, "\x1b[2m{}\x1b[0m", suggestion);
+                width += suggestion.chars().count();
+            }
+
+            // Terminals only wrap once the next character is written, move
+            // to the next row so the cursor ends where it's expected to
+            if width > 0 && width.is_multiple_of(columns) {
+                out.push_str("\r\n");
+            }
+        }
+
+        if end_row > row {
+            let _ = write!(out, "\x1b[{}A", end_row - row);
+        }
+
+        out.push('\r');
+
+        if column > 0 {
+            let _ = write!(out, "\x1b[{}C", column);
+        }
+
+        self.cursor_row = row;
+        write_out(&out)
+    }
+}
+
+// The row and column the byte offset `at` of `text` is shown at, the first
+// line after a prompt as wide as `prompts.0` and the others after one as wide
+// as `prompts.1`. A line filling its last row takes one more, as the cursor
+// is moved to the next one after it
+fn position(text: &str, at: usize, prompts: (usize, usize), columns: usize) -> (usize, usize) {
+    let mut row = 0;
+    let mut prompt = prompts.0;
+    let mut lines = text[..at].split('\n').peekable();
+
+    while let Some(line) = lines.next() {
+        let width = prompt + line.chars().count();
+
+        if lines.peek().is_none() {
+            return (row + width / columns, width % columns);
+        }
+        row += width / columns + 1;
+        prompt = prompts.1;
+    }
+
+    (row, 0)
+}
+
+// The last word of a commandline as it was typed, quotes included
+fn last_word(line: &str) -> Option<&str> {
+    words_of(line).pop()
+}
+
+fn words_of(line: &str) -> Vec<&str> {
+    match tokenize(line) {
+        Ok(tokens) => tokens
+            .into_iter()
+            .filter_map(|(_, token)| match token {
+                Token::Word(word) => Some(word),
+                _ => None,
+            })
+            .collect(),
+        Err(_) => line.split_whitespace().collect(),
+    }
+}
+
+fn write_out(s: &str) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    stdout.write_all(s.as_bytes())?;
+    stdout.flush()
+}
+
+// The number of columns `s` takes on screen, escape sequences take none
+pub fn display_width(s: &str) -> usize {
+    let mut width = 0;
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // Skip up to the final byte of the sequence
+            chars.find(|c| c.is_ascii_alphabetic());
+        } else if !c.is_control() {
+            width += 1;
+        }
+    }
+
+    width
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::status::Status;
+    use std::time::Duration;
+
+    fn editor<'a>(shell: &'a Shell, line: &str) -> Editor<'a> {
+        Editor::new("> ", shell, line.to_string())
+    }
+
+    // What a single key does
+    fn action(keymap: &Keymap, key: Key) -> Option<Action> {
+        match keymap.get(&[key]) {
+            Some(Binding::Action(action)) => Some(*action),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_presets() {
+        assert_eq!(Keymap::default().preset_name(), "readline");
+
+        let fish = Keymap::preset("fish").unwrap();
+        assert_eq!(action(&fish, Key::Ctrl('f')), Some(Action::ForwardChar));
+        assert_eq!(action(&fish, Key::Alt('b')), Some(Action::BackwardWord));
+        assert_eq!(action(&fish, Key::AltRight), Some(Action::ForwardWord));
+        assert_eq!(
+            action(&fish, Key::AltUp),
+            Some(Action::HistoryTokenSearchBackward)
+        );
+        assert_eq!(action(&fish, Key::Alt('e')), Some(Action::EditCommandLine));
+        assert_eq!(action(&Keymap::default(), Key::AltUp), None);
+
+        let vi = Keymap::preset("vi").unwrap();
+        assert_eq!(action(&vi, Key::Enter), Some(Action::AcceptLine));
+        assert_eq!(action(&vi, Key::Alt('b')), None);
+        assert_eq!(action(&vi, Key::Escape), Some(Action::ViCommandMode));
+
+        assert!(Keymap::preset("nano").is_none());
+    }
+
+    #[test]
+    fn test_bind_keys() {
+        assert_eq!(Key::parse("\\C-f"), Some(vec![Key::Ctrl('f')]));
+        assert_eq!(Key::parse("\\C-I"), Some(vec![Key::Tab]));
+        assert_eq!(Key::parse("\\M-d"), Some(vec![Key::Alt('d')]));
+        assert_eq!(Key::parse("\\e\\C-?"), Some(vec![Key::Alt('\x7f')]));
+        assert_eq!(Key::parse("\\e[A"), Some(vec![Key::Up]));
+        assert_eq!(Key::parse("x"), Some(vec![Key::Char('x')]));
+        assert_eq!(
+            Key::parse("\\C-x\\C-e"),
+            Some(vec![Key::Ctrl('x'), Key::Ctrl('e')])
+        );
+        assert_eq!(
+            Key::parse("ab\\e"),
+            Some(vec![Key::Char('a'), Key::Char('b'), Key::Escape])
+        );
+        assert_eq!(Key::parse("\\C-"), None);
+        assert_eq!(Key::parse("\\C-x\\q"), None);
+        assert_eq!(Key::parse(""), None);
+
+        for spec in ["\\C-_", "\\ed", "\\e\\\\", "\\C-m", "\\e[3~", "\\\""] {
+            assert_eq!(Key::parse(spec).unwrap()[0].to_string(), spec);
+        }
+
+        for action in ACTIONS {
+            assert_eq!(Action::from_name(action.name()), Some(*action));
+        }
+
+        let mut keymap = Keymap::default();
+        keymap.bind(
+            vec![Key::Ctrl('w')],
+            Binding::Action(Action::BackwardKillLine),
+        );
+        assert_eq!(
+            action(&keymap, Key::Ctrl('w')),
+            Some(Action::BackwardKillLine)
+        );
+        assert!(keymap.unbind(&[Key::Ctrl('a')]));
+        assert_eq!(action(&keymap, Key::Ctrl('a')), None);
+        assert!(!keymap.unbind(&[Key::Char('a')]));
+    }
+
+    #[test]
+    fn test_key_sequences() {
+        let mut shell = Shell::new();
+        let edit = vec![Key::Ctrl('x'), Key::Ctrl('e')];
+        shell
+            .keymap
+            .bind(edit.clone(), Binding::Command(String::from("edit")));
+
+        let mut editor = editor(&shell, "");
+        assert!(shell.keymap.is_prefix(&[Key::Ctrl('x')]));
+        editor.pending = vec![Key::Ctrl('e'), Key::Char('a')];
+        assert_eq!(editor.read_sequence(Key::Ctrl('x')).unwrap(), edit);
+        assert_eq!(editor.pending, vec![Key::Char('a')]);
+
+        // Not bound past the first key, the rest is handled next
+        editor.pending = vec![Key::Char('b')];
+        assert_eq!(
+            editor.read_sequence(Key::Ctrl('x')).unwrap(),
+            vec![Key::Ctrl('x')]
+        );
+        assert_eq!(editor.pending, vec![Key::Char('b')]);
+    }
+
+    #[test]
+    fn test_run_bound() {
+        let mut shell = Shell::new();
+        shell.status = Status::Exited(3);
+        let mut kept = Kept {
+            buffer: String::from("ls"),
+            cursor: 2,
+            ..Kept::default()
+        };
+
+        let command = "READLINE_LINE=\"$READLINE_LINE ~/src\"; READLINE_POINT=3";
+        run_bound(command, &mut kept, &mut shell);
+        assert_eq!(kept.buffer, "ls ~/src");
+        assert_eq!(kept.cursor, 3);
+        assert_eq!(kept.undo, vec![(String::from("ls"), 2)]);
+        assert_eq!(shell.lookup("READLINE_LINE"), None);
+        assert_eq!(shell.status, Status::Exited(3));
+
+        run_bound("READLINE_LINE=é; READLINE_POINT=1", &mut kept, &mut shell);
+        assert_eq!((kept.buffer.as_str(), kept.cursor), ("é", 0));
+    }
+
+    #[test]
+    fn test_history_search_by_prefix() {
+        let mut shell = Shell::new();
+
+        for line in &["git status", "ls", "git log", "git log", "cargo build"] {
+            shell.history.push(line);
+        }
+
+        let mut editor = editor(&shell, "git");
+
+        editor.perform(Action::HistorySearchBackward);
+        assert_eq!(editor.buffer, "git log");
+        editor.perform(Action::HistorySearchBackward);
+        assert_eq!(editor.buffer, "git status");
+        editor.perform(Action::HistorySearchBackward);
+        assert_eq!(editor.buffer, "git status");
+
+        editor.perform(Action::HistorySearchForward);
+        assert_eq!(editor.buffer, "git log");
+        editor.perform(Action::HistorySearchForward);
+        assert_eq!(editor.buffer, "git");
+
+        // Plain navigation ignores what was typed
+        editor.perform(Action::PreviousHistory);
+        assert_eq!(editor.buffer, "cargo build");
+    }
+
+    #[test]
+    fn test_history_keeps_typed_line() {
+        let mut shell = Shell::new();
+        shell.history.push("ls");
+        shell.history.push("pwd");
+
+        let mut editor = editor(&shell, "echo half");
+        editor.perform(Action::PreviousHistory);
+        editor.perform(Action::PreviousHistory);
+        assert_eq!(editor.buffer, "ls");
+        editor.perform(Action::NextHistory);
+        assert_eq!(editor.buffer, "pwd");
+        editor.perform(Action::NextHistory);
+        assert_eq!(editor.buffer, "echo half");
+        assert_eq!(editor.cursor, editor.buffer.len());
+        editor.perform(Action::NextHistory);
+        assert_eq!(editor.buffer, "echo half");
+    }
+
+    #[test]
+    fn test_history_search_without_prefix() {
+        let mut shell = Shell::new();
+        shell.history.push("ls");
+        shell.history.push("pwd");
+
+        let mut editor = editor(&shell, "");
+
+        editor.perform(Action::HistorySearchBackward);
+        assert_eq!(editor.buffer, "pwd");
+        editor.perform(Action::HistorySearchBackward);
+        assert_eq!(editor.buffer, "ls");
+    }
+
+    #[test]
+    fn test_yank_last_arg() {
+        let mut shell = Shell::new();
+        shell.history.push("mkdir 'new dir'");
+        shell.history.push("cd /tmp; ls -l src");
+
+        let mut editor = editor(&shell, "cat ");
+
+        editor.perform(Action::YankLastArg);
+        assert_eq!(editor.buffer, "cat src");
+        editor.perform(Action::YankLastArg);
+        assert_eq!(editor.buffer, "cat 'new dir'");
+        editor.perform(Action::YankLastArg);
+        assert_eq!(editor.buffer, "cat 'new dir'");
+        assert_eq!(editor.cursor, editor.buffer.len());
+    }
+
+    #[test]
+    fn test_reverse_search() {
+        let mut shell = Shell::new();
+
+        for line in &["make test", "ls", "cargo test --all", "git status"] {
+            shell.history.push(line);
+        }
+
+        let editor = editor(&shell, "");
+        assert_eq!(editor.search_back("test", 4, false), Some((2, 6)));
+        assert_eq!(editor.search_back("test", 2, false), Some((0, 5)));
+        assert_eq!(editor.search_back("test", 0, false), None);
+        assert_eq!(editor.search_back("nope", 4, false), None);
+    }
+
+    #[test]
+    fn test_reverse_search_failed() {
+        let mut shell = Shell::new();
+        shell.history.push("make test");
+        shell.history.finish(Duration::from_secs(3), 2).unwrap();
+        shell.history.push("make build");
+        shell.history.finish(Duration::from_secs(1), 0).unwrap();
+
+        let editor = editor(&shell, "");
+        assert_eq!(editor.search_back("make", 2, false), Some((1, 0)));
+        assert_eq!(editor.search_back("make", 2, true), Some((0, 0)));
+        assert_eq!(editor.search_back("build", 2, true), None);
+    }
+
+    #[test]
+    fn test_suggestion() {
+        let mut shell = Shell::new();
+        shell.history.push("cargo test --all");
+        shell.history.push("cargo build");
+
+        let mut editor = editor(&shell, "cargo t");
+        assert_eq!(editor.suggestion(), Some("est --all"));
+        editor.perform(Action::BackwardChar);
+        assert_eq!(editor.suggestion(), None);
+        editor.perform(Action::ForwardChar);
+        assert_eq!(editor.buffer, "cargo t");
+        editor.perform(Action::EndOfLine);
+        assert_eq!(editor.buffer, "cargo test --all");
+        assert_eq!(editor.cursor, editor.buffer.len());
+        assert_eq!(editor.suggestion(), None);
+
+        let mut by_word = self::editor(&shell, "cargo t");
+        by_word.perform(Action::ForwardWord);
+        assert_eq!(by_word.buffer, "cargo test");
+        by_word.perform(Action::ForwardWord);
+        assert_eq!(by_word.buffer, "cargo test --all");
+    }
+
+    #[test]
+    fn test_token_search() {
+        let mut shell = Shell::new();
+        shell.history.push("vim src/main.rs");
+        shell.history.push("cat src/shell.rs README");
+        shell.history.push("git add src/main.rs");
+
+        let mut editor = editor(&shell, "less src");
+        editor.perform(Action::HistoryTokenSearchBackward);
+        assert_eq!(editor.buffer, "less src/main.rs");
+        editor.perform(Action::HistoryTokenSearchBackward);
+        assert_eq!(editor.buffer, "less src/shell.rs");
+        editor.perform(Action::HistoryTokenSearchBackward);
+        assert_eq!(editor.buffer, "less src/shell.rs");
+        editor.perform(Action::HistoryTokenSearchForward);
+        editor.perform(Action::HistoryTokenSearchForward);
+        assert_eq!(editor.buffer, "less src");
+        assert_eq!(editor.cursor, editor.buffer.len());
+    }
+
+    #[test]
+    fn test_edit_externally() {
+        let mut shell = Shell::new();
+        shell.set_var("VISUAL", String::from("sed -i s/ls/pwd/"));
+        let mut kept = Kept {
+            buffer: String::from("ls -l"),
+            ..Kept::default()
+        };
+
+        edit_externally(&mut kept, &mut shell);
+        assert_eq!(kept.buffer, "pwd -l");
+        assert_eq!(kept.cursor, kept.buffer.len());
+
+        shell.set_var("VISUAL", String::from("false"));
+        edit_externally(&mut kept, &mut shell);
+        assert_eq!(kept.buffer, "pwd -l");
+    }
+
+    #[test]
+    fn test_multiple_lines() {
+        let mut shell = Shell::new();
+        shell.history.push("ls");
+        let mut editor = editor(&shell, "if true\nthen echo a\nfi");
+
+        editor.perform(Action::HistorySearchBackward);
+        assert_eq!(editor.cursor, 10);
+        editor.perform(Action::PreviousHistory);
+        assert_eq!(editor.cursor, 2);
+        editor.perform(Action::EndOfLine);
+        assert_eq!(editor.cursor, 7);
+        editor.perform(Action::HistorySearchForward);
+        assert_eq!(editor.cursor, 15);
+        editor.perform(Action::BeginningOfLine);
+        assert_eq!(editor.cursor, 8);
+        editor.perform(Action::HistorySearchBackward);
+        editor.perform(Action::HistorySearchBackward);
+        assert_eq!(editor.buffer, "ls");
+
+        assert_eq!(position("ab\ncd", 5, (2, 2), 80), (1, 4));
+        assert_eq!(position("ab\ncd", 1, (2, 2), 80), (0, 3));
+        assert_eq!(position("abcdef\nx", 8, (2, 4), 4), (4, 1));
+    }
+
+    #[test]
+    fn test_emacs_keys() {
+        let shell = Shell::new();
+        let mut editor = editor(&shell, "echo hello world");
+        let mut edit = |action| editor.undoable(|editor| editor.perform(action));
+
+        edit(Action::BackwardWord);
+        edit(Action::KillLine);
+        edit(Action::BeginningOfLine);
+        edit(Action::Yank);
+        assert_eq!(editor.buffer, "worldecho hello ");
+        assert_eq!(editor.cursor, 5);
+
+        editor.undoable(|editor| editor.perform(Action::TransposeChars));
+        assert_eq!(editor.buffer, "worledcho hello ");
+        editor.undoable(|editor| editor.perform(Action::KillWord));
+        assert_eq!(editor.buffer, "worled hello ");
+        editor.undoable(|editor| editor.perform(Action::BackwardKillLine));
+        assert_eq!(editor.buffer, " hello ");
+
+        editor.perform(Action::Undo);
+        editor.perform(Action::Undo);
+        assert_eq!(editor.buffer, "worledcho hello ");
+        editor.perform(Action::Undo);
+        editor.perform(Action::Undo);
+        editor.perform(Action::Undo);
+        assert_eq!(editor.buffer, "echo hello world");
+        assert_eq!(editor.cursor, 11);
+
+        let mut editor = self::editor(&shell, "ab");
+        editor.perform(Action::TransposeChars);
+        assert_eq!((editor.buffer.as_str(), editor.cursor), ("ba", 2));
+    }
+
+    #[test]
+    fn test_push_line() {
+        let shell = Shell::new();
+        let mut editor = editor(&shell, "make install");
+
+        editor.perform(Action::PushLine);
+        assert_eq!(editor.buffer, "");
+        assert_eq!(editor.cursor, 0);
+        editor.perform(Action::PushLine);
+        assert_eq!(editor.pushed, ["make install"]);
+    }
+
+    #[test]
+    fn test_auto_pair() {
+        let shell = Shell::new();
+        let mut editor = editor(&shell, "echo ");
+        editor.auto_pair = true;
+
+        for c in "$(ls \"it's".chars() {
+            editor.type_char(c);
+        }
+        assert_eq!(editor.buffer, "echo $(ls \"it's\")");
+
+        editor.type_char('"');
+        editor.type_char(')');
+        assert_eq!(editor.cursor, editor.buffer.len());
+
+        let mut brackets = self::editor(&shell, "");
+        brackets.auto_pair = true;
+        brackets.type_char('(');
+        brackets.type_char('[');
+        assert_eq!(brackets.buffer, "([])");
+        brackets.perform(Action::BackwardDeleteChar);
+        assert_eq!(brackets.buffer, "()");
+    }
+
+    #[test]
+    fn test_toggle_quote() {
+        let shell = Shell::new();
+        let mut editor = editor(&shell, "cat my\\ file.txt");
+
+        editor.perform(Action::ToggleQuote);
+        assert_eq!(editor.buffer, "cat 'my file.txt'");
+        editor.perform(Action::ToggleQuote);
+        assert_eq!(editor.buffer, "cat my\\ file.txt");
+
+        editor.cursor = 1;
+        editor.perform(Action::ToggleQuote);
+        assert_eq!(editor.buffer, "'cat' my\\ file.txt");
+    }
+
+    #[test]
+    fn test_word_motions() {
+        let mut shell = Shell::new();
+        let mut editor = editor(&shell, "vim ~/src/rush/main.rs");
+
+        editor.perform(Action::BackwardKillWord);
+        assert_eq!(editor.buffer, "vim ~/src/rush/");
+        editor.perform(Action::BackwardKillWord);
+        assert_eq!(editor.buffer, "vim ~/src/");
+        editor.perform(Action::BackwardWord);
+        editor.perform(Action::BackwardWord);
+        assert_eq!(editor.cursor, 4);
+        editor.perform(Action::ForwardWord);
+        assert_eq!(editor.cursor, 5);
+        editor.perform(Action::ForwardWord);
+        assert_eq!(editor.cursor, 9);
+
+        // `/` as a word character takes the whole path
+        shell.vars.insert("WORDCHARS".into(), "/.~".into());
+        let mut editor = self::editor(&shell, "vim ~/src/rush/main.rs");
+        editor.perform(Action::BackwardKillWord);
+        assert_eq!(editor.buffer, "vim ");
+    }
+
+    #[test]
+    fn test_display_width() {
+        assert_eq!(display_width("\x1b[31m>\x1b[0m "), 2);
+        assert_eq!(display_width("ñu> "), 4);
+    }
+}
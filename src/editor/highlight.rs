@@ -0,0 +1,289 @@
+// Colors for the line being edited: command names are green when there's
+// such a command and red when not, quoted strings yellow, operators magenta
+// and files that exist underlined. The bracket pairing with the one under or
+// before the cursor stands out, and closing brackets with nothing to close
+// or quotes left open are shown in red so mistakes are seen before running
+
+use std::env;
+use std::ops::Range;
+use std::path::Path;
+
+use crate::cmd::{self, assignment_eq, tokenize, Token, KEYWORDS};
+use crate::complete;
+use crate::path;
+use crate::shell::Shell;
+
+const MATCH: &str = "\x1b[1;36m";
+const ERROR: &str = "\x1b[31m";
+const COMMAND: &str = "\x1b[32m";
+const STRING: &str = "\x1b[33m";
+const OPERATOR: &str = "\x1b[35m";
+const FILE: &str = "\x1b[4m";
+const RESET: &str = "\x1b[0m";
+
+pub fn highlight(line: &str, cursor: usize, shell: &Shell) -> String {
+    let Scan {
+        pairs,
+        unmatched,
+        open_quote,
+    } = scan(line);
+    let styles = styles(&line[..open_quote.unwrap_or(line.len())], shell);
+
+    let before = line[..cursor].char_indices().next_back().map(|(i, _)| i);
+    let matched = [Some(cursor), before].iter().flatten().find_map(|&i| {
+        pairs
+            .iter()
+            .copied()
+            .find(|&(open, close)| open == i || close == i)
+    });
+
+    let mut out = String::new();
+    let mut current = None;
+
+    for (i, c) in line.char_indices() {
+        let style = if open_quote.is_some_and(|open| i >= open) {
+            Some(ERROR)
+        } else if matched.is_some_and(|(open, close)| i == open || i == close) {
+            Some(MATCH)
+        } else if unmatched.contains(&i) {
+            Some(ERROR)
+        } else {
+            styles
+                .iter()
+                .find(|(range, _)| range.contains(&i))
+                .map(|&(_, style)| style)
+        };
+
+        if style != current {
+            if current.is_some() {
+                out.push_str(RESET);
+            }
+            if let Some(style) = style {
+                out.push_str(style);
+            }
+            current = style;
+        }
+        out.push(c);
+    }
+
+    if current.is_some() {
+        out.push_str(RESET);
+    }
+    out
+}
+
+// The parts of the line colored by what the tokens are, the first range
+// containing a character giving its color. Nothing when it doesn't tokenize
+fn styles(line: &str, shell: &Shell) -> Vec<(Range<usize>, &'static str)> {
+    let tokens = match tokenize(line) {
+        Ok(tokens) => tokens,
+        Err(_) => return vec![],
+    };
+
+    let mut styles = vec![];
+    let mut command_next = true;
+    let mut after_redirect = false;
+
+    for (span, token) in tokens {
+        match token {
+            Token::Word(word) => {
+                styles.extend(strings(word, span.start));
+
+                if command_next && assignment_eq(word).is_none() {
+                    let style = match is_command(word, shell) {
+                        true => COMMAND,
+                        false => ERROR,
+                    };
+                    styles.push((span.clone(), style));
+                    command_next = cmd::opens_list(word);
+                } else if (after_redirect || !command_next) && is_file(word) {
+                    styles.push((span, FILE));
+                }
+                after_redirect = false;
+            }
+
+            Token::Redirect(_) => {
+                styles.push((span, OPERATOR));
+                after_redirect = true;
+            }
+
+            Token::Arith(_) | Token::Cond(_) => command_next = false,
+            Token::Newline => command_next = true,
+
+            _ => {
+                styles.push((span, OPERATOR));
+                command_next = true;
+            }
+        }
+    }
+
+    styles
+}
+
+// The quoted parts of a word starting at `start`
+fn strings(word: &str, start: usize) -> Vec<(Range<usize>, &'static str)> {
+    let mut strings = vec![];
+    let mut quote = None;
+    let mut chars = word.char_indices();
+
+    while let Some((i, c)) = chars.next() {
+        match (quote, c) {
+            (Some((open, q)), c) if c == q => {
+                strings.push((start + open..start + i + 1, STRING));
+                quote = None;
+            }
+            (Some((_, '"')), '\\') | (None, '\\') => {
+                chars.next();
+            }
+            (None, '\'') | (None, '"') => quote = Some((i, c)),
+            _ => {}
+        }
+    }
+
+    strings
+}
+
+// Words with expansions in them are taken as commands, what they expand to
+// isn't known until they run
+fn is_command(word: &str, shell: &Shell) -> bool {
+    if word.contains(['$', '`']) {
+        return true;
+    }
+
+    let name = unquote(word);
+    KEYWORDS.contains(&name.as_str())
+        || shell.builtins.contains(&name)
+        || shell.functions.contains_key(&name)
+        || shell.aliases.contains_key(&name)
+        || !path::find_all_executables(&name).is_empty()
+}
+
+fn is_file(word: &str) -> bool {
+    if word.contains(['$', '`', '*', '?', '[']) {
+        return false;
+    }
+
+    let name = unquote(word);
+    match name.strip_prefix("~/") {
+        Some(rest) => env::var_os("HOME").is_some_and(|home| Path::new(&home).join(rest).exists()),
+        None => Path::new(&name).exists(),
+    }
+}
+
+fn unquote(word: &str) -> String {
+    complete::split(word).0.pop().unwrap_or_default()
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct Scan {
+    // Where the brackets that close each other are
+    pairs: Vec<(usize, usize)>,
+    // Closing brackets with no opening one
+    unmatched: Vec<usize>,
+    // The quote still open at the end of the line
+    open_quote: Option<usize>,
+}
+
+fn scan(line: &str) -> Scan {
+    let mut scan = Scan::default();
+    let mut open = vec![];
+    let mut quote = None;
+    let mut chars = line.char_indices();
+
+    while let Some((i, c)) = chars.next() {
+        match (quote.map(|(_, q)| q), c) {
+            (Some('\''), '\'') | (Some('"'), '"') => quote = None,
+            (Some('\''), _) => {}
+            (_, '\\') => {
+                chars.next();
+            }
+            (None, '\'') | (None, '"') => quote = Some((i, c)),
+            (_, '(') | (_, '{') => open.push((i, c)),
+            (_, ')') | (_, '}') => match open.last() {
+                Some(&(start, bracket))
+                    if (bracket, c) == ('(', ')') || (bracket, c) == ('{', '}') =>
+                {
+                    open.pop();
+                    scan.pairs.push((start, i));
+                }
+                _ => scan.unmatched.push(i),
+            },
+            _ => {}
+        }
+    }
+
+    scan.open_quote = quote.map(|(i, _)| i);
+    scan
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_scan() {
+        let scan = scan("{ echo $(ls ')'); }; )");
+        assert_eq!(scan.pairs, [(8, 15), (0, 18)]);
+        assert_eq!(scan.unmatched, [21]);
+        assert_eq!(scan.open_quote, None);
+
+        assert_eq!(super::scan("echo \"it's").open_quote, Some(5));
+        assert_eq!(super::scan("echo it\\'s").open_quote, None);
+    }
+
+    #[test]
+    fn test_highlight() {
+        let shell = Shell::new();
+        let highlight = |line, cursor| highlight(line, cursor, &shell);
+
+        assert_eq!(
+            highlight("(ls)", 4),
+            format!(
+                "{}({}{}ls{}{}){}",
+                MATCH, RESET, COMMAND, RESET, MATCH, RESET
+            )
+        );
+        assert_eq!(
+            highlight("(ls)", 2),
+            format!(
+                "{}({}{}ls{}{}){}",
+                OPERATOR, RESET, COMMAND, RESET, OPERATOR, RESET
+            )
+        );
+        assert_eq!(
+            highlight("echo 'a", 0),
+            format!("{}echo{} {}'a{}", COMMAND, RESET, ERROR, RESET)
+        );
+    }
+
+    #[test]
+    fn test_styles() {
+        let shell = Shell::new();
+        let styles = |line| styles(line, &shell);
+
+        assert_eq!(
+            styles("x=1 nope-no-such-command | echo 'a b' /"),
+            vec![
+                (4..24, ERROR),
+                (25..26, OPERATOR),
+                (27..31, COMMAND),
+                (32..37, STRING),
+                (38..39, FILE),
+            ]
+        );
+        assert_eq!(
+            styles("if true; then cat < /; fi"),
+            vec![
+                (0..2, COMMAND),
+                (3..7, COMMAND),
+                (7..8, OPERATOR),
+                (9..13, COMMAND),
+                (14..17, COMMAND),
+                (18..19, OPERATOR),
+                (20..21, FILE),
+                (21..22, OPERATOR),
+                (23..25, COMMAND),
+            ]
+        );
+    }
+}
@@ -0,0 +1,279 @@
+use std::collections::HashMap;
+
+use super::keys::Key;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    AcceptLine,
+    BackwardChar,
+    BackwardDeleteChar,
+    // Delete from the start of the word before the cursor, what makes up a
+    // word is up to `WORDCHARS`
+    BackwardKillWord,
+    // Delete from the start of the line to the cursor
+    BackwardKillLine,
+    BackwardWord,
+    BeginningOfLine,
+    // Clear the screen, leaving the line at the top
+    ClearScreen,
+    // Complete the word before the cursor, listing the candidates when
+    // there's more than one
+    Complete,
+    DeleteChar,
+    // Delete the character under the cursor, or signal end of input when the
+    // line is empty
+    DeleteCharOrEof,
+    // Edit the line in `$VISUAL` or `$EDITOR`
+    EditCommandLine,
+    EndOfLine,
+    ForwardChar,
+    ForwardWord,
+    HistorySearchBackward,
+    HistorySearchForward,
+    // Replace the word at the cursor with older words from the history that
+    // contain it, or newer ones again
+    HistoryTokenSearchBackward,
+    HistoryTokenSearchForward,
+    // Delete from the cursor to the end of the line
+    KillLine,
+    // Delete to the end of the word after the cursor
+    KillWord,
+    NextHistory,
+    PreviousHistory,
+    // Search the history backwards incrementally for entries containing
+    // what's typed
+    ReverseSearch,
+    // Put the line aside for a fresh prompt, it comes back after the next
+    // line is read
+    PushLine,
+    // Leave insert mode in the vi preset, the keys typed are vi commands
+    // until one goes back to inserting
+    ViCommandMode,
+    // Single quote the word under the cursor, or backslash escape it instead
+    // if it's single quoted already
+    ToggleQuote,
+    // Swap the character before the cursor with the one under it, or the
+    // two before it at the end of the line
+    TransposeChars,
+    // Put the line back as it was before the last edit
+    Undo,
+    // Insert what the last kill deleted
+    Yank,
+    // Insert the last argument of the previous command, repeating it goes
+    // further back in history
+    YankLastArg,
+}
+
+// Every action, in the order `bind -l` lists them
+pub const ACTIONS: &[Action] = &[
+    Action::AcceptLine,
+    Action::BackwardChar,
+    Action::BackwardDeleteChar,
+    Action::BackwardKillLine,
+    Action::BackwardKillWord,
+    Action::BackwardWord,
+    Action::BeginningOfLine,
+    Action::ClearScreen,
+    Action::Complete,
+    Action::DeleteChar,
+    Action::DeleteCharOrEof,
+    Action::EditCommandLine,
+    Action::EndOfLine,
+    Action::ForwardChar,
+    Action::ForwardWord,
+    Action::HistorySearchBackward,
+    Action::HistorySearchForward,
+    Action::HistoryTokenSearchBackward,
+    Action::HistoryTokenSearchForward,
+    Action::KillLine,
+    Action::KillWord,
+    Action::NextHistory,
+    Action::PreviousHistory,
+    Action::PushLine,
+    Action::ReverseSearch,
+    Action::ToggleQuote,
+    Action::TransposeChars,
+    Action::Undo,
+    Action::ViCommandMode,
+    Action::Yank,
+    Action::YankLastArg,
+];
+
+impl Action {
+    // The name `bind` knows it by, readline's where it has the same one
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::AcceptLine => "accept-line",
+            Action::BackwardChar => "backward-char",
+            Action::BackwardDeleteChar => "backward-delete-char",
+            Action::BackwardKillLine => "backward-kill-line",
+            Action::BackwardKillWord => "backward-kill-word",
+            Action::BackwardWord => "backward-word",
+            Action::BeginningOfLine => "beginning-of-line",
+            Action::ClearScreen => "clear-screen",
+            Action::Complete => "complete",
+            Action::DeleteChar => "delete-char",
+            Action::DeleteCharOrEof => "delete-char-or-eof",
+            Action::EditCommandLine => "edit-command-line",
+            Action::EndOfLine => "end-of-line",
+            Action::ForwardChar => "forward-char",
+            Action::ForwardWord => "forward-word",
+            Action::HistorySearchBackward => "history-search-backward",
+            Action::HistorySearchForward => "history-search-forward",
+            Action::HistoryTokenSearchBackward => "history-token-search-backward",
+            Action::HistoryTokenSearchForward => "history-token-search-forward",
+            Action::KillLine => "kill-line",
+            Action::KillWord => "kill-word",
+            Action::NextHistory => "next-history",
+            Action::PreviousHistory => "previous-history",
+            Action::PushLine => "push-line",
+            Action::ReverseSearch => "reverse-search-history",
+            Action::ToggleQuote => "toggle-quote",
+            Action::TransposeChars => "transpose-chars",
+            Action::Undo => "undo",
+            Action::ViCommandMode => "vi-movement-mode",
+            Action::Yank => "yank",
+            Action::YankLastArg => "yank-last-arg",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        ACTIONS.iter().copied().find(|action| action.name() == name)
+    }
+}
+
+// What a key, or a sequence of them, is bound to
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Binding {
+    Action(Action),
+    // A command line run with the line in `READLINE_LINE` and the cursor in
+    // `READLINE_POINT`, both read back once it's done, like bash's `bind -x`
+    Command(String),
+}
+
+pub struct Keymap {
+    bindings: HashMap<Vec<Key>, Binding>,
+    // The preset the bindings come from
+    preset: &'static str,
+}
+
+type Bindings = &'static [(Key, Action)];
+
+// The keys bound the same way in every preset
+const COMMON: Bindings = &[
+    (Key::Enter, Action::AcceptLine),
+    (Key::Tab, Action::Complete),
+    (Key::Left, Action::BackwardChar),
+    (Key::Right, Action::ForwardChar),
+    (Key::Ctrl('w'), Action::BackwardKillWord),
+    (Key::Home, Action::BeginningOfLine),
+    (Key::End, Action::EndOfLine),
+    (Key::Backspace, Action::BackwardDeleteChar),
+    (Key::Delete, Action::DeleteChar),
+    (Key::Ctrl('d'), Action::DeleteCharOrEof),
+    (Key::Up, Action::HistorySearchBackward),
+    (Key::Down, Action::HistorySearchForward),
+    (Key::Ctrl('p'), Action::PreviousHistory),
+    (Key::Ctrl('n'), Action::NextHistory),
+    (Key::Ctrl('r'), Action::ReverseSearch),
+];
+
+const READLINE: Bindings = &[
+    (Key::Ctrl('a'), Action::BeginningOfLine),
+    (Key::Ctrl('e'), Action::EndOfLine),
+    (Key::Ctrl('b'), Action::BackwardChar),
+    (Key::Ctrl('f'), Action::ForwardChar),
+    (Key::Ctrl('k'), Action::KillLine),
+    (Key::Ctrl('u'), Action::BackwardKillLine),
+    (Key::Ctrl('y'), Action::Yank),
+    (Key::Ctrl('t'), Action::TransposeChars),
+    (Key::Ctrl('_'), Action::Undo),
+    (Key::Ctrl('l'), Action::ClearScreen),
+    (Key::Alt('d'), Action::KillWord),
+    (Key::Alt('b'), Action::BackwardWord),
+    (Key::Alt('f'), Action::ForwardWord),
+    (Key::Alt('.'), Action::YankLastArg),
+    (Key::Alt('_'), Action::YankLastArg),
+    (Key::Alt('q'), Action::PushLine),
+    (Key::Alt('\''), Action::ToggleQuote),
+];
+
+// On top of the readline ones, fish's. Ctrl-F and Right take the suggestion
+// in every preset
+const FISH: Bindings = &[
+    (Key::Alt('\x7f'), Action::BackwardKillWord),
+    (Key::AltLeft, Action::BackwardWord),
+    (Key::AltRight, Action::ForwardWord),
+    (Key::AltUp, Action::HistoryTokenSearchBackward),
+    (Key::AltDown, Action::HistoryTokenSearchForward),
+    (Key::Alt('e'), Action::EditCommandLine),
+    (Key::Alt('v'), Action::EditCommandLine),
+];
+
+// Escape switches to vi's normal mode, only control keys are bound on top
+// of the common ones
+const VI: Bindings = &[
+    (Key::Escape, Action::ViCommandMode),
+    (Key::Ctrl('o'), Action::PushLine),
+    (Key::Ctrl('y'), Action::YankLastArg),
+];
+
+// Every preset, the first one by default
+pub const PRESETS: &[(&str, &[Bindings])] = &[
+    ("readline", &[COMMON, READLINE]),
+    ("fish", &[COMMON, READLINE, FISH]),
+    ("vi", &[COMMON, VI]),
+];
+
+impl Keymap {
+    pub fn get(&self, keys: &[Key]) -> Option<&Binding> {
+        self.bindings.get(keys)
+    }
+
+    // Whether a longer sequence bound starts with the keys
+    pub fn is_prefix(&self, keys: &[Key]) -> bool {
+        self.bindings
+            .keys()
+            .any(|bound| bound.len() > keys.len() && bound.starts_with(keys))
+    }
+
+    pub fn preset(name: &str) -> Option<Self> {
+        let (preset, layers) = PRESETS.iter().find(|(preset, _)| *preset == name)?;
+
+        Some(Keymap {
+            bindings: layers
+                .iter()
+                .flat_map(|layer| layer.iter())
+                .map(|&(key, action)| (vec![key], Binding::Action(action)))
+                .collect(),
+            preset,
+        })
+    }
+
+    pub fn preset_name(&self) -> &'static str {
+        self.preset
+    }
+
+    // Bind the keys on top of the preset's bindings, until another preset is
+    // picked
+    pub fn bind(&mut self, keys: Vec<Key>, binding: Binding) {
+        self.bindings.insert(keys, binding);
+    }
+
+    // Whether the keys were bound, a character types itself again
+    pub fn unbind(&mut self, keys: &[Key]) -> bool {
+        self.bindings.remove(keys).is_some()
+    }
+
+    pub fn bindings(&self) -> impl Iterator<Item = (&[Key], &Binding)> {
+        self.bindings
+            .iter()
+            .map(|(keys, binding)| (keys.as_slice(), binding))
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap::preset(PRESETS[0].0).unwrap()
+    }
+}
@@ -0,0 +1,279 @@
+use std::fmt;
+use std::io;
+
+use super::terminal;
+use crate::signal;
+
+// How long to wait for the rest of an escape sequence, in milliseconds
+const ESCAPE_TIMEOUT: i32 = 50;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Key {
+    Char(char),
+    Ctrl(char),
+    Alt(char),
+    Enter,
+    Tab,
+    Backspace,
+    Delete,
+    Escape,
+    Left,
+    Right,
+    Up,
+    Down,
+    // The arrows with Alt held
+    AltLeft,
+    AltRight,
+    AltUp,
+    AltDown,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Unknown,
+}
+
+// The keys sent as escape sequences, written the way `bind` takes them
+const SEQUENCES: &[(&str, Key)] = &[
+    ("\\e", Key::Escape),
+    ("\\e[A", Key::Up),
+    ("\\e[B", Key::Down),
+    ("\\e[C", Key::Right),
+    ("\\e[D", Key::Left),
+    ("\\e[1;3A", Key::AltUp),
+    ("\\e[1;3B", Key::AltDown),
+    ("\\e[1;3C", Key::AltRight),
+    ("\\e[1;3D", Key::AltLeft),
+    ("\\e[H", Key::Home),
+    ("\\e[F", Key::End),
+    ("\\e[3~", Key::Delete),
+    ("\\e[5~", Key::PageUp),
+    ("\\e[6~", Key::PageDown),
+];
+
+impl Key {
+    // A key written like in readline's inputrc: `\C-a` for control keys,
+    // `\M-d` or `\ed` for meta ones, the escape sequence of the others or
+    // just the character typed. Sequences of them are written one after the
+    // other, like `\C-x\C-e`
+    pub fn parse(spec: &str) -> Option<Vec<Key>> {
+        let mut keys = vec![];
+        let mut rest = spec;
+
+        while !rest.is_empty() {
+            let (key, after) = Key::parse_first(rest)?;
+            keys.push(key);
+            rest = after;
+        }
+
+        match keys.is_empty() {
+            true => None,
+            false => Some(keys),
+        }
+    }
+
+    // The first key written in `spec` and what's after it
+    fn parse_first(spec: &str) -> Option<(Key, &str)> {
+        // Before `\e` alone, which makes the key after it a meta one
+        if let Some(&(seq, key)) = SEQUENCES
+            .iter()
+            .find(|(seq, _)| *seq != "\\e" && spec.starts_with(seq))
+        {
+            return Some((key, &spec[seq.len()..]));
+        }
+
+        if spec == "\\e" {
+            return Some((Key::Escape, ""));
+        }
+
+        if let Some(rest) = spec
+            .strip_prefix("\\M-")
+            .or_else(|| spec.strip_prefix("\\e"))
+        {
+            let (key, rest) = Key::parse_first(rest)?;
+            return match key {
+                Key::Char(c) if c.is_ascii_graphic() || c == ' ' => Some((Key::Alt(c), rest)),
+                Key::Backspace => Some((Key::Alt('\x7f'), rest)),
+                _ => None,
+            };
+        }
+
+        if let Some(rest) = spec.strip_prefix("\\C-") {
+            let c = rest.chars().next()?;
+            let byte = match c {
+                '?' => 0x7f,
+                c if ('@'..='_').contains(&c.to_ascii_uppercase()) => {
+                    c.to_ascii_uppercase() as u8 & 0x1f
+                }
+                _ => return None,
+            };
+            return Some((control(byte), &rest[c.len_utf8()..]));
+        }
+
+        if let Some(rest) = spec.strip_prefix('\\') {
+            let key = match rest.chars().next()? {
+                'r' | 'n' => Key::Enter,
+                't' => Key::Tab,
+                '\\' => Key::Char('\\'),
+                '"' => Key::Char('"'),
+                _ => return None,
+            };
+            return Some((key, &rest[1..]));
+        }
+
+        let c = spec.chars().next()?;
+        match c.is_control() {
+            true => None,
+            false => Some((Key::Char(c), &spec[c.len_utf8()..])),
+        }
+    }
+}
+
+// In the notation `Key::parse` takes
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some((seq, _)) = SEQUENCES.iter().find(|(_, key)| key == self) {
+            return f.write_str(seq);
+        }
+
+        match self {
+            Key::Char('\\') => f.write_str("\\\\"),
+            Key::Char('"') => f.write_str("\\\""),
+            Key::Char(c) => write!(f, "{}", c),
+            Key::Ctrl(c) => write!(f, "\\C-{}", c),
+            Key::Alt('\x7f') => f.write_str("\\e\\C-?"),
+            Key::Alt(c) => write!(f, "\\e{}", Key::Char(*c)),
+            Key::Enter => f.write_str("\\C-m"),
+            Key::Tab => f.write_str("\\C-i"),
+            Key::Backspace => f.write_str("\\C-?"),
+            _ => f.write_str("?"),
+        }
+    }
+}
+
+// Read the next key press, `None` once the terminal is closed. Waiting for it
+// is cut short by Ctrl-C's interrupt, an `Interrupted` error
+pub fn read() -> io::Result<Option<Key>> {
+    while !terminal::input_pending(-1) {
+        if signal::is_pending(signal::SIGINT) {
+            return Err(io::ErrorKind::Interrupted.into());
+        }
+
+        // Not a signal, the read tells what's wrong
+        if io::Error::last_os_error().kind() != io::ErrorKind::Interrupted {
+            break;
+        }
+    }
+
+    let byte = match terminal::read_byte()? {
+        Some(byte) => byte,
+        None => return Ok(None),
+    };
+
+    let key = match byte {
+        0x1b => read_escape()?,
+        0x00..=0x1f | 0x7f => control(byte),
+        _ => Key::Char(read_utf8(byte)?),
+    };
+
+    Ok(Some(key))
+}
+
+// The key a control character is sent for
+fn control(byte: u8) -> Key {
+    match byte {
+        b'\r' | b'\n' => Key::Enter,
+        b'\t' => Key::Tab,
+        0x7f | 0x08 => Key::Backspace,
+        0x1b => Key::Escape,
+        0x01..=0x1a => Key::Ctrl((byte + 0x60) as char),
+        _ => Key::Ctrl((byte + 0x40) as char),
+    }
+}
+
+fn read_escape() -> io::Result<Key> {
+    if !terminal::input_pending(ESCAPE_TIMEOUT) {
+        return Ok(Key::Escape);
+    }
+
+    let byte = match terminal::read_byte()? {
+        Some(byte) => byte,
+        None => return Ok(Key::Escape),
+    };
+
+    match byte {
+        b'[' => read_csi(),
+        b'O' => Ok(match terminal::read_byte()? {
+            Some(b'A') => Key::Up,
+            Some(b'B') => Key::Down,
+            Some(b'C') => Key::Right,
+            Some(b'D') => Key::Left,
+            Some(b'H') => Key::Home,
+            Some(b'F') => Key::End,
+            _ => Key::Unknown,
+        }),
+        0x7f => Ok(Key::Alt('\x7f')),
+        0x20..=0x7e => Ok(Key::Alt(byte as char)),
+        _ => Ok(Key::Unknown),
+    }
+}
+
+// Control sequences look like `ESC [ params final` where the parameters are
+// digits and semicolons
+fn read_csi() -> io::Result<Key> {
+    let mut params = String::new();
+
+    loop {
+        let byte = match terminal::read_byte()? {
+            Some(byte) => byte,
+            None => return Ok(Key::Unknown),
+        };
+
+        if byte.is_ascii_digit() || byte == b';' {
+            params.push(byte as char);
+            continue;
+        }
+
+        return Ok(match (byte, params.as_str()) {
+            (b'A', "1;3") => Key::AltUp,
+            (b'B', "1;3") => Key::AltDown,
+            (b'C', "1;3") => Key::AltRight,
+            (b'D', "1;3") => Key::AltLeft,
+            (b'A', _) => Key::Up,
+            (b'B', _) => Key::Down,
+            (b'C', _) => Key::Right,
+            (b'D', _) => Key::Left,
+            (b'H', _) => Key::Home,
+            (b'F', _) => Key::End,
+            (b'~', "1") | (b'~', "7") => Key::Home,
+            (b'~', "4") | (b'~', "8") => Key::End,
+            (b'~', "3") => Key::Delete,
+            (b'~', "5") => Key::PageUp,
+            (b'~', "6") => Key::PageDown,
+            _ => Key::Unknown,
+        });
+    }
+}
+
+fn read_utf8(first: u8) -> io::Result<char> {
+    let len = match first {
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf7 => 4,
+        _ => 1,
+    };
+
+    let mut bytes = vec![first];
+
+    while bytes.len() < len {
+        match terminal::read_byte()? {
+            Some(byte) => bytes.push(byte),
+            None => break,
+        }
+    }
+
+    Ok(std::str::from_utf8(&bytes)
+        .ok()
+        .and_then(|s| s.chars().next())
+        .unwrap_or(char::REPLACEMENT_CHARACTER))
+}
@@ -0,0 +1,100 @@
+use std::io;
+use std::mem;
+use std::os::raw::c_void;
+
+use crate::sys;
+
+const STDIN: i32 = 0;
+const STDOUT: i32 = 1;
+
+// Keeps the terminal in raw mode, restoring the original settings when dropped
+pub struct RawMode {
+    original: sys::Termios,
+}
+
+impl RawMode {
+    pub fn enable() -> io::Result<Self> {
+        let mut original: sys::Termios = unsafe { mem::zeroed() };
+
+        if unsafe { sys::tcgetattr(STDIN, &mut original) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        // Signals are still generated by the terminal so ^C and ^Z keep
+        // working while a line is being edited
+        let mut raw = original;
+        raw.c_iflag &= !(sys::ICRNL | sys::IXON);
+        raw.c_lflag &= !(sys::ICANON | sys::ECHO | sys::IEXTEN);
+        raw.c_cc[sys::VMIN] = 1;
+        raw.c_cc[sys::VTIME] = 0;
+
+        if unsafe { sys::tcsetattr(STDIN, sys::TCSADRAIN, &raw) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(RawMode { original })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        unsafe { sys::tcsetattr(STDIN, sys::TCSADRAIN, &self.original) };
+    }
+}
+
+// Read a single byte straight from the terminal, bypassing the buffering of
+// `io::stdin` so nothing typed ahead is kept from the commands we run
+pub fn read_byte() -> io::Result<Option<u8>> {
+    let mut byte = 0u8;
+
+    loop {
+        match unsafe { sys::read(STDIN, &mut byte as *mut u8 as *mut c_void, 1) } {
+            1 => return Ok(Some(byte)),
+            0 => return Ok(None),
+            _ => {
+                let error = io::Error::last_os_error();
+
+                if error.kind() != io::ErrorKind::Interrupted {
+                    return Err(error);
+                }
+            }
+        }
+    }
+}
+
+// Whether more input arrives within `timeout` milliseconds, used to tell a
+// lone escape key from the start of an escape sequence
+pub fn input_pending(timeout: i32) -> bool {
+    let mut fd = sys::PollFd {
+        fd: STDIN,
+        events: sys::POLLIN,
+        revents: 0,
+    };
+
+    unsafe { sys::poll(&mut fd, 1, timeout) > 0 }
+}
+
+// Whether the terminal can't be expected to understand escape sequences, as
+// in Emacs shell-mode or a CI log, or what it is isn't known
+pub fn is_dumb() -> bool {
+    matches!(
+        std::env::var("TERM").as_deref(),
+        Err(_) | Ok("") | Ok("dumb")
+    )
+}
+
+pub fn columns() -> usize {
+    let mut size = sys::WinSize::default();
+
+    if unsafe { sys::ioctl(STDOUT, sys::TIOCGWINSZ, &mut size as *mut sys::WinSize) } == 0
+        && size.ws_col > 0
+    {
+        return size.ws_col as usize;
+    }
+
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.parse().ok())
+        .filter(|&columns| columns > 0)
+        .unwrap_or(80)
+}
@@ -0,0 +1,297 @@
+// The normal mode of the vi preset: Escape leaves insert mode and the keys
+// typed are then motions, operators taking a motion and commands going back
+// to inserting. Counts repeat motions, `cw` changes to the end of the word as
+// in vi
+
+use super::{Action, Editor};
+
+#[derive(Default)]
+pub struct Vi {
+    // Whether keys are commands rather than typed in
+    pub normal: bool,
+    count: Option<usize>,
+    // `d`, `c` or `y` waiting for its motion
+    operator: Option<char>,
+    // What the last delete or yank took, for `p` and `P`
+    register: String,
+}
+
+// Whitespace, letters digits and `_`, and everything else, `W` `B` and `E`
+// only tell blanks from the rest
+fn class(c: char, big: bool) -> u8 {
+    match c {
+        c if c.is_whitespace() => 0,
+        _ if big => 1,
+        c if c.is_alphanumeric() || c == '_' => 1,
+        _ => 2,
+    }
+}
+
+impl<'a> Editor<'a> {
+    // Escape puts the cursor on the character before it, like in vi
+    pub(super) fn vi_command_mode(&mut self) {
+        if !self.vi.normal && self.cursor > self.line_start() {
+            self.cursor = self.prev_boundary();
+        }
+        self.vi.normal = true;
+        self.vi.count = None;
+        self.vi.operator = None;
+    }
+
+    pub(super) fn vi_command(&mut self, c: char) {
+        match (c, self.vi.count) {
+            ('1'..='9', _) | ('0', Some(_)) => {
+                let digit = c.to_digit(10).unwrap() as usize;
+                self.vi.count = Some(self.vi.count.unwrap_or(0) * 10 + digit);
+                return;
+            }
+            _ => {}
+        }
+
+        let count = self.vi.count.take().unwrap_or(1);
+
+        if let Some(operator) = self.vi.operator.take() {
+            return self.vi_operate(operator, c, count);
+        }
+
+        match c {
+            'd' | 'c' | 'y' => {
+                self.vi.count = Some(count).filter(|&count| count > 1);
+                self.vi.operator = Some(c);
+            }
+
+            'i' => self.vi.normal = false,
+            'a' => {
+                self.cursor = self.next_boundary().min(self.line_end());
+                self.vi.normal = false;
+            }
+            'I' => {
+                self.cursor = self.line_start();
+                self.vi.normal = false;
+            }
+            'A' => {
+                self.cursor = self.line_end();
+                self.vi.normal = false;
+            }
+
+            'x' => self.vi_operate('d', 'l', count),
+            'X' => self.vi_operate('d', 'h', count),
+            'D' => self.vi_operate('d', '$', 1),
+            'C' => self.vi_operate('c', '$', 1),
+            's' => self.vi_operate('c', 'l', count),
+            'S' => self.vi_operate('c', 'c', 1),
+
+            'p' | 'P' => {
+                if self.vi.register.is_empty() {
+                    return;
+                }
+                if c == 'p' && self.cursor < self.line_end() {
+                    self.cursor = self.next_boundary();
+                }
+                let text = self.vi.register.repeat(count);
+                self.buffer.insert_str(self.cursor, &text);
+                self.cursor += text.len();
+                self.cursor = self.prev_boundary();
+            }
+
+            'k' => (0..count).for_each(|_| self.perform(Action::PreviousHistory)),
+            'j' => (0..count).for_each(|_| self.perform(Action::NextHistory)),
+
+            _ => {
+                if let Some((target, _)) = self.vi_motion(c, count, false) {
+                    self.cursor = target;
+                }
+            }
+        }
+
+        self.vi_clamp();
+    }
+
+    // Apply the operator to the text between the cursor and where the
+    // motion goes, doubled like `dd` it takes the whole line
+    fn vi_operate(&mut self, operator: char, motion: char, count: usize) {
+        let range = match motion {
+            _ if motion == operator => Some((self.line_start(), self.line_end())),
+            _ => self
+                .vi_motion(motion, count, operator == 'c')
+                .map(|(target, inclusive)| {
+                    let (start, mut end) = match target < self.cursor {
+                        true => (target, self.cursor),
+                        false => (self.cursor, target),
+                    };
+                    if inclusive {
+                        end = self.boundary_after(end);
+                    }
+                    (start, end)
+                }),
+        };
+
+        let (start, end) = match range {
+            Some(range) => range,
+            None => return self.vi_clamp(),
+        };
+
+        self.vi.register = self.buffer[start..end].to_string();
+        if operator != 'y' {
+            self.buffer.replace_range(start..end, "");
+        }
+        self.cursor = start;
+
+        match operator {
+            'c' => self.vi.normal = false,
+            _ => self.vi_clamp(),
+        }
+    }
+
+    // Where the motion takes the cursor, and whether an operator applied to
+    // it takes the character there too. `cw` is `ce`, the blanks after the
+    // word are left alone
+    fn vi_motion(&self, motion: char, count: usize, change: bool) -> Option<(usize, bool)> {
+        let mut target = self.cursor;
+        let big = motion.is_ascii_uppercase();
+
+        for _ in 0..count {
+            target = match motion {
+                'h' => self.buffer[self.line_start()..target]
+                    .char_indices()
+                    .next_back()
+                    .map_or(target, |(i, _)| self.line_start() + i),
+                'l' | ' ' => self.boundary_after(target).min(self.line_end()),
+                '0' => self.line_start(),
+                '^' => {
+                    let line = &self.buffer[self.line_start()..self.line_end()];
+                    self.line_start() + (line.len() - line.trim_start().len())
+                }
+                '$' => self.line_end(),
+                'w' | 'W' if change && !self.at_blank(target) => self.word_end(target, big),
+                'w' | 'W' => self.word_start(target, big),
+                'b' | 'B' => self.prev_word_start(target, big),
+                'e' | 'E' => self.word_end(target, big),
+                _ => return None,
+            };
+        }
+
+        let inclusive = matches!(motion, 'e' | 'E')
+            || (change && matches!(motion, 'w' | 'W') && !self.at_blank(self.cursor));
+        Some((target, inclusive))
+    }
+
+    // Normal mode keeps the cursor on a character of the line
+    fn vi_clamp(&mut self) {
+        if self.vi.normal && self.cursor == self.line_end() && self.cursor > self.line_start() {
+            self.cursor = self.prev_boundary();
+        }
+    }
+
+    fn at_blank(&self, at: usize) -> bool {
+        self.buffer[at..]
+            .chars()
+            .next()
+            .is_none_or(char::is_whitespace)
+    }
+
+    fn boundary_after(&self, at: usize) -> usize {
+        self.buffer[at..]
+            .chars()
+            .next()
+            .map_or(at, |c| at + c.len_utf8())
+    }
+
+    // The start of the next word, past the rest of the one `at` is in
+    fn word_start(&self, at: usize, big: bool) -> usize {
+        let mut chars = self.buffer[at..].char_indices().peekable();
+        let first = chars.peek().map(|&(_, c)| class(c, big));
+
+        if first != Some(0) {
+            while chars
+                .next_if(|&(_, c)| Some(class(c, big)) == first)
+                .is_some()
+            {}
+        }
+        while chars.next_if(|&(_, c)| class(c, big) == 0).is_some() {}
+
+        at + chars.peek().map_or(self.buffer.len() - at, |&(i, _)| i)
+    }
+
+    // The start of the word before `at`, or of the one it's in
+    fn prev_word_start(&self, at: usize, big: bool) -> usize {
+        let mut chars = self.buffer[..at].char_indices().rev().peekable();
+
+        while chars.next_if(|&(_, c)| class(c, big) == 0).is_some() {}
+        let word = chars.peek().map(|&(_, c)| class(c, big));
+        let mut start = chars.peek().map_or(0, |&(i, _)| i);
+
+        while let Some((i, _)) = chars.next_if(|&(_, c)| Some(class(c, big)) == word) {
+            start = i;
+        }
+        start
+    }
+
+    // The last character of the word after `at`, or of the one it's in
+    fn word_end(&self, at: usize, big: bool) -> usize {
+        let mut chars = self.buffer[at..].char_indices().skip(1).peekable();
+
+        while chars.next_if(|&(_, c)| class(c, big) == 0).is_some() {}
+        let word = chars.peek().map(|&(_, c)| class(c, big));
+        let mut end = chars.peek().map_or(at, |&(i, _)| at + i);
+
+        while let Some((i, _)) = chars.next_if(|&(_, c)| Some(class(c, big)) == word) {
+            end = at + i;
+        }
+        end
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::shell::Shell;
+
+    use super::super::Editor;
+
+    fn run(shell: &Shell, line: &str, keys: &str) -> (String, usize, bool) {
+        let mut editor = Editor::new("> ", shell, line.to_string());
+        editor.perform(super::Action::ViCommandMode);
+
+        for c in keys.chars() {
+            match editor.vi.normal {
+                true => editor.vi_command(c),
+                false => editor.insert(c),
+            }
+        }
+        (editor.buffer, editor.cursor, editor.vi.normal)
+    }
+
+    #[test]
+    fn test_motions() {
+        let shell = Shell::new();
+        let line = "git commit -m 'fix it'";
+
+        assert_eq!(run(&shell, line, "0").1, 0);
+        assert_eq!(run(&shell, line, "0w").1, 4);
+        assert_eq!(run(&shell, line, "03w").1, 12);
+        assert_eq!(run(&shell, line, "03W").1, 14);
+        assert_eq!(run(&shell, line, "b").1, 19);
+        assert_eq!(run(&shell, line, "bb").1, 15);
+        assert_eq!(run(&shell, line, "0e").1, 2);
+        assert_eq!(run(&shell, line, "0$").1, 21);
+        assert_eq!(run(&shell, "  ls", "0^").1, 2);
+        assert_eq!(run(&shell, line, "0lllh").1, 2);
+    }
+
+    #[test]
+    fn test_operators() {
+        let shell = Shell::new();
+        let line = "git commit -m 'fix it'";
+
+        assert_eq!(run(&shell, line, "0dw").0, "commit -m 'fix it'");
+        assert_eq!(run(&shell, line, "0d2w").0, "-m 'fix it'");
+        assert_eq!(run(&shell, line, "0cwpush").0, "push commit -m 'fix it'");
+        assert_eq!(run(&shell, line, "0wD").0, "git ");
+        assert_eq!(run(&shell, line, "dd").0, "");
+        assert_eq!(run(&shell, line, "0ywP").0, "git git commit -m 'fix it'");
+        assert_eq!(run(&shell, "abc", "0xp"), ("bac".into(), 1, true));
+        assert_eq!(run(&shell, "abc", "0ccx"), ("x".into(), 1, false));
+        assert_eq!(run(&shell, "ls", "Aa"), ("lsa".into(), 3, false));
+        assert_eq!(run(&shell, "ls", "0i-"), ("-ls".into(), 1, false));
+    }
+}
@@ -0,0 +1,118 @@
+// Shell patterns: `*` matches any string, `?` any character and `[...]` any
+// of the characters in the brackets, or any other with `[!...]`. A backslash
+// makes the character after it match itself
+
+pub fn matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    matches_at(&pattern, &text)
+}
+
+fn matches_at(pattern: &[char], text: &[char]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+
+        Some('*') => {
+            let rest = &pattern[pattern.iter().take_while(|&&c| c == '*').count()..];
+            (0..=text.len()).any(|i| matches_at(rest, &text[i..]))
+        }
+
+        Some('?') => !text.is_empty() && matches_at(&pattern[1..], &text[1..]),
+
+        Some('[') => match (bracket(&pattern[1..]), text.first()) {
+            (Some((chars, len)), Some(&c)) => {
+                chars(c) && matches_at(&pattern[1 + len..], &text[1..])
+            }
+            (Some(_), None) => false,
+            (None, c) => c == Some(&'[') && matches_at(&pattern[1..], &text[1..]),
+        },
+
+        Some('\\') if pattern.len() > 1 => {
+            text.first() == Some(&pattern[1]) && matches_at(&pattern[2..], &text[1..])
+        }
+
+        Some(c) => text.first() == Some(c) && matches_at(&pattern[1..], &text[1..]),
+    }
+}
+
+// The bracket expression `pattern` starts with, just past its `[`, as what
+// tells whether a character is one of those it stands for along with its
+// length up to and including the `]`. `None` when it isn't closed, then the
+// `[` is just a character
+fn bracket(pattern: &[char]) -> Option<(impl Fn(char) -> bool, usize)> {
+    let negated = matches!(pattern.first(), Some('!') | Some('^'));
+    let mut i = negated as usize;
+    let mut ranges = vec![];
+
+    // A `]` right at the start is one of the characters
+    let start = i;
+
+    loop {
+        let first = match pattern.get(i) {
+            Some(']') if i > start => break,
+            Some('\\') => {
+                i += 1;
+                *pattern.get(i)?
+            }
+            Some(&first) => first,
+            None => return None,
+        };
+
+        match (pattern.get(i + 1), pattern.get(i + 2)) {
+            (Some('-'), Some(&last)) if last != ']' => {
+                ranges.push((first, last));
+                i += 3;
+            }
+            _ => {
+                ranges.push((first, first));
+                i += 1;
+            }
+        }
+    }
+
+    let chars =
+        move |c: char| ranges.iter().any(|&(first, last)| first <= c && c <= last) != negated;
+    Some((chars, i + 1))
+}
+
+// Backslash the characters that would otherwise mean something in a pattern
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+
+    for c in text.chars() {
+        if "*?[]\\".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_matches() {
+        assert!(matches("*", ""));
+        assert!(matches("*.rs", "main.rs"));
+        assert!(!matches("*.rs", "main.rs.bak"));
+        assert!(matches("a*b*c", "aXbYbc"));
+        assert!(matches("?at", "cat"));
+        assert!(!matches("?at", "at"));
+        assert!(matches("[ch]at", "hat"));
+        assert!(matches("[!ch]at", "bat"));
+        assert!(!matches("[^ch]at", "cat"));
+        assert!(matches("[a-c0-9]x", "7x"));
+        assert!(matches("[]]", "]"));
+        assert!(matches("[a-]", "-"));
+        assert!(matches("[ab", "[ab"));
+        assert!(matches("\\*", "*"));
+        assert!(!matches("\\*", "x"));
+        assert!(matches("ñ?", "ñé"));
+        assert!(matches(&escape("a*[b]?"), "a*[b]?"));
+        assert!(!matches(&escape("a*"), "ab"));
+    }
+}
@@ -0,0 +1,352 @@
+// The commands typed in, kept in memory and written through a backend that
+// stores them along with where and when they ran and how they went
+
+mod file;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+use std::env;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::sys;
+
+pub use self::file::FileBackend;
+#[cfg(feature = "sqlite")]
+pub use self::sqlite::SqliteBackend;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Entry {
+    pub command: String,
+    // The working directory the command ran in
+    pub cwd: String,
+    pub host: String,
+    // Tells apart the shells that shared the history file
+    pub session: String,
+    // When it started, in seconds since the epoch
+    pub time: u64,
+    pub duration: Duration,
+    pub status: i32,
+}
+
+// `RUSH_HISTFILE`, or `~/.rush_history` by default
+pub fn default_path() -> Option<PathBuf> {
+    match env::var_os("RUSH_HISTFILE") {
+        Some(path) => Some(PathBuf::from(path)),
+        None => env::var_os("HOME").map(|home| PathBuf::from(home).join(".rush_history")),
+    }
+}
+
+// An SQLite database for a `.db` or `.sqlite` file, a text file otherwise
+pub fn open_backend(path: &Path) -> io::Result<Box<dyn Backend>> {
+    let database = matches!(
+        path.extension().and_then(|ext| ext.to_str()),
+        Some("db") | Some("sqlite")
+    );
+
+    match database {
+        #[cfg(feature = "sqlite")]
+        true => Ok(Box::new(SqliteBackend::open(path)?)),
+        #[cfg(not(feature = "sqlite"))]
+        true => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "built without SQLite support",
+        )),
+        false => Ok(Box::new(FileBackend::new(path.to_path_buf()))),
+    }
+}
+
+pub trait Backend {
+    // Every entry stored, oldest first
+    fn load(&mut self) -> io::Result<Vec<Entry>>;
+
+    fn append(&mut self, entry: &Entry) -> io::Result<()>;
+
+    fn clear(&mut self) -> io::Result<()>;
+}
+
+// Keeps nothing, the history lasts as long as the shell
+pub struct Memory;
+
+impl Backend for Memory {
+    fn load(&mut self) -> io::Result<Vec<Entry>> {
+        Ok(vec![])
+    }
+
+    fn append(&mut self, _: &Entry) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+pub struct History {
+    entries: Vec<Entry>,
+    backend: Box<dyn Backend>,
+    // Whether the last entry is still running, it's stored once it finishes
+    running: bool,
+    host: String,
+    session: String,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        History {
+            entries: vec![],
+            backend: Box::new(Memory),
+            running: false,
+            host: sys::hostname(),
+            session: new_session(),
+        }
+    }
+}
+
+// The shell's pid and start time, unique enough among the shells of a host
+fn new_session() -> String {
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_millis());
+
+    format!("{:x}-{:x}", unsafe { sys::getpid() }, time)
+}
+
+impl History {
+    pub fn open(mut backend: Box<dyn Backend>) -> io::Result<Self> {
+        Ok(History {
+            entries: backend.load()?,
+            backend,
+            ..History::default()
+        })
+    }
+
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    pub fn session(&self) -> &str {
+        &self.session
+    }
+
+    pub fn push(&mut self, line: &str) {
+        let line = line.trim_end_matches('\n');
+        self.running = false;
+
+        if !line.trim().is_empty() {
+            self.entries.push(Entry {
+                command: line.to_string(),
+                cwd: env::current_dir()
+                    .map(|dir| dir.display().to_string())
+                    .unwrap_or_default(),
+                time: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |time| time.as_secs()),
+                host: self.host.clone(),
+                session: self.session.clone(),
+                ..Entry::default()
+            });
+            self.running = true;
+        }
+    }
+
+    // The last command pushed is done, store it with how it went
+    pub fn finish(&mut self, duration: Duration, status: i32) -> io::Result<()> {
+        if !self.running {
+            return Ok(());
+        }
+        self.running = false;
+
+        match self.entries.last_mut() {
+            Some(entry) => {
+                entry.duration = duration;
+                entry.status = status;
+                self.backend.append(entry)
+            }
+            None => Ok(()),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.running = false;
+        let _ = self.backend.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn get(&self, i: usize) -> Option<&str> {
+        self.entries.get(i).map(|entry| entry.command.as_str())
+    }
+
+    pub fn entry(&self, i: usize) -> Option<&Entry> {
+        self.entries.get(i)
+    }
+
+    // What the newest entry starting with `prefix` has after it, entries of
+    // more than one line left out
+    pub fn suggest(&self, prefix: &str) -> Option<&str> {
+        self.entries.iter().rev().find_map(|entry| {
+            let rest = entry.command.strip_prefix(prefix)?;
+            Some(rest).filter(|rest| !rest.is_empty() && !entry.command.contains('\n'))
+        })
+    }
+
+    // Replace `!!` with the last command, `!n` with command n, `!-n` with the
+    // nth last and `!prefix` with the last one starting with prefix, except in
+    // single quotes or after a backslash. `None` when the line has none, the
+    // designator that didn't match any command as the error
+    pub fn expand(&self, line: &str) -> Result<Option<String>, String> {
+        let mut out = String::new();
+        let mut chars = line.char_indices().peekable();
+        let mut single = false;
+        let mut double = false;
+        let mut expanded = false;
+        let mut prev = None;
+
+        while let Some((i, c)) = chars.next() {
+            let literal = match (c, chars.peek().map(|&(_, c)| c)) {
+                ('\\', Some(next)) if !single => {
+                    out.push(c);
+                    out.push(next);
+                    chars.next();
+                    prev = Some(next);
+                    continue;
+                }
+                ('\'', _) if !double => {
+                    single = !single;
+                    true
+                }
+                ('"', _) if !single => {
+                    double = !double;
+                    true
+                }
+                ('!', _) if single || matches!(prev, Some('$') | Some('{') | Some('&')) => true,
+                ('!', None) => true,
+                ('!', Some(next)) => next.is_whitespace() || "=(\"".contains(next),
+                _ => true,
+            };
+            prev = Some(c);
+
+            if literal {
+                out.push(c);
+                continue;
+            }
+
+            let rest = &line[i + 1..];
+            let len = match rest.strip_prefix('!') {
+                Some(_) => 1,
+                None => rest
+                    .find(|c: char| c.is_whitespace() || ";&|()<>\"'".contains(c))
+                    .unwrap_or(rest.len()),
+            };
+            let designator = &rest[..len];
+
+            let found = match designator {
+                "!" => self.len().checked_sub(1),
+                _ => match designator.parse::<i64>() {
+                    Ok(n) if n < 0 => self.len().checked_sub(n.unsigned_abs() as usize),
+                    Ok(n) => (n as usize).checked_sub(1).filter(|&i| i < self.len()),
+                    Err(_) => (0..self.len())
+                        .rev()
+                        .find(|&i| self.entries[i].command.starts_with(designator)),
+                },
+            };
+
+            match found {
+                Some(i) => out.push_str(&self.entries[i].command),
+                None => return Err(format!("!{}", designator)),
+            }
+
+            for _ in designator.chars() {
+                chars.next();
+            }
+            expanded = true;
+        }
+
+        Ok(if expanded { Some(out) } else { None })
+    }
+
+    // Entries along with their 1-based history number
+    #[cfg(test)]
+    pub fn numbered(&self) -> impl Iterator<Item = (usize, &str)> {
+        self.entries().map(|(n, entry)| (n, entry.command.as_str()))
+    }
+
+    // Entries with everything known about them, numbered like `numbered`
+    pub fn entries(&self) -> impl Iterator<Item = (usize, &Entry)> {
+        self.entries
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| (i + 1, entry))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_history_numbering() {
+        let mut history = History::default();
+        history.push("ls\n");
+        history.push("  \n");
+        history.push("cd /tmp");
+
+        assert_eq!(
+            history.numbered().collect::<Vec<_>>(),
+            vec![(1, "ls"), (2, "cd /tmp")]
+        );
+
+        history.clear();
+        assert_eq!(history.len(), 0);
+    }
+
+    #[test]
+    fn test_expand() {
+        let mut history = History::default();
+        for line in &["ls -l", "cargo build", "echo hi"] {
+            history.push(line);
+        }
+
+        let expand = |line| history.expand(line);
+        assert_eq!(expand("sudo !!"), Ok(Some("sudo echo hi".into())));
+        assert_eq!(expand("!1 && !-2"), Ok(Some("ls -l && cargo build".into())));
+        assert_eq!(expand("!car; !e"), Ok(Some("cargo build; echo hi".into())));
+        assert_eq!(expand("! true; a != b; '!!' \\!! $! ${!m[@]} &!"), Ok(None));
+        assert_eq!(expand("\"!!\""), Ok(Some("\"echo hi\"".into())));
+        assert_eq!(expand("!nope"), Err("!nope".into()));
+        assert_eq!(expand("!9"), Err("!9".into()));
+        assert_eq!(expand("!-4"), Err("!-4".into()));
+    }
+
+    #[test]
+    fn test_suggest() {
+        let mut history = History::default();
+        for line in &["git status", "git log", "for x in a\nb", "git"] {
+            history.push(line);
+        }
+
+        assert_eq!(history.suggest("git"), Some(" log"));
+        assert_eq!(history.suggest("git s"), Some("tatus"));
+        assert_eq!(history.suggest("for"), None);
+        assert_eq!(history.suggest("ls"), None);
+    }
+
+    #[test]
+    fn test_finish() {
+        let mut history = History::default();
+        history.push("false");
+        history.finish(Duration::from_millis(3), 1).unwrap();
+        history.push(" ");
+        history.finish(Duration::from_millis(3), 0).unwrap();
+
+        let (_, entry) = history.entries().next().unwrap();
+        assert_eq!(entry.status, 1);
+        assert_eq!(entry.duration, Duration::from_millis(3));
+        assert_eq!(history.len(), 1);
+    }
+}
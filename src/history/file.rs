@@ -0,0 +1,138 @@
+// History kept in a file with one entry per line, its fields separated by
+// tabs: start time, duration in milliseconds, status, host, session,
+// directory and command.
+// Lines that aren't records, as in a plain list of commands, are read as
+// commands with nothing else known about them
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use super::{Backend, Entry};
+
+pub struct FileBackend {
+    path: PathBuf,
+}
+
+impl FileBackend {
+    pub fn new(path: PathBuf) -> Self {
+        FileBackend { path }
+    }
+}
+
+impl Backend for FileBackend {
+    fn load(&mut self) -> io::Result<Vec<Entry>> {
+        match fs::read_to_string(&self.path) {
+            Ok(contents) => Ok(contents.lines().filter_map(parse).collect()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(vec![]),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn append(&mut self, entry: &Entry) -> io::Result<()> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+
+        file.write_all(format(entry).as_bytes())
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        File::create(&self.path).map(|_| ())
+    }
+}
+
+fn format(entry: &Entry) -> String {
+    format!(
+        "{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+        entry.time,
+        entry.duration.as_millis(),
+        entry.status,
+        escape(&entry.host),
+        escape(&entry.session),
+        escape(&entry.cwd),
+        escape(&entry.command)
+    )
+}
+
+fn parse(line: &str) -> Option<Entry> {
+    if line.is_empty() {
+        return None;
+    }
+
+    let record = || {
+        let mut fields = line.splitn(7, '\t');
+
+        Some(Entry {
+            time: fields.next()?.parse().ok()?,
+            duration: Duration::from_millis(fields.next()?.parse().ok()?),
+            status: fields.next()?.parse().ok()?,
+            host: unescape(fields.next()?),
+            session: unescape(fields.next()?),
+            cwd: unescape(fields.next()?),
+            command: unescape(fields.next()?),
+        })
+    };
+
+    record().or_else(|| {
+        Some(Entry {
+            command: line.to_string(),
+            ..Entry::default()
+        })
+    })
+}
+
+fn escape(field: &str) -> String {
+    field
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+}
+
+fn unescape(field: &str) -> String {
+    let mut out = String::new();
+    let mut chars = field.chars();
+
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('t')) => out.push('\t'),
+            ('\\', Some('n')) => out.push('\n'),
+            ('\\', Some('\\')) => out.push('\\'),
+            (c, _) => {
+                out.push(c);
+                continue;
+            }
+        }
+        chars.next();
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let entry = Entry {
+            command: String::from("printf 'a\\tb'\techo"),
+            cwd: String::from("/tmp"),
+            host: String::from("box"),
+            session: String::from("1a-2b"),
+            time: 1700000000,
+            duration: Duration::from_millis(42),
+            status: 1,
+        };
+
+        let line = format(&entry);
+        assert_eq!(line.matches('\t').count(), 6);
+        assert_eq!(parse(line.trim_end()), Some(entry));
+
+        let plain = parse("ls -l").unwrap();
+        assert_eq!(plain.command, "ls -l");
+        assert_eq!(plain.time, 0);
+    }
+}
@@ -0,0 +1,280 @@
+// History kept in an SQLite database, one row per entry. The directory and
+// status are indexed so the entries of a directory or the ones that failed
+// can be looked up without reading them all
+
+use std::convert::TryFrom;
+use std::ffi::{CStr, CString};
+use std::io;
+use std::os::raw::{c_char, c_int, c_void};
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::ptr;
+use std::time::Duration;
+
+use super::{Backend, Entry};
+
+const SQLITE_OK: c_int = 0;
+const SQLITE_ROW: c_int = 100;
+const SQLITE_DONE: c_int = 101;
+
+// Has SQLite copy the text bound, it's freed before the statement runs
+const SQLITE_TRANSIENT: isize = -1;
+
+#[link(name = "sqlite3")]
+extern "C" {
+    fn sqlite3_open(path: *const c_char, db: *mut *mut c_void) -> c_int;
+    fn sqlite3_close(db: *mut c_void) -> c_int;
+    fn sqlite3_busy_timeout(db: *mut c_void, ms: c_int) -> c_int;
+    fn sqlite3_errmsg(db: *mut c_void) -> *const c_char;
+    fn sqlite3_exec(
+        db: *mut c_void,
+        sql: *const c_char,
+        callback: *const c_void,
+        arg: *mut c_void,
+        errmsg: *mut *mut c_char,
+    ) -> c_int;
+    fn sqlite3_prepare_v2(
+        db: *mut c_void,
+        sql: *const c_char,
+        len: c_int,
+        stmt: *mut *mut c_void,
+        tail: *mut *const c_char,
+    ) -> c_int;
+    fn sqlite3_bind_int64(stmt: *mut c_void, i: c_int, value: i64) -> c_int;
+    fn sqlite3_bind_text(
+        stmt: *mut c_void,
+        i: c_int,
+        text: *const c_char,
+        len: c_int,
+        destructor: isize,
+    ) -> c_int;
+    fn sqlite3_step(stmt: *mut c_void) -> c_int;
+    fn sqlite3_column_int64(stmt: *mut c_void, i: c_int) -> i64;
+    fn sqlite3_column_text(stmt: *mut c_void, i: c_int) -> *const u8;
+    fn sqlite3_column_bytes(stmt: *mut c_void, i: c_int) -> c_int;
+    fn sqlite3_finalize(stmt: *mut c_void) -> c_int;
+}
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS history (
+        id INTEGER PRIMARY KEY,
+        time INTEGER NOT NULL,
+        duration INTEGER NOT NULL,
+        status INTEGER NOT NULL,
+        host TEXT NOT NULL,
+        session TEXT NOT NULL,
+        cwd TEXT NOT NULL,
+        command TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS history_cwd ON history (cwd);
+    CREATE INDEX IF NOT EXISTS history_status ON history (status);
+";
+
+pub struct SqliteBackend {
+    db: *mut c_void,
+}
+
+impl SqliteBackend {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let path = CString::new(path.as_os_str().as_bytes())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let mut db = ptr::null_mut();
+        let code = unsafe { sqlite3_open(path.as_ptr(), &mut db) };
+
+        // Even a database that failed to open has to be closed
+        let backend = SqliteBackend { db };
+        if code != SQLITE_OK {
+            return Err(backend.error());
+        }
+
+        // Other shells writing at the same time are waited for
+        unsafe { sqlite3_busy_timeout(db, 1000) };
+        backend.exec(SCHEMA)?;
+        Ok(backend)
+    }
+
+    fn error(&self) -> io::Error {
+        let message = match self.db.is_null() {
+            true => String::from("out of memory"),
+            false => unsafe { CStr::from_ptr(sqlite3_errmsg(self.db)) }
+                .to_string_lossy()
+                .into_owned(),
+        };
+
+        io::Error::other(message)
+    }
+
+    fn exec(&self, sql: &str) -> io::Result<()> {
+        let sql = CString::new(sql).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let code = unsafe {
+            sqlite3_exec(
+                self.db,
+                sql.as_ptr(),
+                ptr::null(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+            )
+        };
+
+        match code {
+            SQLITE_OK => Ok(()),
+            _ => Err(self.error()),
+        }
+    }
+
+    fn prepare(&self, sql: &str) -> io::Result<Statement<'_>> {
+        let sql = CString::new(sql).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let mut stmt = ptr::null_mut();
+        let code =
+            unsafe { sqlite3_prepare_v2(self.db, sql.as_ptr(), -1, &mut stmt, ptr::null_mut()) };
+
+        match code {
+            SQLITE_OK => Ok(Statement {
+                backend: self,
+                stmt,
+            }),
+            _ => Err(self.error()),
+        }
+    }
+}
+
+impl Drop for SqliteBackend {
+    fn drop(&mut self) {
+        unsafe { sqlite3_close(self.db) };
+    }
+}
+
+struct Statement<'a> {
+    backend: &'a SqliteBackend,
+    stmt: *mut c_void,
+}
+
+impl Statement<'_> {
+    // Parameters are numbered from 1
+    fn bind_int(&mut self, i: c_int, value: i64) -> io::Result<()> {
+        match unsafe { sqlite3_bind_int64(self.stmt, i, value) } {
+            SQLITE_OK => Ok(()),
+            _ => Err(self.backend.error()),
+        }
+    }
+
+    fn bind_text(&mut self, i: c_int, text: &str) -> io::Result<()> {
+        let len = c_int::try_from(text.len())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let code =
+            unsafe { sqlite3_bind_text(self.stmt, i, text.as_ptr().cast(), len, SQLITE_TRANSIENT) };
+
+        match code {
+            SQLITE_OK => Ok(()),
+            _ => Err(self.backend.error()),
+        }
+    }
+
+    // Whether there's a row to read, false once it's done
+    fn step(&mut self) -> io::Result<bool> {
+        match unsafe { sqlite3_step(self.stmt) } {
+            SQLITE_ROW => Ok(true),
+            SQLITE_DONE => Ok(false),
+            _ => Err(self.backend.error()),
+        }
+    }
+
+    fn int(&self, i: c_int) -> i64 {
+        unsafe { sqlite3_column_int64(self.stmt, i) }
+    }
+
+    fn text(&self, i: c_int) -> String {
+        let text = unsafe { sqlite3_column_text(self.stmt, i) };
+        if text.is_null() {
+            return String::new();
+        }
+
+        let len = unsafe { sqlite3_column_bytes(self.stmt, i) } as usize;
+        let bytes = unsafe { std::slice::from_raw_parts(text, len) };
+        String::from_utf8_lossy(bytes).into_owned()
+    }
+}
+
+impl Drop for Statement<'_> {
+    fn drop(&mut self) {
+        unsafe { sqlite3_finalize(self.stmt) };
+    }
+}
+
+impl Backend for SqliteBackend {
+    fn load(&mut self) -> io::Result<Vec<Entry>> {
+        let mut stmt = self.prepare(
+            "SELECT time, duration, status, host, session, cwd, command \
+             FROM history ORDER BY id",
+        )?;
+        let mut entries = vec![];
+
+        while stmt.step()? {
+            entries.push(Entry {
+                time: stmt.int(0) as u64,
+                duration: Duration::from_millis(stmt.int(1) as u64),
+                status: stmt.int(2) as i32,
+                host: stmt.text(3),
+                session: stmt.text(4),
+                cwd: stmt.text(5),
+                command: stmt.text(6),
+            });
+        }
+
+        Ok(entries)
+    }
+
+    fn append(&mut self, entry: &Entry) -> io::Result<()> {
+        let mut stmt = self.prepare(
+            "INSERT INTO history (time, duration, status, host, session, cwd, command) \
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )?;
+
+        stmt.bind_int(1, entry.time as i64)?;
+        stmt.bind_int(2, entry.duration.as_millis() as i64)?;
+        stmt.bind_int(3, entry.status.into())?;
+        stmt.bind_text(4, &entry.host)?;
+        stmt.bind_text(5, &entry.session)?;
+        stmt.bind_text(6, &entry.cwd)?;
+        stmt.bind_text(7, &entry.command)?;
+        stmt.step().map(|_| ())
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        self.exec("DELETE FROM history")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn test_round_trip() {
+        let path = env::temp_dir().join(format!("rush-history-{}.db", std::process::id()));
+        let entry = Entry {
+            command: String::from("printf 'a\\tb'\necho '\u{2717}'"),
+            cwd: String::from("/tmp"),
+            host: String::from("box"),
+            session: String::from("1a-2b"),
+            time: 1700000000,
+            duration: Duration::from_millis(42),
+            status: 1,
+        };
+
+        let mut backend = SqliteBackend::open(&path).unwrap();
+        backend.append(&entry).unwrap();
+        backend.append(&Entry::default()).unwrap();
+        drop(backend);
+
+        let mut backend = SqliteBackend::open(&path).unwrap();
+        assert_eq!(backend.load().unwrap(), vec![entry, Entry::default()]);
+
+        backend.clear().unwrap();
+        assert_eq!(backend.load().unwrap(), vec![]);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,465 @@
+// Message catalogs for rush's own diagnostics. Messages are looked up by their
+// English text, so anything missing from a catalog falls back to English
+
+use std::env;
+use std::fmt::Display;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Language {
+    English,
+    Spanish,
+}
+
+const SPANISH: &[(&str, &str)] = &[
+    ("empty line", "línea vacía"),
+    ("no command given", "no se indicó ninguna orden"),
+    (
+        "cd: no directory given",
+        "cd: no se indicó ningún directorio",
+    ),
+    (
+        "source: no file given",
+        "source: no se indicó ningún archivo",
+    ),
+    ("unclosed quote", "comilla sin cerrar"),
+    (
+        "unclosed command substitution",
+        "sustitución de orden sin cerrar",
+    ),
+    ("syntax error near `{}`", "error de sintaxis cerca de `{}`"),
+    (
+        "too deeply nested (more than {} levels)",
+        "anidamiento demasiado profundo (más de {} niveles)",
+    ),
+    ("{}: command not found", "{}: orden no encontrada"),
+    (
+        "export: `{}': not a valid identifier",
+        "export: `{}': no es un identificador válido",
+    ),
+    (
+        "trap: {}: invalid signal specification",
+        "trap: {}: especificación de señal inválida",
+    ),
+    (
+        "trap: {}: cannot be trapped",
+        "trap: {}: no se puede capturar",
+    ),
+    (
+        "kill: {}: invalid signal specification",
+        "kill: {}: especificación de señal inválida",
+    ),
+    (
+        "kill: {}: arguments must be process or job IDs",
+        "kill: {}: los argumentos deben ser identificadores de proceso o de trabajo",
+    ),
+    (
+        "ulimit: -{}: invalid option",
+        "ulimit: -{}: opción inválida",
+    ),
+    ("ulimit: {}: invalid number", "ulimit: {}: número inválido"),
+    (
+        "history: {}: numeric argument required",
+        "history: {}: se requiere un argumento numérico",
+    ),
+    ("alias: {}: not found", "alias: {}: no encontrado"),
+    ("unalias: {}: not found", "unalias: {}: no encontrado"),
+    ("{}: -{}: invalid option", "{}: -{}: opción inválida"),
+    ("{} is aliased to `{}'", "{} es un alias de `{}'"),
+    ("{} is a shell keyword", "{} es una palabra clave del shell"),
+    ("{} is a shell builtin", "{} es una orden interna del shell"),
+    ("{} is {}", "{} es {}"),
+    ("{}: {}: not found", "{}: {}: no encontrado"),
+    ("Running", "Ejecutando"),
+    ("Done", "Hecho"),
+    ("jobs: {}: invalid option", "jobs: {}: opción inválida"),
+    ("There are stopped jobs.", "Hay trabajos detenidos."),
+    ("There are running jobs.", "Hay trabajos en ejecución."),
+    (
+        "wait: {}: not a pid or valid job spec",
+        "wait: {}: no es un pid ni una especificación de trabajo válida",
+    ),
+    (
+        "wait: pid {} is not a child of this shell",
+        "wait: el pid {} no es un hijo de este shell",
+    ),
+    ("{}: no script given", "{}: no se indicó ningún script"),
+    (
+        "{} of {} lines run ({}%)",
+        "{} de {} líneas ejecutadas ({}%)",
+    ),
+    ("unexpected end of input", "fin inesperado de la entrada"),
+    ("{} is a function", "{} es una función"),
+    (
+        "shtest: usage: shtest file...",
+        "shtest: uso: shtest archivo...",
+    ),
+    ("{} passed, {} failed", "{} correctos, {} fallidos"),
+    ("{}: unbound variable", "{}: variable no definida"),
+    ("bad expansion", "expansión incorrecta"),
+    (
+        "set: {}: invalid option name",
+        "set: {}: nombre de opción inválido",
+    ),
+    ("set: -{}: invalid option", "set: -{}: opción inválida"),
+    (
+        "history: --in-dir: directory required",
+        "history: --in-dir: se requiere un directorio",
+    ),
+    ("shopt: -{}: invalid option", "shopt: -{}: opción inválida"),
+    (
+        "shopt: {}: invalid shell option name",
+        "shopt: {}: nombre de opción de la shell inválido",
+    ),
+    (
+        "rush: {} is not trusted, source it? [y/N] ",
+        "rush: {} no es de confianza, ¿cargarlo? [y/N] ",
+    ),
+    (
+        "source: {}: not trusted, not sourced",
+        "source: {}: no es de confianza, no se ha cargado",
+    ),
+    (
+        "warning: {}: command not found",
+        "aviso: {}: orden no encontrada",
+    ),
+    ("hash: hash table empty", "hash: tabla hash vacía"),
+    ("hits", "usos"),
+    ("command", "orden"),
+    ("hash: {}: not found", "hash: {}: no se encontró"),
+    ("sleep: missing operand", "sleep: falta un operando"),
+    (
+        "sleep: invalid time interval `{}'",
+        "sleep: intervalo de tiempo no válido `{}'",
+    ),
+    (
+        "getconf: -v: variable name expected",
+        "getconf: -v: se esperaba un nombre de variable",
+    ),
+    (
+        "getconf: -v needs a name to look up",
+        "getconf: -v necesita un nombre que consultar",
+    ),
+    (
+        "getconf: {}: unknown name",
+        "getconf: {}: nombre desconocido",
+    ),
+    (
+        "local: can only be used in a function",
+        "local: solo se puede usar en una función",
+    ),
+    (
+        "local: `{}': not a valid identifier",
+        "local: `{}': no es un identificador válido",
+    ),
+    (
+        "random: choice: nothing to choose from",
+        "random: choice: no hay nada entre lo que elegir",
+    ),
+    ("random: {}: not a number", "random: {}: no es un número"),
+    (
+        "random: {} is greater than {}",
+        "random: {} es mayor que {}",
+    ),
+    (
+        "random: usage: random [low high] | random choice word...",
+        "random: uso: random [mínimo máximo] | random choice palabra...",
+    ),
+    (
+        "return: can only `return' from a function or sourced script",
+        "return: solo se puede usar `return' en una función o un script cargado",
+    ),
+    (
+        "exit: {}: numeric argument required",
+        "exit: {}: se necesita un argumento numérico",
+    ),
+    (
+        "return: {}: numeric argument required",
+        "return: {}: se necesita un argumento numérico",
+    ),
+    (
+        "strftime: usage: strftime format [seconds]",
+        "strftime: uso: strftime formato [segundos]",
+    ),
+    ("strftime: {}: invalid time", "strftime: {}: hora no válida"),
+    (
+        "shift: {}: numeric argument required",
+        "shift: {}: se necesita un argumento numérico",
+    ),
+    (
+        "shift: {}: shift count out of range",
+        "shift: {}: número de desplazamientos fuera de rango",
+    ),
+    (
+        "{}: {}: directory stack index out of range",
+        "{}: {}: índice de la pila de directorios fuera de rango",
+    ),
+    ("dirs: -{}: invalid option", "dirs: -{}: opción no válida"),
+    (
+        "dirs: {}: invalid argument",
+        "dirs: {}: argumento no válido",
+    ),
+    ("pushd: no other directory", "pushd: no hay otro directorio"),
+    (
+        "popd: directory stack empty",
+        "popd: la pila de directorios está vacía",
+    ),
+    (
+        "popd: {}: invalid argument",
+        "popd: {}: argumento no válido",
+    ),
+    (
+        "getopts: usage: getopts optstring name [arg...]",
+        "getopts: uso: getopts opciones nombre [argumento...]",
+    ),
+    (
+        "getopts: illegal option -- {}",
+        "getopts: opción ilegal -- {}",
+    ),
+    (
+        "getopts: option requires an argument -- {}",
+        "getopts: la opción necesita un argumento -- {}",
+    ),
+    ("division by 0", "división por 0"),
+    ("exponent less than 0", "exponente menor que 0"),
+    (
+        "attempted assignment to non-variable",
+        "intento de asignación a algo que no es una variable",
+    ),
+    (
+        "expression recursion level exceeded",
+        "nivel de recursión de la expresión excedido",
+    ),
+    ("end of expression", "fin de la expresión"),
+    ("let: expression expected", "let: se esperaba una expresión"),
+    (
+        "builtin: {}: not a shell builtin",
+        "builtin: {}: no es una orden interna",
+    ),
+    (
+        "&!: no directory for job logs, HOME isn't set",
+        "&!: no hay directorio para los registros de trabajos, HOME no está definido",
+    ),
+    (
+        "{} disowned, output in {}",
+        "{} desasociado, la salida está en {}",
+    ),
+    ("jobs: {}: no such log", "jobs: {}: no existe ese registro"),
+    ("bad file descriptor", "descriptor de archivo incorrecto"),
+    (
+        "save-cmd: usage: save-cmd [-d] name [command...]",
+        "save-cmd: uso: save-cmd [-d] nombre [orden...]",
+    ),
+    (
+        "save-cmd: HOME isn't set",
+        "save-cmd: HOME no está definido",
+    ),
+    (
+        "save-cmd: {}: no such command saved",
+        "save-cmd: {}: no hay ninguna orden guardada con ese nombre",
+    ),
+    (
+        "save-cmd: no previous command",
+        "save-cmd: no hay orden anterior",
+    ),
+    (
+        "run: {}: no such command saved",
+        "run: {}: no hay ninguna orden guardada con ese nombre",
+    ),
+    ("assertion failed: {}", "aserción fallida: {}"),
+    ("bind: {}: no such preset", "bind: {}: no existe ese perfil"),
+    (
+        "bind: usage: bind [-lpX] [-r keys] [-x keys command] [--preset [name]] [keys action]",
+        "bind: uso: bind [-lpX] [-r teclas] [-x teclas orden] [--preset [nombre]] [teclas acción]",
+    ),
+    ("bind: {}: unknown action", "bind: {}: acción desconocida"),
+    (
+        "bind: {}: invalid key sequence",
+        "bind: {}: secuencia de teclas inválida",
+    ),
+    (
+        "{}: {}: loop count out of range",
+        "{}: {}: número de bucles fuera de rango",
+    ),
+    (
+        "{}: {}: numeric argument required",
+        "{}: {}: se necesita un argumento numérico",
+    ),
+    (
+        "{}: only meaningful in a loop",
+        "{}: solo tiene sentido en un bucle",
+    ),
+    (
+        "-c: option requires an argument",
+        "-c: la opción necesita un argumento",
+    ),
+    (
+        "declare: {}: invalid option",
+        "declare: {}: opción inválida",
+    ),
+    (
+        "declare: `{}': not a valid identifier",
+        "declare: `{}': no es un identificador válido",
+    ),
+    (
+        "declare: {}: arrays are set one element at a time",
+        "declare: {}: los arrays se asignan elemento a elemento",
+    ),
+    ("{}: event not found", "{}: evento no encontrado"),
+    (
+        "complete: {}: no completion specification",
+        "complete: {}: no hay especificación de completado",
+    ),
+    (
+        "complete: usage: complete [-p] [-W words | -r] [name...]",
+        "complete: uso: complete [-p] [-W palabras | -r] [nombre...]",
+    ),
+    ("umask: {}: invalid mode", "umask: {}: modo inválido"),
+    (
+        "disown: -{}: invalid option",
+        "disown: -{}: opción inválida",
+    ),
+    ("fg: no job control", "fg: no hay control de trabajos"),
+    ("bg: no job control", "bg: no hay control de trabajos"),
+    (
+        "bg: job {} already in background",
+        "bg: el trabajo {} ya está en segundo plano",
+    ),
+    ("{}: {}: no such job", "{}: {}: no existe ese trabajo"),
+    (
+        "command: usage: command [-p] name [arg...] or command -v|-V name...",
+        "command: uso: command [-p] nombre [arg...] o command -v|-V nombre...",
+    ),
+    ("Exit {}", "Salida {}"),
+    ("core dumped", "core generado"),
+    ("Unknown signal", "Señal desconocida"),
+    ("Hangup", "Colgar"),
+    ("Interrupt", "Interrupción"),
+    ("Quit", "Salir"),
+    ("Illegal instruction", "Instrucción ilegal"),
+    ("Trace/breakpoint trap", "Trampa de traza/punto de parada"),
+    ("Aborted", "Abortado"),
+    ("Bus error", "Error del bus"),
+    ("Floating point exception", "Excepción de coma flotante"),
+    ("Killed", "Matado"),
+    ("User defined signal 1", "Señal definida por el usuario 1"),
+    ("Segmentation fault", "Violación de segmento"),
+    ("User defined signal 2", "Señal definida por el usuario 2"),
+    ("Broken pipe", "Tubería rota"),
+    ("Alarm clock", "Temporizador"),
+    ("Terminated", "Terminado"),
+    ("Stack fault", "Fallo de pila"),
+    ("Child exited", "El proceso hijo terminó"),
+    ("Continued", "Continuado"),
+    ("Stopped (signal)", "Detenido (señal)"),
+    ("Stopped", "Detenido"),
+    ("Stopped (tty input)", "Detenido (entrada de tty)"),
+    ("Stopped (tty output)", "Detenido (salida de tty)"),
+    ("Urgent I/O condition", "Condición urgente de E/S"),
+    (
+        "CPU time limit exceeded",
+        "Límite de tiempo de CPU excedido",
+    ),
+    (
+        "File size limit exceeded",
+        "Límite de tamaño de archivo excedido",
+    ),
+    ("Virtual timer expired", "Temporizador virtual expirado"),
+    (
+        "Profiling timer expired",
+        "Temporizador de perfilado expirado",
+    ),
+    ("Window changed", "Ventana cambiada"),
+    ("I/O possible", "E/S posible"),
+    ("Power failure", "Fallo de alimentación"),
+    ("Bad system call", "Llamada al sistema errónea"),
+];
+
+// The language of messages as selected by LC_ALL, LC_MESSAGES or LANG, in
+// that order of precedence
+pub fn language() -> Language {
+    for var in &["LC_ALL", "LC_MESSAGES", "LANG"] {
+        match env::var(var) {
+            Ok(locale) if !locale.is_empty() => {
+                return if locale.starts_with("es") {
+                    Language::Spanish
+                } else {
+                    Language::English
+                };
+            }
+            _ => {}
+        }
+    }
+
+    Language::English
+}
+
+pub fn translate(msg: &'static str) -> &'static str {
+    translate_to(language(), msg)
+}
+
+fn translate_to(language: Language, msg: &'static str) -> &'static str {
+    let catalog = match language {
+        Language::English => return msg,
+        Language::Spanish => SPANISH,
+    };
+
+    catalog
+        .iter()
+        .find(|(english, _)| *english == msg)
+        .map_or(msg, |(_, translated)| *translated)
+}
+
+// Replace the `{}` placeholders of a message, in order
+pub fn fill(template: &str, args: &[&dyn Display]) -> String {
+    let mut out = String::new();
+    let mut args = args.iter();
+    let mut parts = template.split("{}");
+
+    if let Some(part) = parts.next() {
+        out.push_str(part);
+    }
+
+    for part in parts {
+        if let Some(arg) = args.next() {
+            out.push_str(&arg.to_string());
+        }
+        out.push_str(part);
+    }
+
+    out
+}
+
+// Translate a message and fill its placeholders with the remaining arguments
+macro_rules! tr {
+    ($msg:expr) => {
+        $crate::i18n::translate($msg).to_string()
+    };
+
+    ($msg:expr, $($arg:expr),+ $(,)?) => {
+        $crate::i18n::fill(
+            $crate::i18n::translate($msg),
+            &[$(&$arg as &dyn std::fmt::Display),+],
+        )
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_translate() {
+        let msg = "alias: {}: not found";
+
+        assert_eq!(translate_to(Language::English, msg), msg);
+        assert_eq!(
+            fill(translate_to(Language::Spanish, msg), &[&"ll"]),
+            "alias: ll: no encontrado"
+        );
+        assert_eq!(
+            translate_to(Language::Spanish, "untranslated"),
+            "untranslated"
+        );
+        assert_eq!(fill("{} of {}", &[&1, &2]), "1 of 2");
+    }
+}
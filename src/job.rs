@@ -0,0 +1,458 @@
+// The job table: commands running in the background or stopped, along with
+// handing the terminal to whichever job runs in the foreground
+
+mod logs;
+
+use std::io;
+use std::os::unix::process::ExitStatusExt;
+use std::process::ExitStatus;
+
+use crate::signal;
+use crate::status::Status;
+use crate::sys;
+
+pub use self::logs::{create_log, log_dir, log_path, logs, output};
+
+const TERMINAL: i32 = 0;
+
+pub struct Process {
+    pub pid: i32,
+    // Set once the process has finished
+    pub status: Option<Status>,
+}
+
+pub struct Job {
+    // 0 until the job is added to the table
+    pub id: usize,
+    pub pgid: i32,
+    pub processes: Vec<Process>,
+    pub cmdline: String,
+    pub stopped: bool,
+    // Whether the latest change of state has been reported
+    pub notified: bool,
+    // Left alone when the shell exits, see `disown -h`
+    pub no_hangup: bool,
+    // Whether the status is the last failing one, see `set -o pipefail`
+    pub pipefail: bool,
+}
+
+pub enum State {
+    Running,
+    Stopped,
+    Done(Status),
+}
+
+impl Job {
+    pub fn new(pgid: i32, pids: Vec<i32>, cmdline: &str) -> Self {
+        Job {
+            id: 0,
+            pgid,
+            processes: pids
+                .into_iter()
+                .map(|pid| Process { pid, status: None })
+                .collect(),
+            cmdline: cmdline.to_string(),
+            stopped: false,
+            notified: false,
+            no_hangup: false,
+            pipefail: false,
+        }
+    }
+
+    // A job is done once all its processes are, with the status of the last
+    // or with `pipefail` of the last that failed
+    pub fn state(&self) -> State {
+        let statuses: Option<Vec<Status>> = self.processes.iter().map(|p| p.status).collect();
+
+        match statuses {
+            Some(statuses) => {
+                let last = statuses.last().copied().unwrap_or(Status::Exited(0));
+                let failed = statuses.iter().rev().find(|status| !status.success());
+
+                match failed {
+                    Some(&failed) if self.pipefail => State::Done(failed),
+                    _ => State::Done(last),
+                }
+            }
+            None if self.stopped => State::Stopped,
+            None => State::Running,
+        }
+    }
+}
+
+enum Event {
+    Exited(Status),
+    Stopped(i32),
+    Continued,
+}
+
+impl Event {
+    fn from_raw(raw: i32) -> Self {
+        let status = ExitStatus::from_raw(raw);
+
+        if let Some(signum) = status.stopped_signal() {
+            Event::Stopped(signum)
+        } else if status.continued() {
+            Event::Continued
+        } else {
+            Event::Exited(Status::from(status))
+        }
+    }
+}
+
+// `waitpid` retried when interrupted, `None` when there's nothing to report
+fn wait(pid: i32, options: i32) -> io::Result<Option<(i32, Event)>> {
+    loop {
+        let mut raw = 0;
+
+        match unsafe { sys::waitpid(pid, &mut raw, options) } {
+            0 => return Ok(None),
+            -1 => {
+                let e = io::Error::last_os_error();
+                if e.kind() != io::ErrorKind::Interrupted {
+                    return Err(e);
+                }
+            }
+            pid => return Ok(Some((pid, Event::from_raw(raw)))),
+        }
+    }
+}
+
+// Put the shell in its own process group in control of the terminal, and make
+// it immune to the signals that stop jobs
+pub fn init_job_control() {
+    for &signum in signal::JOB_CONTROL {
+        signal::ignore(signum);
+    }
+
+    unsafe {
+        sys::setpgid(0, 0);
+        sys::tcsetpgrp(TERMINAL, sys::getpgrp());
+    }
+}
+
+// Run in a child about to become part of a job, joining process group `pgid`
+// or starting one of its own with 0. With `foreground` it also takes the
+// terminal so it doesn't have to wait for the shell to hand it over
+pub fn enter_job(pgid: i32, foreground: bool) {
+    unsafe {
+        sys::setpgid(0, pgid);
+
+        if foreground {
+            sys::tcsetpgrp(TERMINAL, sys::getpgrp());
+        }
+    }
+
+    for &signum in signal::JOB_CONTROL {
+        signal::reset(signum);
+    }
+}
+
+#[derive(Default)]
+pub struct Jobs {
+    jobs: Vec<Job>,
+    // Job ids by when they were last started or stopped, the current job
+    // `%+` is the last one and the previous job `%-` the one before
+    recent: Vec<usize>,
+}
+
+impl Jobs {
+    // Add the job under the lowest id above those in use, unless it had one
+    pub fn insert(&mut self, mut job: Job) -> usize {
+        if job.id == 0 {
+            job.id = self.jobs.iter().map(|job| job.id).max().unwrap_or(0) + 1;
+        }
+
+        let id = job.id;
+        self.jobs.push(job);
+        self.jobs.sort_by_key(|job| job.id);
+        self.recent.retain(|&recent| recent != id);
+        self.recent.push(id);
+        id
+    }
+
+    pub fn remove(&mut self, id: usize) -> Option<Job> {
+        let i = self.jobs.iter().position(|job| job.id == id)?;
+        self.recent.retain(|&recent| recent != id);
+        Some(self.jobs.remove(i))
+    }
+
+    pub fn current(&self) -> Option<usize> {
+        self.recent.last().copied()
+    }
+
+    // Look up `%n`, `%+` or `%%` for the current job, `%-` for the previous
+    // one, `%name` for the job whose command starts with name and `%?text`
+    // for the one whose command contains text
+    pub fn find(&self, spec: &str) -> Option<usize> {
+        let spec = spec.strip_prefix('%').unwrap_or(spec);
+
+        let matching: Vec<usize> = match spec {
+            "" | "+" | "%" => return self.current(),
+            "-" => return self.recent.iter().rev().nth(1).copied(),
+            _ => match spec.parse::<usize>() {
+                Ok(id) => return self.get(id).map(|job| job.id),
+                Err(_) => self
+                    .jobs
+                    .iter()
+                    .filter(|job| match spec.strip_prefix('?') {
+                        Some(text) => job.cmdline.contains(text),
+                        None => job.cmdline.starts_with(spec),
+                    })
+                    .map(|job| job.id)
+                    .collect(),
+            },
+        };
+
+        // Ambiguous names don't match any job
+        match matching[..] {
+            [id] => Some(id),
+            _ => None,
+        }
+    }
+
+    pub fn get(&self, id: usize) -> Option<&Job> {
+        self.jobs.iter().find(|job| job.id == id)
+    }
+
+    pub fn get_mut(&mut self, id: usize) -> Option<&mut Job> {
+        self.jobs.iter_mut().find(|job| job.id == id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Job> {
+        self.jobs.iter()
+    }
+
+    pub fn marker(&self, id: usize) -> char {
+        let mut recent = self.recent.iter().rev();
+
+        if recent.next() == Some(&id) {
+            '+'
+        } else if recent.next() == Some(&id) {
+            '-'
+        } else {
+            ' '
+        }
+    }
+
+    // The job as listed by `jobs`
+    pub fn format(&self, job: &Job) -> String {
+        let (state, background) = match job.state() {
+            State::Running => (tr!("Running").to_string(), " &"),
+            State::Stopped => (tr!("Stopped").to_string(), ""),
+            State::Done(Status::Exited(0)) => (tr!("Done").to_string(), ""),
+            State::Done(status) => (status.to_string(), ""),
+        };
+
+        format!(
+            "[{}]{}  {:<24}{}{}",
+            job.id,
+            self.marker(job.id),
+            state,
+            job.cmdline,
+            background
+        )
+    }
+
+    // Collect every change of state of the jobs without blocking
+    pub fn reap(&mut self) {
+        let options = sys::WNOHANG | sys::WUNTRACED | sys::WCONTINUED;
+
+        while let Ok(Some((pid, event))) = wait(-1, options) {
+            let job = self
+                .jobs
+                .iter_mut()
+                .find(|job| job.processes.iter().any(|p| p.pid == pid));
+
+            if let Some(job) = job {
+                job.notified = false;
+
+                match event {
+                    Event::Exited(status) => {
+                        for process in job.processes.iter_mut().filter(|p| p.pid == pid) {
+                            process.status = Some(status);
+                        }
+                    }
+                    Event::Stopped(_) => job.stopped = true,
+                    Event::Continued => job.stopped = false,
+                }
+            }
+        }
+    }
+
+    // Report jobs that changed state since last time, forgetting those done
+    pub fn notify(&mut self) {
+        self.reap();
+
+        for job in self.jobs.iter().filter(|job| !job.notified) {
+            if !matches!(job.state(), State::Running) {
+                eprintln!("{}", self.format(job));
+            }
+        }
+
+        self.forget_done();
+    }
+
+    pub fn forget_done(&mut self) {
+        for job in &mut self.jobs {
+            job.notified = true;
+        }
+
+        let done: Vec<usize> = self
+            .jobs
+            .iter()
+            .filter(|job| matches!(job.state(), State::Done(_)))
+            .map(|job| job.id)
+            .collect();
+
+        for id in done {
+            self.remove(id);
+        }
+    }
+
+    // Continue a job in the foreground, back to the table if it stops again
+    pub fn foreground(&mut self, mut job: Job) -> io::Result<Status> {
+        unsafe { sys::tcsetpgrp(TERMINAL, job.pgid) };
+
+        if job.stopped {
+            unsafe { sys::kill(-job.pgid, signal::SIGCONT) };
+            job.stopped = false;
+        }
+
+        self.wait_foreground(job, true)
+    }
+
+    // Continue a stopped job in the background, false if it wasn't stopped
+    pub fn background(&mut self, id: usize) -> bool {
+        let job = match self.get_mut(id) {
+            Some(job) if job.stopped => job,
+            _ => return false,
+        };
+
+        unsafe { sys::kill(-job.pgid, signal::SIGCONT) };
+        job.stopped = false;
+        self.recent.retain(|&recent| recent != id);
+        self.recent.push(id);
+        true
+    }
+
+    pub fn hang_up(&mut self) {
+        self.reap();
+
+        for job in self.jobs.iter().filter(|job| !job.no_hangup) {
+            unsafe {
+                sys::kill(-job.pgid, signal::SIGHUP);
+
+                if job.stopped {
+                    sys::kill(-job.pgid, signal::SIGCONT);
+                }
+            }
+        }
+    }
+
+    // Wait for a job running in the foreground, with job control it gets the
+    // terminal meanwhile and goes to the table if it's stopped
+    pub fn wait_foreground(&mut self, job: Job, job_control: bool) -> io::Result<Status> {
+        if job_control {
+            unsafe { sys::tcsetpgrp(TERMINAL, job.pgid) };
+        }
+
+        let status = self.wait_job(job, job_control);
+
+        if job_control {
+            unsafe { sys::tcsetpgrp(TERMINAL, sys::getpgrp()) };
+        }
+
+        status
+    }
+
+    // Block until the job is done, or also until it's stopped with `untraced`
+    // which puts it back in the table
+    pub fn wait_job(&mut self, mut job: Job, untraced: bool) -> io::Result<Status> {
+        let options = if untraced { sys::WUNTRACED } else { 0 };
+        let mut stopped_by = None;
+
+        for process in job.processes.iter_mut().filter(|p| p.status.is_none()) {
+            match wait(process.pid, options)? {
+                Some((_, Event::Exited(status))) => process.status = Some(status),
+                Some((_, Event::Stopped(signum))) => {
+                    stopped_by = Some(signum);
+                    break;
+                }
+                _ => {}
+            }
+        }
+
+        match stopped_by {
+            Some(signum) => {
+                job.stopped = true;
+                job.notified = true;
+                let id = self.insert(job);
+
+                if let Some(job) = self.get(id) {
+                    eprintln!("\n{}", self.format(job));
+                }
+
+                Ok(Status::Exited(128 + signum))
+            }
+
+            None => match job.state() {
+                State::Done(status) => Ok(status),
+                _ => Ok(Status::Exited(0)),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_pipeline_status() {
+        let mut job = Job::new(100, vec![100, 101, 102], "a | b | c");
+        job.processes[0].status = Some(Status::Exited(2));
+        job.processes[1].status = Some(Status::Exited(3));
+        job.processes[2].status = Some(Status::Exited(0));
+
+        assert!(matches!(job.state(), State::Done(Status::Exited(0))));
+        job.pipefail = true;
+        assert!(matches!(job.state(), State::Done(Status::Exited(3))));
+    }
+
+    #[test]
+    fn test_job_table() {
+        let mut jobs = Jobs::default();
+
+        let first = jobs.insert(Job::new(100, vec![100], "sleep 10"));
+        let second = jobs.insert(Job::new(200, vec![200], "vim"));
+        assert_eq!((first, second), (1, 2));
+
+        jobs.jobs[1].stopped = true;
+        assert_eq!(
+            jobs.format(&jobs.jobs[0]),
+            "[1]-  Running                 sleep 10 &"
+        );
+        assert_eq!(
+            jobs.format(&jobs.jobs[1]),
+            "[2]+  Stopped                 vim"
+        );
+
+        jobs.jobs[0].processes[0].status = Some(Status::Exited(1));
+        assert_eq!(
+            jobs.format(&jobs.jobs[0]),
+            "[1]-  Exit 1                  sleep 10"
+        );
+
+        assert_eq!(jobs.find("%+"), Some(2));
+        assert_eq!(jobs.find("%-"), Some(1));
+        assert_eq!(jobs.find("%1"), Some(1));
+        assert_eq!(jobs.find("%vi"), Some(2));
+        assert_eq!(jobs.find("%?10"), Some(1));
+        assert_eq!(jobs.find("%3"), None);
+
+        jobs.forget_done();
+        assert_eq!(jobs.iter().count(), 1);
+        assert_eq!(jobs.insert(Job::new(300, vec![300], "make")), 3);
+    }
+}
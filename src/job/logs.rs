@@ -0,0 +1,98 @@
+// Jobs started with `&!` are disowned right away and write to a log file of
+// their own, named after when they started and their pid, that starts with
+// the command line
+
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+pub struct Log {
+    pub pid: i32,
+    // In seconds since the epoch
+    pub started: u64,
+    pub cmdline: String,
+    pub path: PathBuf,
+}
+
+// `$XDG_STATE_HOME/rush/jobs`, `~/.local/state/rush/jobs` by default
+pub fn log_dir() -> Option<PathBuf> {
+    let state = match env::var_os("XDG_STATE_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env::var_os("HOME")?).join(".local/state"),
+    };
+
+    Some(state.join("rush/jobs"))
+}
+
+pub fn log_path(dir: &Path, started: u64, pid: i32) -> PathBuf {
+    dir.join(format!("{}-{}.log", started, pid))
+}
+
+pub fn create_log(path: &Path, cmdline: &str) -> io::Result<File> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let mut file = File::create(path)?;
+    writeln!(file, "{}", cmdline)?;
+    Ok(file)
+}
+
+// Every log in the directory, oldest first
+pub fn logs(dir: &Path) -> Vec<Log> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+
+    let mut logs: Vec<Log> = entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let name = path.file_name()?.to_str()?.strip_suffix(".log")?;
+            let (started, pid) = name.split_once('-')?;
+            let cmdline = fs::read_to_string(&path).ok()?.lines().next()?.to_string();
+
+            Some(Log {
+                pid: pid.parse().ok()?,
+                started: started.parse().ok()?,
+                cmdline,
+                path,
+            })
+        })
+        .collect();
+
+    logs.sort_by_key(|log| (log.started, log.pid));
+    logs
+}
+
+// What the job wrote, without the command line in front of it
+pub fn output(log: &Log) -> io::Result<String> {
+    let contents = fs::read_to_string(&log.path)?;
+    Ok(contents
+        .split_once('\n')
+        .map_or(String::new(), |(_, rest)| rest.to_string()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_logs() {
+        let dir = env::temp_dir().join(format!("rush-logs-{}", std::process::id()));
+
+        let mut file = create_log(&log_path(&dir, 20, 7), "make all").unwrap();
+        writeln!(file, "done").unwrap();
+        create_log(&log_path(&dir, 10, 9), "sleep 5").unwrap();
+        fs::write(dir.join("notes.txt"), "").unwrap();
+
+        let logs = logs(&dir);
+        assert_eq!(logs.len(), 2);
+        assert_eq!((logs[0].pid, logs[0].started), (9, 10));
+        assert_eq!(logs[1].cmdline, "make all");
+        assert_eq!(output(&logs[1]).unwrap(), "done\n");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,28 +1,302 @@
+#[macro_use]
+mod i18n;
+
+mod arith;
+mod bookmarks;
+mod builtins;
 mod cmd;
+mod complete;
+mod cond;
+mod coverage;
+mod defer;
+mod dirs;
+mod editor;
+mod glob;
+mod history;
+mod job;
+mod mode;
+mod options;
+mod path;
+mod profile;
+mod prompt;
+mod random;
+mod sha256;
+mod shell;
+mod signal;
+mod status;
+mod sys;
+mod trap;
+mod trust;
 
-use std::convert::TryFrom;
+use std::env;
 use std::io::{self, Write};
+use std::path::Path;
+use std::process;
+use std::time::Instant;
 
-use self::cmd::{Error, Expression};
+use self::coverage::Coverage;
+use self::history::History;
+use self::profile::Profiler;
+use self::shell::Shell;
+use self::status::Status;
 
 fn main() -> io::Result<()> {
     let stdin = io::stdin();
     let mut stdout = io::stdout();
+    let mut shell = Shell::new();
+    let mut args = env::args().peekable();
+
+    // A login shell is started as `-rush` or with `-l`, it reads the profiles
+    // before anything else
+    let mut login = args.next().is_some_and(|arg0| arg0.starts_with('-'));
+    if let Some("-l" | "--login") = args.peek().map(String::as_str) {
+        args.next();
+        login = true;
+    }
+
+    if login {
+        source_if_exists(Path::new("/etc/profile"), &mut shell);
+
+        if let Some(home) = env::var_os("HOME") {
+            source_if_exists(&Path::new(&home).join(".rush_profile"), &mut shell);
+        }
+    }
+
+    match args.next().as_deref() {
+        Some(option @ ("--profile" | "--coverage")) => {
+            let script = match args.next() {
+                Some(script) => script,
+                None => {
+                    eprintln!("rush: {}", tr!("{}: no script given", option));
+                    process::exit(2);
+                }
+            };
+
+            if option == "--profile" {
+                shell.profiler = Some(Profiler::default());
+            } else {
+                shell.coverage = Some(Coverage::default());
+            }
+
+            run_script(&script, args.collect(), &mut shell);
+        }
 
-    loop {
-        stdout.write(b"> ")?;
-        stdout.flush()?;
+        // `rush -c command [name [arg...]]` runs the command with `name` as `$0`
+        // and the arguments after it as its positional parameters
+        Some("-c") => {
+            let command = match args.next() {
+                Some(command) => command,
+                None => {
+                    eprintln!("rush: {}", tr!("-c: option requires an argument"));
+                    process::exit(2);
+                }
+            };
 
-        let mut input = String::new();
-        stdin.read_line(&mut input)?;
-        match Expression::try_from(input.as_ref()) {
-            Ok(expr) => {
-                expr.run().unwrap();
+            if let Some(name) = args.next() {
+                shell.args[0] = name;
             }
+            shell.args.extend(args);
 
-            Err(Error::EmptyLine) => {}
+            cmd::run_line(&command, &mut shell);
+            cmd::exit(shell.status.code(), &mut shell);
+        }
 
-            _ => {}
+        // `rush script [arg...]` runs the script with the arguments as its
+        // positional parameters rather than reading commands
+        Some(script) if !script.starts_with('-') => {
+            run_script(script, args.collect(), &mut shell);
         }
+
+        _ => {}
+    }
+
+    let interactive = sys::is_terminal(0);
+    // A dumb terminal gets lines read as they come, without raw mode or
+    // anything drawn over them
+    let line_editor = interactive && !editor::is_dumb();
+
+    if interactive {
+        job::init_job_control();
+        shell.job_control = true;
+        // Ctrl-C interrupts what runs rather than the shell
+        signal::keep(signal::SIGINT);
+
+        if let Some(path) = history::default_path() {
+            match history::open_backend(&path).and_then(History::open) {
+                Ok(history) => shell.history = history,
+                Err(e) => eprintln!("rush: {}: {}", path.display(), e),
+            }
+        }
+
+        // Aliases, functions and settings of the user's own
+        if let Some(home) = env::var_os("HOME") {
+            source_if_exists(&Path::new(&home).join(".rushrc"), &mut shell);
+        }
+    }
+
+    'read: loop {
+        if shell.job_control {
+            shell.jobs.notify();
+        }
+
+        defer::merge_finished(&mut shell);
+
+        let prompt = prompt::render(&shell);
+        let warned = shell.exit_warned;
+
+        let mut input = if line_editor {
+            match editor::read_line(&prompt, &mut shell) {
+                Ok(Some(line)) => line,
+                Ok(None) if cmd::confirm_exit(&mut shell) => {
+                    cmd::exit(shell.status.code(), &mut shell)
+                }
+                Ok(None) => continue,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {
+                    interrupted(&mut shell);
+                    continue;
+                }
+                Err(e) => return Err(e),
+            }
+        } else {
+            // Commands piped in are read without prompts until they end
+            if interactive {
+                stdout.write_all(prompt.as_bytes())?;
+                stdout.flush()?;
+            }
+
+            let mut input = String::new();
+            if stdin.read_line(&mut input)? == 0 {
+                if !interactive || cmd::confirm_exit(&mut shell) {
+                    cmd::exit(shell.status.code(), &mut shell);
+                }
+                continue;
+            }
+            input
+        };
+
+        // A line leaving a quote, a group or an `if` open goes on in the next
+        // ones, read with `PS2` as the prompt
+        while cmd::is_incomplete(&input, &shell) {
+            let prompt = shell.lookup("PS2").unwrap_or_else(|| String::from("> "));
+
+            let line = if line_editor {
+                match editor::read_line(&prompt, &mut shell) {
+                    Ok(Some(line)) => line,
+                    Ok(None) => break,
+                    // The lines before it are dropped too
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => {
+                        interrupted(&mut shell);
+                        continue 'read;
+                    }
+                    Err(e) => return Err(e),
+                }
+            } else {
+                if interactive {
+                    stdout.write_all(prompt.as_bytes())?;
+                    stdout.flush()?;
+                }
+
+                let mut line = String::new();
+                if stdin.read_line(&mut line)? == 0 {
+                    break;
+                }
+                line
+            };
+
+            if !input.ends_with('\n') {
+                input.push('\n');
+            }
+            input.push_str(&line);
+        }
+
+        // `!!` and the like are replaced with the commands they stand for,
+        // showing what runs
+        if interactive {
+            match shell.history.expand(&input) {
+                Ok(Some(expanded)) => {
+                    println!("{}", expanded.trim_end());
+                    input = expanded;
+                }
+                Ok(None) => {}
+                Err(event) => {
+                    eprintln!("rush: {}", tr!("{}: event not found", event));
+                    continue;
+                }
+            }
+        }
+
+        shell.history.push(&input);
+
+        // With `shopt -s showexpansion` the line is shown dimmed with its
+        // aliases expanded before it runs
+        if interactive && shell.shopts.showexpansion {
+            let expanded = shell.expand_aliases(&input);
+
+            if expanded != input && line_editor {
+                eprintln!("\x1b[2m{}\x1b[0m", expanded.trim_end());
+            } else if expanded != input {
+                eprintln!("{}", expanded.trim_end());
+            }
+        }
+
+        let start = Instant::now();
+        cmd::run_line(&input, &mut shell);
+
+        if shell.interrupted {
+            // The line the terminal echoed `^C` on is left alone
+            eprintln!();
+            interrupted(&mut shell);
+        }
+
+        if let Err(e) = shell.history.finish(start.elapsed(), shell.status.code()) {
+            eprintln!("rush: history: {}", e);
+        }
+
+        if warned {
+            shell.exit_warned = false;
+        }
+    }
+}
+
+// Ctrl-C at the prompt or while builtins run fails the line, for a fresh
+// prompt
+fn interrupted(shell: &mut Shell) {
+    shell.status = Status::Exited(128 + signal::SIGINT);
+    cmd::run_pending_traps(shell);
+    shell.interrupted = false;
+}
+
+// Run the script as `$0` with `params` as `$1..$n` and exit with the status
+// of its last command
+fn run_script(script: &str, params: Vec<String>, shell: &mut Shell) -> ! {
+    shell.args[0] = script.to_string();
+
+    let status = match cmd::run_source(Path::new(script), params, shell) {
+        Ok(status) => status.code(),
+        Err(e) => {
+            eprintln!("rush: {}: {}", script, e);
+            127
+        }
+    };
+
+    if let Some(profiler) = shell.profiler.take() {
+        eprint!("{}", profiler.report());
+    }
+
+    if let Some(coverage) = shell.coverage.take() {
+        eprint!("{}", coverage.report());
+    }
+
+    cmd::exit(status, shell)
+}
+
+// Startup files are optional, only the ones that exist are sourced
+fn source_if_exists(path: &Path, shell: &mut Shell) {
+    if !path.is_file() {
+        return;
+    }
+
+    if let Err(e) = cmd::run_source(path, vec![], shell) {
+        eprintln!("rush: {}: {}", path.display(), e);
     }
 }
@@ -0,0 +1,102 @@
+// File creation masks in the octal and symbolic forms `umask` takes
+
+const WHO: [(char, u32); 3] = [('u', 0o700), ('g', 0o070), ('o', 0o007)];
+const PERMS: [(char, u32); 3] = [('r', 0o444), ('w', 0o222), ('x', 0o111)];
+
+// `u=rwx,g=rx,o=rx` for a mask of 022, listing what the mask allows
+pub fn symbolic(mask: u32) -> String {
+    let allowed = !mask & 0o777;
+
+    WHO.iter()
+        .map(|&(who, bits)| {
+            let perms: String = PERMS
+                .iter()
+                .filter(|&&(_, perm)| allowed & bits & perm != 0)
+                .map(|&(c, _)| c)
+                .collect();
+            format!("{}={}", who, perms)
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+// The mask `spec` sets starting from `mask`, either an octal number or
+// clauses like `u=rwx,g+w,o-rwx` changing what the mask allows
+pub fn parse(spec: &str, mask: u32) -> Option<u32> {
+    if spec.starts_with(|c: char| c.is_ascii_digit()) {
+        return u32::from_str_radix(spec, 8)
+            .ok()
+            .filter(|&mask| mask <= 0o777);
+    }
+
+    let mut allowed = !mask & 0o777;
+
+    for clause in spec.split(',') {
+        let op = clause.find(['=', '+', '-'])?;
+        let (who, rest) = clause.split_at(op);
+
+        let mut who_bits = 0;
+        for c in who.chars() {
+            who_bits |= match c {
+                'a' => 0o777,
+                c => WHO.iter().find(|&&(w, _)| w == c)?.1,
+            };
+        }
+        if who.is_empty() {
+            who_bits = 0o777;
+        }
+
+        let mut ops = rest.chars().peekable();
+
+        while let Some(op) = ops.next() {
+            let mut perm_bits = 0;
+
+            while let Some(&c) = ops.peek() {
+                if "=+-".contains(c) {
+                    break;
+                }
+                perm_bits |= PERMS.iter().find(|&&(p, _)| p == c)?.1;
+                ops.next();
+            }
+
+            let bits = who_bits & perm_bits;
+
+            match op {
+                '=' => allowed = (allowed & !who_bits) | bits,
+                '+' => allowed |= bits,
+                _ => allowed &= !bits,
+            }
+        }
+    }
+
+    Some(!allowed & 0o777)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_symbolic() {
+        assert_eq!(symbolic(0o022), "u=rwx,g=rx,o=rx");
+        assert_eq!(symbolic(0o077), "u=rwx,g=,o=");
+        assert_eq!(symbolic(0o777), "u=,g=,o=");
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("027", 0o022), Some(0o027));
+        assert_eq!(parse("u=rwx,g=rx,o=", 0o022), Some(0o027));
+        assert_eq!(parse("g+w", 0o022), Some(0o002));
+        assert_eq!(parse("o-rx", 0o022), Some(0o027));
+        assert_eq!(parse("a=r,u+w", 0o000), Some(0o133));
+        assert_eq!(parse("=", 0o022), Some(0o777));
+        assert_eq!(
+            parse("u=rwx,g=rx,o=", 0o022),
+            parse("u=rwx,g=rx,o-rwx", 0o022)
+        );
+        assert_eq!(parse("999", 0o022), None);
+        assert_eq!(parse("z=r", 0o022), None);
+        assert_eq!(parse("u=q", 0o022), None);
+    }
+}
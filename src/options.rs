@@ -0,0 +1,144 @@
+// Shell options, turned on with `set -o name` or the letter some have as in
+// `set -e` and off again with `+o name` or `+e`
+
+#[derive(Default)]
+pub struct Options {
+    // Exit as soon as a command fails outside of conditions
+    pub errexit: bool,
+    // Expanding a variable that isn't set is an error
+    pub nounset: bool,
+    // A pipeline fails if any of its commands does, not just the last one
+    pub pipefail: bool,
+    // Print commands to stderr before running them
+    pub xtrace: bool,
+}
+
+// Every option by name, along with its letter
+pub const NAMES: &[(&str, Option<char>)] = &[
+    ("errexit", Some('e')),
+    ("nounset", Some('u')),
+    ("pipefail", None),
+    ("xtrace", Some('x')),
+];
+
+impl Options {
+    pub fn get(&self, name: &str) -> Option<bool> {
+        match name {
+            "errexit" => Some(self.errexit),
+            "nounset" => Some(self.nounset),
+            "pipefail" => Some(self.pipefail),
+            "xtrace" => Some(self.xtrace),
+            _ => None,
+        }
+    }
+
+    // Returns whether there's an option with that name
+    pub fn set(&mut self, name: &str, on: bool) -> bool {
+        let option = match name {
+            "errexit" => &mut self.errexit,
+            "nounset" => &mut self.nounset,
+            "pipefail" => &mut self.pipefail,
+            "xtrace" => &mut self.xtrace,
+            _ => return false,
+        };
+
+        *option = on;
+        true
+    }
+
+    // The letters of the options that are on, as `$-` shows them
+    pub fn letters(&self) -> String {
+        NAMES
+            .iter()
+            .filter(|(name, _)| self.get(name) == Some(true))
+            .filter_map(|(_, letter)| *letter)
+            .collect()
+    }
+}
+
+// Extended options, turned on with `shopt -s name` and off with `shopt -u`
+#[derive(Default)]
+pub struct Shopts {
+    // A command that's the name of a directory changes to it
+    pub autocd: bool,
+    // The contents of a directory are listed after changing to it
+    pub autols: bool,
+    // Typing a quote or an opening bracket in the editor inserts its pair
+    pub autopair: bool,
+    // Ctrl-R only finds the commands typed in this shell, not in others
+    // sharing the history file
+    pub histsession: bool,
+    // Lines typed in are shown with their aliases expanded before they run
+    pub showexpansion: bool,
+}
+
+pub const SHOPT_NAMES: &[&str] = &[
+    "autocd",
+    "autols",
+    "autopair",
+    "histsession",
+    "showexpansion",
+];
+
+impl Shopts {
+    pub fn get(&self, name: &str) -> Option<bool> {
+        match name {
+            "autocd" => Some(self.autocd),
+            "autols" => Some(self.autols),
+            "autopair" => Some(self.autopair),
+            "histsession" => Some(self.histsession),
+            "showexpansion" => Some(self.showexpansion),
+            _ => None,
+        }
+    }
+
+    pub fn set(&mut self, name: &str, on: bool) -> bool {
+        let option = match name {
+            "autocd" => &mut self.autocd,
+            "autols" => &mut self.autols,
+            "autopair" => &mut self.autopair,
+            "histsession" => &mut self.histsession,
+            "showexpansion" => &mut self.showexpansion,
+            _ => return false,
+        };
+
+        *option = on;
+        true
+    }
+}
+
+pub fn by_letter(letter: char) -> Option<&'static str> {
+    NAMES
+        .iter()
+        .find(|(_, l)| *l == Some(letter))
+        .map(|(name, _)| *name)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_options() {
+        let mut options = Options::default();
+
+        assert!(options.set(by_letter('x').unwrap(), true));
+        assert!(options.set("pipefail", true));
+        assert!(!options.set("noclobber", true));
+        assert_eq!(options.get("xtrace"), Some(true));
+        assert_eq!(options.letters(), "x");
+        assert_eq!(by_letter('q'), None);
+    }
+
+    #[test]
+    fn test_shopts() {
+        let mut shopts = Shopts::default();
+
+        for name in SHOPT_NAMES {
+            assert_eq!(shopts.get(name), Some(false));
+            assert!(shopts.set(name, true));
+            assert_eq!(shopts.get(name), Some(true));
+        }
+        assert!(!shopts.set("globstar", true));
+    }
+}
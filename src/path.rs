@@ -0,0 +1,161 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+pub fn is_executable(path: &Path) -> bool {
+    fs::metadata(path).is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+}
+
+// Every executable named `name` on PATH in lookup order, names containing a
+// slash are not looked up
+pub fn find_all_executables(name: &str) -> Vec<PathBuf> {
+    if name.contains('/') {
+        let path = PathBuf::from(name);
+        return if is_executable(&path) {
+            vec![path]
+        } else {
+            vec![]
+        };
+    }
+
+    match env::var_os("PATH") {
+        Some(paths) => executables_in(name, &paths),
+        None => vec![],
+    }
+}
+
+// Where the standard utilities are found whatever PATH is, for `command -p`
+pub const DEFAULT_PATH: &str = "/usr/local/bin:/usr/bin:/bin:/usr/local/sbin:/usr/sbin:/sbin";
+
+// Every executable named `name` in the directories of `paths`
+pub fn executables_in(name: &str, paths: &OsStr) -> Vec<PathBuf> {
+    env::split_paths(paths)
+        .map(|dir| dir.join(name))
+        .filter(|path| is_executable(path))
+        .collect()
+}
+
+// The names of the executables on PATH starting with `prefix`
+pub fn executables_with_prefix(prefix: &str) -> Vec<String> {
+    let paths = match env::var_os("PATH") {
+        Some(paths) => paths,
+        None => return vec![],
+    };
+
+    let mut names = vec![];
+
+    for dir in env::split_paths(&paths) {
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+
+            if name.starts_with(prefix) && is_executable(&entry.path()) {
+                names.push(name);
+            }
+        }
+    }
+
+    names
+}
+
+// Where the commands run were found on PATH, forgotten when PATH changes
+#[derive(Default)]
+pub struct Hash {
+    entries: BTreeMap<String, Hashed>,
+    // The PATH the entries were looked up in
+    path: Option<OsString>,
+}
+
+pub struct Hashed {
+    pub path: PathBuf,
+    // How many times the command ran since it was remembered
+    pub hits: usize,
+}
+
+impl Hash {
+    // The remembered location of `name`, looking it up when it's not known
+    // or no longer there
+    pub fn find(&mut self, name: &str) -> Option<PathBuf> {
+        self.check_path();
+
+        match self.entries.get_mut(name) {
+            Some(hashed) if is_executable(&hashed.path) => {
+                hashed.hits += 1;
+                Some(hashed.path.clone())
+            }
+            _ => {
+                let path = self.lookup(name)?;
+                self.entries.get_mut(name).unwrap().hits = 1;
+                Some(path)
+            }
+        }
+    }
+
+    // Look `name` up again and remember where it is
+    pub fn lookup(&mut self, name: &str) -> Option<PathBuf> {
+        self.check_path();
+
+        match find_all_executables(name).into_iter().next() {
+            Some(path) => {
+                let hashed = Hashed {
+                    path: path.clone(),
+                    hits: 0,
+                };
+                self.entries.insert(name.to_string(), hashed);
+                Some(path)
+            }
+            None => {
+                self.entries.remove(name);
+                None
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn entries(&mut self) -> impl Iterator<Item = (&String, &Hashed)> {
+        self.check_path();
+        self.entries.iter()
+    }
+
+    fn check_path(&mut self) {
+        let path = env::var_os("PATH");
+
+        if path != self.path {
+            self.entries.clear();
+            self.path = path;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_hash() {
+        let mut hash = Hash::default();
+
+        let sh = hash.find("sh").unwrap();
+        assert_eq!(hash.find("sh").unwrap(), sh);
+        assert_eq!(hash.entries().next().unwrap().1.hits, 2);
+
+        hash.lookup("sh").unwrap();
+        assert_eq!(hash.entries().next().unwrap().1.hits, 0);
+
+        assert!(hash.lookup("no-such-command-anywhere").is_none());
+        assert_eq!(hash.entries().count(), 1);
+
+        hash.clear();
+        assert_eq!(hash.entries().count(), 0);
+    }
+}
@@ -0,0 +1,173 @@
+// `rush --profile script`: time spent on every line of a script is measured
+// from the DEBUG hook, each command's time lasting until the next one starts.
+// Functions get the total of their calls, with what they call
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs;
+use std::time::{Duration, Instant};
+
+use crate::shell::Location;
+
+#[derive(Default)]
+struct Stat {
+    // Commands run on a line, or calls of a function
+    commands: usize,
+    time: Duration,
+}
+
+#[derive(Default)]
+pub struct Profiler {
+    stats: HashMap<Location, Stat>,
+    // Where the running command came from and when it started
+    current: Option<(Location, Instant)>,
+    functions: HashMap<String, Stat>,
+    // The functions running and when they were called, innermost last
+    calls: Vec<(String, Instant)>,
+}
+
+impl Profiler {
+    // A command is about to run at `location`, commands typed in rather
+    // than sourced from a file aren't accounted for
+    pub fn hit(&mut self, location: Option<&Location>) {
+        self.stop();
+
+        if let Some(location) = location {
+            self.stats.entry(location.clone()).or_default().commands += 1;
+            self.current = Some((location.clone(), Instant::now()));
+        }
+    }
+
+    pub fn enter(&mut self, function: &str) {
+        self.functions
+            .entry(function.to_string())
+            .or_default()
+            .commands += 1;
+        self.calls.push((function.to_string(), Instant::now()));
+    }
+
+    // The function called last returned, its time counts once for recursive
+    // calls
+    pub fn leave(&mut self) {
+        if let Some((function, start)) = self.calls.pop() {
+            if self.calls.iter().all(|(name, _)| *name != function) {
+                self.functions.entry(function).or_default().time += start.elapsed();
+            }
+        }
+    }
+
+    fn stop(&mut self) {
+        if let Some((location, start)) = self.current.take() {
+            self.stats.entry(location).or_default().time += start.elapsed();
+        }
+    }
+
+    // Lines by the time spent on them, slowest first, then functions the
+    // same way
+    pub fn report(mut self) -> String {
+        self.stop();
+
+        let mut stats: Vec<(Location, Stat)> = self.stats.into_iter().collect();
+        stats.sort_by(|(a, a_stat), (b, b_stat)| {
+            b_stat
+                .time
+                .cmp(&a_stat.time)
+                .then_with(|| (&a.file, a.line).cmp(&(&b.file, b.line)))
+        });
+
+        let mut sources: HashMap<String, Vec<String>> = HashMap::new();
+        let mut report = format!("{:>12} {:>8}  {}\n", "time", "commands", "line");
+
+        for (location, stat) in stats {
+            let source = sources.entry(location.file.clone()).or_insert_with(|| {
+                fs::read_to_string(&location.file)
+                    .map(|contents| contents.lines().map(String::from).collect())
+                    .unwrap_or_default()
+            });
+            let text = source.get(location.line - 1).map_or("", |line| line.trim());
+
+            let _ = writeln!(
+                report,
+                "{:>10.3}ms {:>8}  {}:{}: {}",
+                stat.time.as_secs_f64() * 1000.0,
+                stat.commands,
+                location.file,
+                location.line,
+                text
+            );
+        }
+
+        if !self.functions.is_empty() {
+            let mut functions: Vec<(String, Stat)> = self.functions.into_iter().collect();
+            functions.sort_by(|(a, a_stat), (b, b_stat)| {
+                b_stat.time.cmp(&a_stat.time).then_with(|| a.cmp(b))
+            });
+
+            let _ = writeln!(report, "\n{:>12} {:>8}  function", "time", "calls");
+            for (function, stat) in functions {
+                let _ = writeln!(
+                    report,
+                    "{:>10.3}ms {:>8}  {}",
+                    stat.time.as_secs_f64() * 1000.0,
+                    stat.commands,
+                    function
+                );
+            }
+        }
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_report_order() {
+        let location = |line| Location {
+            file: String::from("/nonexistent/script"),
+            line,
+        };
+
+        let mut profiler = Profiler::default();
+        profiler.hit(Some(&location(1)));
+        profiler.hit(Some(&location(2)));
+        profiler.hit(Some(&location(1)));
+        profiler.hit(None);
+
+        profiler.stats.get_mut(&location(2)).unwrap().time = Duration::from_millis(5);
+        profiler.stats.get_mut(&location(1)).unwrap().time = Duration::from_millis(1);
+
+        let report = profiler.report();
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].ends_with("1  /nonexistent/script:2: "));
+        assert!(lines[2].ends_with("2  /nonexistent/script:1: "));
+    }
+
+    #[test]
+    fn test_functions() {
+        let mut profiler = Profiler::default();
+        profiler.enter("outer");
+        profiler.enter("inner");
+        profiler.enter("inner");
+        profiler.leave();
+        profiler.leave();
+        profiler.leave();
+        profiler.enter("inner");
+        profiler.leave();
+
+        assert_eq!(profiler.functions["outer"].commands, 1);
+        assert_eq!(profiler.functions["inner"].commands, 3);
+        profiler.functions.get_mut("outer").unwrap().time = Duration::from_millis(5);
+        profiler.functions.get_mut("inner").unwrap().time = Duration::from_millis(3);
+
+        let report = profiler.report();
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert!(lines[2].ends_with("calls  function"));
+        assert!(lines[3].ends_with("1  outer"));
+        assert!(lines[4].ends_with("3  inner"));
+    }
+}
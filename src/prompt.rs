@@ -0,0 +1,336 @@
+use std::env;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::editor;
+use crate::shell::Shell;
+use crate::status::Status;
+use crate::sys;
+
+const RED: &str = "\x1b[31m";
+const MAGENTA: &str = "\x1b[35m";
+const RESET: &str = "\x1b[0m";
+
+// The prompt is made from `PS1` when it's set, otherwise it's just an
+// indicator that setting `RUSH_COLOR_PROMPT` colors by how the last command
+// finished: red for failures and magenta for deaths by signal
+pub fn render(shell: &Shell) -> String {
+    if let Some(ps1) = shell.lookup("PS1") {
+        let max_width = shell
+            .lookup("RUSH_PROMPT_MAX_WIDTH")
+            .and_then(|width| width.parse().ok())
+            .unwrap_or_else(|| editor::columns() / 2);
+        let dirtrim = shell
+            .lookup("PROMPT_DIRTRIM")
+            .and_then(|n| n.parse().ok())
+            .unwrap_or(0);
+
+        let cwd = env::current_dir()
+            .map(|dir| dir.display().to_string())
+            .unwrap_or_default();
+        let home = env::var("HOME").unwrap_or_default();
+        let dir = Dir::new(&cwd, &home);
+
+        return fit(&ps1, &dir, &User::current(), dirtrim, now(), max_width);
+    }
+
+    let color = env::var_os("RUSH_COLOR_PROMPT").is_some_and(|v| !v.is_empty())
+        && sys::is_terminal(1)
+        && !editor::is_dumb();
+
+    indicator(shell.status, color)
+}
+
+// The working directory as shown in prompts, with the home directory as `~`
+struct Dir {
+    path: String,
+}
+
+impl Dir {
+    fn new(cwd: &str, home: &str) -> Self {
+        let path = match cwd.strip_prefix(home) {
+            Some(rest) if !home.is_empty() && (rest.is_empty() || rest.starts_with('/')) => {
+                format!("~{}", rest)
+            }
+            _ => cwd.to_string(),
+        };
+
+        Dir { path }
+    }
+
+    // Only the last `n` components, with `...` for the ones left out
+    fn trimmed(&self, n: usize) -> String {
+        let (prefix, rest) = match self.path.strip_prefix('~') {
+            Some(rest) => ("~/", rest.trim_start_matches('/')),
+            None => ("", self.path.trim_start_matches('/')),
+        };
+        let components: Vec<&str> = rest.split('/').filter(|c| !c.is_empty()).collect();
+
+        if n == 0 || components.len() <= n {
+            return self.path.clone();
+        }
+
+        format!(
+            "{}.../{}",
+            prefix,
+            components[components.len() - n..].join("/")
+        )
+    }
+
+    // Every component but the last down to its first letter, two for hidden
+    // ones, as in `~/p/r/src`
+    fn short(&self) -> String {
+        let components: Vec<&str> = self.path.split('/').collect();
+        let last = components.len() - 1;
+
+        components
+            .iter()
+            .enumerate()
+            .map(|(i, component)| {
+                let len = if component.starts_with('.') { 2 } else { 1 };
+
+                match component.char_indices().nth(len) {
+                    Some((end, _)) if i < last && *component != "~" => &component[..end],
+                    _ => component,
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    fn last(&self) -> &str {
+        match self.path.rfind('/') {
+            Some(i) if self.path.len() > 1 => &self.path[i + 1..],
+            _ => &self.path,
+        }
+    }
+}
+
+// Who the prompt is shown to, for `\u`, `\h` and `\$`
+struct User {
+    name: String,
+    host: String,
+    root: bool,
+}
+
+impl User {
+    fn current() -> Self {
+        let uid = unsafe { sys::getuid() };
+        let name = sys::user_name(uid)
+            .or_else(|| env::var("USER").ok())
+            .unwrap_or_else(|| uid.to_string());
+
+        User {
+            name,
+            host: sys::hostname(),
+            root: uid == 0,
+        }
+    }
+}
+
+// How much `\w` gets shortened for the prompt to fit
+#[derive(Clone, Copy)]
+enum Fit {
+    Full,
+    Short,
+    Last,
+}
+
+// Prompts wider than `max_width` get `\w` shortened like `\P`, and down to
+// the last component if that's not enough
+fn fit(ps1: &str, dir: &Dir, user: &User, dirtrim: usize, now: i64, max_width: usize) -> String {
+    let mut prompt = String::new();
+
+    for fit in [Fit::Full, Fit::Short, Fit::Last] {
+        prompt = expand(ps1, dir, user, dirtrim, now, fit);
+
+        if editor::display_width(&prompt) <= max_width {
+            break;
+        }
+    }
+
+    prompt
+}
+
+// Replace the escapes in `PS1`:
+//   \w  the working directory, only its last `PROMPT_DIRTRIM` components if
+//       that's set
+//   \W  the last component of the working directory
+//   \P  the working directory with all but its last component shortened
+//   \t  the time as HH:MM:SS
+//   \D{format}  the time formatted by strftime, `%X` if empty
+//   \u  the user name
+//   \h  the host name up to the first dot, \H all of it
+//   \$  `#` for root, `$` for anyone else
+//   \e  an escape character, to start color sequences like `\e[32m`
+//   \nnn  the character with octal code nnn, as in `\033`
+//   \n  a newline
+//   \[ \]  around characters that take no room on screen, they're left out
+//       as escape sequences are told apart anyway
+//   \\  a backslash
+fn expand(ps1: &str, dir: &Dir, user: &User, dirtrim: usize, now: i64, fit: Fit) -> String {
+    let mut prompt = String::new();
+    let mut chars = ps1.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            prompt.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('w') => match fit {
+                Fit::Full => prompt.push_str(&dir.trimmed(dirtrim)),
+                Fit::Short => prompt.push_str(&dir.short()),
+                Fit::Last => prompt.push_str(dir.last()),
+            },
+            Some('W') => prompt.push_str(dir.last()),
+            Some('P') => prompt.push_str(&dir.short()),
+            Some('t') => prompt.push_str(&sys::format_time("%H:%M:%S", now)),
+            Some('D') if chars.as_str().starts_with('{') && chars.as_str().contains('}') => {
+                let rest = &chars.as_str()[1..];
+                let end = rest.find('}').unwrap();
+                let format = match &rest[..end] {
+                    "" => "%X",
+                    format => format,
+                };
+
+                prompt.push_str(&sys::format_time(format, now));
+                chars = rest[end + 1..].chars();
+            }
+            Some('u') => prompt.push_str(&user.name),
+            Some('h') => prompt.push_str(user.host.split('.').next().unwrap_or_default()),
+            Some('H') => prompt.push_str(&user.host),
+            Some('$') => prompt.push(if user.root { '#' } else { '$' }),
+            Some('e') => prompt.push('\x1b'),
+            Some('n') => prompt.push('\n'),
+            Some('[') | Some(']') => {}
+            Some(c @ '0'..='7') => {
+                let rest = chars.as_str();
+                let len = rest
+                    .chars()
+                    .take(2)
+                    .take_while(|c| ('0'..='7').contains(c))
+                    .count();
+                let code = u32::from_str_radix(&format!("{}{}", c, &rest[..len]), 8).unwrap();
+
+                prompt.extend(char::from_u32(code));
+                chars = rest[len..].chars();
+            }
+            Some('\\') => prompt.push('\\'),
+            Some(c) => {
+                prompt.push('\\');
+                prompt.push(c);
+            }
+            None => prompt.push('\\'),
+        }
+    }
+
+    prompt
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_secs() as i64)
+}
+
+fn indicator(status: Status, color: bool) -> String {
+    let color = match status {
+        _ if !color => None,
+        Status::Exited(0) => None,
+        Status::Exited(_) => Some(RED),
+        Status::Signaled { .. } => Some(MAGENTA),
+    };
+
+    match color {
+        Some(color) => format!("{}>{} ", color, RESET),
+        None => String::from("> "),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_status_colors() {
+        let killed = Status::Signaled {
+            signum: 9,
+            core_dumped: false,
+        };
+
+        assert_eq!(indicator(Status::Exited(0), true), "> ");
+        assert_eq!(indicator(Status::Exited(1), true), "\x1b[31m>\x1b[0m ");
+        assert_eq!(indicator(killed, true), "\x1b[35m>\x1b[0m ");
+        assert_eq!(indicator(killed, false), "> ");
+    }
+
+    #[test]
+    fn test_dir_shortening() {
+        let dir = Dir::new("/home/ana/projects/rush/src", "/home/ana");
+        assert_eq!(dir.path, "~/projects/rush/src");
+        assert_eq!(dir.short(), "~/p/r/src");
+        assert_eq!(dir.trimmed(2), "~/.../rush/src");
+        assert_eq!(dir.trimmed(3), "~/projects/rush/src");
+        assert_eq!(dir.last(), "src");
+
+        let dir = Dir::new("/home/anabel/.config/git", "/home/ana");
+        assert_eq!(dir.short(), "/h/a/.c/git");
+        assert_eq!(dir.trimmed(1), ".../git");
+
+        assert_eq!(Dir::new("/", "/home/ana").last(), "/");
+        assert_eq!(Dir::new("/home/ana", "/home/ana").short(), "~");
+    }
+
+    #[test]
+    fn test_prompt_fitting() {
+        let dir = Dir::new("/home/ana/projects/rush/src", "/home/ana");
+
+        assert_eq!(
+            fit("\\w \\\\ ", &dir, &USER, 0, 0, 40),
+            "~/projects/rush/src \\ "
+        );
+        assert_eq!(fit("[\\w]$ ", &dir, &USER, 0, 0, 15), "[~/p/r/src]$ ");
+        assert_eq!(fit("[\\w]$ ", &dir, &USER, 0, 0, 10), "[src]$ ");
+        assert_eq!(fit("\\W \\P> ", &dir, &USER, 0, 0, 80), "src ~/p/r/src> ");
+    }
+
+    const USER: User = User {
+        name: String::new(),
+        host: String::new(),
+        root: false,
+    };
+
+    #[test]
+    fn test_user_escapes() {
+        let dir = Dir::new("/", "");
+        let user = User {
+            name: "ana".into(),
+            host: "box.example.org".into(),
+            root: false,
+        };
+        let fit = |ps1| fit(ps1, &dir, &user, 0, 0, 80);
+
+        assert_eq!(fit("\\u@\\h:\\W\\$ "), "ana@box:/$ ");
+        assert_eq!(fit("\\H"), "box.example.org");
+        assert_eq!(
+            fit("\\[\\e[32m\\]\\u\\[\\033[0m\\]\\n> "),
+            "\x1b[32mana\x1b[0m\n> "
+        );
+        assert_eq!(fit("\\0417"), "!7");
+
+        let root = User { root: true, ..user };
+        assert_eq!(super::fit("\\$", &dir, &root, 0, 0, 80), "#");
+    }
+
+    #[test]
+    fn test_time_escapes() {
+        let dir = Dir::new("/", "");
+        // Mid 1971 in any timezone
+        let time = 86400 * 500;
+
+        assert_eq!(fit("\\D{%Y}> ", &dir, &USER, 0, time, 80), "1971> ");
+        assert_eq!(fit("\\D{%Y", &dir, &USER, 0, time, 80), "\\D{%Y");
+        assert_eq!(fit("\\t", &dir, &USER, 0, time, 80).len(), 8);
+    }
+}
@@ -0,0 +1,89 @@
+// The generator behind `random`, splitmix64. Assigning `RANDOM_SEED` seeds
+// it again so the numbers that follow are repeatable
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::sys;
+
+#[derive(Default)]
+pub struct Rng {
+    state: u64,
+    seeded: bool,
+}
+
+impl Rng {
+    pub fn is_seeded(&self) -> bool {
+        self.seeded
+    }
+
+    // Without a seed it's seeded from the time and pid
+    pub fn seed(&mut self, seed: Option<&str>) {
+        self.state = match seed {
+            Some(seed) => seed.parse().unwrap_or_else(|_| hash(seed)),
+            None => {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default();
+                now.as_nanos() as u64 ^ (unsafe { sys::getpid() } as u64) << 32
+            }
+        };
+        self.seeded = true;
+    }
+
+    pub fn next(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // Uniform in `low..=high`, numbers past the last whole multiple of the
+    // range are drawn again so none is more likely
+    pub fn range(&mut self, low: i64, high: i64) -> i64 {
+        let span = high.wrapping_sub(low) as u64;
+
+        if span == u64::MAX {
+            return self.next() as i64;
+        }
+
+        let span = span + 1;
+        let limit = u64::MAX - u64::MAX % span;
+
+        loop {
+            let n = self.next();
+            if n < limit {
+                return low.wrapping_add((n % span) as i64);
+            }
+        }
+    }
+}
+
+// FNV-1a, for seeds that aren't numbers
+fn hash(seed: &str) -> u64 {
+    seed.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_seeded_range() {
+        let mut rng = Rng::default();
+        rng.seed(Some("42"));
+        let first: Vec<_> = (0..20).map(|_| rng.range(1, 6)).collect();
+        assert!(first.iter().all(|n| (1..=6).contains(n)));
+
+        rng.seed(Some("42"));
+        assert_eq!((0..20).map(|_| rng.range(1, 6)).collect::<Vec<_>>(), first);
+
+        rng.seed(Some("words work too"));
+        assert_ne!((0..20).map(|_| rng.range(1, 6)).collect::<Vec<_>>(), first);
+
+        assert_eq!(rng.range(7, 7), 7);
+        rng.range(i64::MIN, i64::MAX);
+    }
+}
@@ -0,0 +1,660 @@
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::str::Chars;
+
+use crate::arith;
+use crate::builtins::Builtins;
+use crate::cmd::{
+    arithmetic_body, find_closing_paren, opens_list, report, subscript, substitute, tokenize, Token,
+};
+use crate::complete::Completions;
+use crate::coverage::Coverage;
+use crate::defer::Deferred;
+use crate::dirs::DirStack;
+use crate::editor::Keymap;
+use crate::history::History;
+use crate::job::Jobs;
+use crate::options::{Options, Shopts};
+use crate::path::Hash;
+use crate::profile::Profiler;
+use crate::random::Rng;
+use crate::status::Status;
+use crate::trap::Traps;
+
+// Where the command being run comes from
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Location {
+    pub file: String,
+    pub line: usize,
+}
+
+// Set by `break` and `continue`, along with how many loops they leave
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Jump {
+    Break(usize),
+    Continue(usize),
+}
+
+// What a variable made local was before, put back when the function returns
+pub enum Saved {
+    Var(String),
+    Exported(String),
+    Unset,
+}
+
+pub struct Shell {
+    // `$0` followed by the positional parameters `$1..$n`
+    pub args: Vec<String>,
+    pub aliases: BTreeMap<String, String>,
+    // Variables not exported, exported ones live in the process environment
+    pub vars: BTreeMap<String, String>,
+    // Associative arrays, `declare -A name` and `name[key]=value`
+    pub maps: BTreeMap<String, HashMap<String, String>>,
+    // The bodies of the functions defined, by name
+    pub functions: BTreeMap<String, String>,
+    // Where the bodies of the functions defined in files start
+    pub function_locations: HashMap<String, Location>,
+    // One frame per function running, with the variables it made local
+    pub scopes: Vec<Vec<(String, Saved)>>,
+    // The `OPTIND` getopts left, where it was in that argument and the
+    // arguments it was going through
+    pub getopts_position: (usize, usize, Vec<String>),
+    // How many files are being sourced
+    pub sourcing: usize,
+    // Set by `return` until the function or sourced file it leaves is done
+    pub returning: bool,
+    // Set until the loop `break` or `continue` leaves gets to it
+    pub jump: Option<Jump>,
+    // How many loops are running
+    pub loops: usize,
+    // Set once Ctrl-C interrupts the line running in an interactive shell,
+    // until the prompt is back
+    pub interrupted: bool,
+    pub traps: Traps,
+    pub options: Options,
+    pub shopts: Shopts,
+    // Commands failing while this is above zero don't make `set -e` exit, as
+    // on the left of `&&`
+    pub conditions: usize,
+    // The first unset variable expanded with `set -u`, which fails the
+    // command being expanded
+    pub unbound: Option<String>,
+    // Set when an expansion fails with its error reported, like `$((1/0))`,
+    // which fails the command being expanded without running it
+    pub failed_expansion: bool,
+    pub history: History,
+    pub keymap: Keymap,
+    // Lines put aside in the editor, the last one is shown at the next prompt
+    pub pushed_lines: Vec<String>,
+    pub completions: Completions,
+    // The directories below the working one, for `pushd` and `popd`
+    pub dirs: DirStack,
+    // `hash`, where the commands run were found
+    pub hash: Hash,
+    // The builtins that are looked up by name when they run
+    pub builtins: Builtins,
+    pub jobs: Jobs,
+    // Whether jobs get process groups of their own and the terminal is handed
+    // to the one in the foreground, only for interactive shells
+    pub job_control: bool,
+    // Set when exiting was refused because of jobs, so trying again right
+    // away does exit
+    pub exit_warned: bool,
+    // The line of the sourced file being run, if any
+    pub location: Option<Location>,
+    // The line of that file the text being run starts on, the lines of its
+    // commands count from there
+    pub text_line: usize,
+    // Set while the DEBUG trap runs so its own commands don't trigger it
+    pub in_debug_trap: bool,
+    pub profiler: Option<Profiler>,
+    pub coverage: Option<Coverage>,
+    pub rng: Rng,
+    // Files being sourced by `async-source`
+    pub deferred: Vec<Deferred>,
+    // The pid of the last background job, `$!`
+    pub last_background: Option<i32>,
+    // The status of the last command, `$?`
+    pub status: Status,
+}
+
+impl Shell {
+    pub fn new() -> Self {
+        Shell {
+            args: vec![String::from("rush")],
+            aliases: BTreeMap::new(),
+            vars: BTreeMap::new(),
+            maps: BTreeMap::new(),
+            functions: BTreeMap::new(),
+            function_locations: HashMap::new(),
+            scopes: vec![],
+            getopts_position: (1, 1, vec![]),
+            sourcing: 0,
+            returning: false,
+            jump: None,
+            loops: 0,
+            interrupted: false,
+            traps: Traps::default(),
+            options: Options::default(),
+            shopts: Shopts::default(),
+            conditions: 0,
+            unbound: None,
+            failed_expansion: false,
+            history: History::default(),
+            keymap: Keymap::default(),
+            pushed_lines: vec![],
+            completions: Completions::default(),
+            hash: Hash::default(),
+            builtins: Builtins::default(),
+            dirs: DirStack::default(),
+            jobs: Jobs::default(),
+            job_control: false,
+            exit_warned: false,
+            location: None,
+            text_line: 0,
+            in_debug_trap: false,
+            profiler: None,
+            coverage: None,
+            rng: Rng::default(),
+            deferred: vec![],
+            last_background: None,
+            status: Status::Exited(0),
+        }
+    }
+
+    // Run `f` with `$1..$n` replaced by `params`, restoring the previous
+    // positional parameters afterwards
+    pub fn with_positional<T>(&mut self, params: Vec<String>, f: impl FnOnce(&mut Self) -> T) -> T {
+        let saved = self.args.split_off(1);
+        self.args.extend(params);
+
+        let result = f(self);
+
+        self.args.truncate(1);
+        self.args.extend(saved);
+        result
+    }
+
+    // Whether the commands left in a list are skipped, as `return`, `break` or
+    // `continue` ran or the line was interrupted
+    pub fn leaving(&self) -> bool {
+        self.returning || self.jump.is_some() || self.interrupted
+    }
+
+    // Replace the first word of every simple command with its alias, an alias
+    // is not expanded again while its own value is being expanded
+    pub fn expand_aliases(&self, line: &str) -> String {
+        self.expand_aliases_with(line, &mut vec![])
+    }
+
+    fn expand_aliases_with<'a>(&'a self, line: &str, active: &mut Vec<&'a str>) -> String {
+        let tokens = match tokenize(line) {
+            Ok(tokens) => tokens,
+            // Let the parser report the error
+            Err(_) => return line.to_string(),
+        };
+
+        let mut expanded = String::new();
+        let mut last = 0;
+        let mut command_position = true;
+        let mut target = false;
+
+        for (span, token) in tokens {
+            match token {
+                // The word after a redirection is where it goes
+                Token::Redirect(_) => target = true,
+                Token::Word(_) if target => target = false,
+
+                // A group or a keyword opens a list, so the next word is a
+                // command again
+                Token::Word(word) if command_position && opens_list(word) => {}
+
+                Token::Word(word) if command_position => {
+                    command_position = false;
+
+                    if let Some((name, value)) = self.aliases.get_key_value(word) {
+                        if !active.contains(&name.as_str()) {
+                            expanded.push_str(&line[last..span.start]);
+                            active.push(name);
+                            expanded.push_str(&self.expand_aliases_with(value, active));
+                            active.pop();
+                            last = span.end;
+                        }
+                    }
+                }
+
+                Token::Word(_) => {}
+
+                _ => command_position = true,
+            }
+        }
+
+        expanded.push_str(&line[last..]);
+        expanded
+    }
+
+    // Assigning to an exported variable updates the environment
+    pub fn set_var(&mut self, name: &str, value: String) {
+        if name == "RANDOM_SEED" {
+            self.rng.seed(Some(&value));
+        }
+
+        if env::var_os(name).is_some() {
+            env::set_var(name, value);
+        } else {
+            self.vars.insert(name.to_string(), value);
+        }
+    }
+
+    pub fn export_var(&mut self, name: &str, value: Option<String>) {
+        let value = value.or_else(|| self.vars.get(name).cloned());
+        self.vars.remove(name);
+
+        if let Some(seed) = value.as_deref().filter(|_| name == "RANDOM_SEED") {
+            self.rng.seed(Some(seed));
+        }
+
+        match value {
+            Some(value) => env::set_var(name, value),
+            None if env::var_os(name).is_none() => env::set_var(name, ""),
+            None => {}
+        }
+    }
+
+    // Shadow the variable until the function running returns, the local is
+    // exported if the variable it shadows was. False outside functions
+    pub fn make_local(&mut self, name: &str, value: Option<String>) -> bool {
+        let frame = match self.scopes.last_mut() {
+            Some(frame) => frame,
+            None => return false,
+        };
+
+        if !frame.iter().any(|(local, _)| local == name) {
+            let saved = match (self.vars.get(name), env::var(name)) {
+                (Some(value), _) => Saved::Var(value.clone()),
+                (None, Ok(value)) => Saved::Exported(value),
+                (None, Err(_)) => Saved::Unset,
+            };
+            frame.push((name.to_string(), saved));
+        }
+
+        match value {
+            Some(value) => self.set_var(name, value),
+            None => self.unset_var(name),
+        }
+        true
+    }
+
+    // Run `f` as a function, the variables it makes local are restored
+    // afterwards
+    pub fn with_scope<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> T {
+        self.scopes.push(vec![]);
+        let result = f(self);
+
+        for (name, saved) in self.scopes.pop().unwrap_or_default().into_iter().rev() {
+            self.unset_var(&name);
+
+            match saved {
+                Saved::Var(value) => {
+                    self.vars.insert(name, value);
+                }
+                Saved::Exported(value) => env::set_var(name, value),
+                Saved::Unset => {}
+            }
+        }
+
+        result
+    }
+
+    pub fn unset_var(&mut self, name: &str) {
+        if let Some((name, key)) = subscript(name) {
+            if let Some(map) = self.maps.get_mut(name) {
+                map.remove(key);
+            }
+            return;
+        }
+
+        self.vars.remove(name);
+        self.maps.remove(name);
+        env::remove_var(name);
+    }
+
+    // Assigning to an element of an array that isn't there makes it
+    pub fn set_element(&mut self, name: &str, key: String, value: String) {
+        self.maps
+            .entry(name.to_string())
+            .or_default()
+            .insert(key, value);
+    }
+
+    // Expand a word without splitting it, as for the value of assignments
+    pub fn expand_string(&mut self, word: &str) -> String {
+        self.expand_word(word).0
+    }
+
+    // Expand parameter references and remove quotes on every word, `$@`
+    // expands to one word per positional parameter
+    pub fn expand<'a>(&mut self, words: impl Iterator<Item = &'a str>) -> Vec<String> {
+        let mut fields = vec![];
+
+        for word in words {
+            if word == "$@" || word == "\"$@\"" {
+                fields.extend(self.args.iter().skip(1).cloned());
+                continue;
+            }
+
+            if let Some(elements) = self.elements(word) {
+                fields.extend(elements);
+                continue;
+            }
+
+            // The output of an unquoted substitution is split into words
+            if let Some(body) = whole_substitution(word) {
+                let output = substitute(body, self);
+                fields.extend(output.split_whitespace().map(String::from));
+                continue;
+            }
+
+            let (expanded, quoted) = self.expand_word(word);
+
+            // Like other shells drop unquoted words that expanded to nothing
+            if !expanded.is_empty() || quoted || !word.contains('$') {
+                fields.push(expanded);
+            }
+        }
+
+        fields
+    }
+
+    fn expand_word(&mut self, word: &str) -> (String, bool) {
+        let mut out = String::new();
+        let mut chars = word.chars();
+        let mut quoted = false;
+        let mut in_double = false;
+
+        // `~` alone or before a `/` is the home directory
+        if let Some(rest) = word.strip_prefix('~') {
+            if rest.is_empty() || rest.starts_with('/') {
+                if let Some(home) = self.lookup("HOME") {
+                    out.push_str(&home);
+                    chars.next();
+                }
+            }
+        }
+
+        while let Some(c) = chars.next() {
+            match c {
+                '\'' if !in_double => {
+                    quoted = true;
+                    for c in &mut chars {
+                        if c == '\'' {
+                            break;
+                        }
+                        out.push(c);
+                    }
+                }
+
+                '"' => {
+                    quoted = true;
+                    in_double = !in_double;
+                }
+
+                '\\' => match chars.next() {
+                    // The line goes on in the next one
+                    Some('\n') => {}
+                    Some(c) if !in_double || "$`\"\\".contains(c) => out.push(c),
+                    Some(c) => {
+                        out.push('\\');
+                        out.push(c);
+                    }
+                    None => out.push('\\'),
+                },
+
+                '$' => self.expand_parameter(&mut chars, &mut out),
+
+                c => out.push(c),
+            }
+        }
+
+        (out, quoted)
+    }
+
+    fn expand_parameter(&mut self, chars: &mut Chars, out: &mut String) {
+        let name = match chars.clone().next() {
+            Some('(') => {
+                let rest = &chars.as_str()[1..];
+
+                match find_closing_paren(rest) {
+                    Ok(len) => {
+                        match arithmetic_body(&rest[..len]) {
+                            // `$((expr))`
+                            Some(expr) => {
+                                let expr = self.expand_string(expr);
+                                match arith::eval(&expr, self) {
+                                    Ok(n) => out.push_str(&n.to_string()),
+                                    Err(e) => {
+                                        report(format!("{}: {}", expr.trim(), e), self);
+                                        self.failed_expansion = true;
+                                    }
+                                }
+                            }
+                            None => out.push_str(&substitute(&rest[..len], self)),
+                        }
+                        *chars = rest[len + 1..].chars();
+                    }
+                    // Rejected by the parser already
+                    Err(_) => *chars = "".chars(),
+                }
+                return;
+            }
+
+            Some('{') => {
+                chars.next();
+                let mut name = String::new();
+                for c in &mut *chars {
+                    if c == '}' {
+                        break;
+                    }
+                    name.push(c);
+                }
+                name
+            }
+
+            Some(c) if c.is_ascii_digit() || "#@*?!-".contains(c) => {
+                chars.next();
+                c.to_string()
+            }
+
+            Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+                let mut name = String::new();
+                while let Some(c) = chars.clone().next() {
+                    if !c.is_ascii_alphanumeric() && c != '_' {
+                        break;
+                    }
+                    name.push(c);
+                    chars.next();
+                }
+                name
+            }
+
+            _ => {
+                out.push('$');
+                return;
+            }
+        };
+
+        let value = match subscript(name.trim_start_matches('!')) {
+            Some((array, key)) => self.lookup_element(&name, array, key),
+            None => self.lookup(&name),
+        };
+
+        match value {
+            Some(value) => out.push_str(&value),
+            None if self.options.nounset => {
+                self.unbound.get_or_insert(name);
+            }
+            None => {}
+        }
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<String> {
+        match name {
+            "#" => Some((self.args.len() - 1).to_string()),
+            "?" => Some(self.status.code().to_string()),
+            "!" => self.last_background.map(|pid| pid.to_string()),
+            "-" => Some(self.options.letters()),
+            "@" | "*" => Some(self.args[1..].join(" ")),
+            _ => match name.parse::<usize>() {
+                Ok(n) => self.args.get(n).cloned(),
+                Err(_) => self.vars.get(name).cloned().or_else(|| env::var(name).ok()),
+            },
+        }
+    }
+}
+
+impl Shell {
+    // `${name[key]}`, with `${name[@]}` or `${name[*]}` being all the values
+    // and `${!name[@]}` all the keys, separated by spaces
+    fn lookup_element(&mut self, reference: &str, name: &str, key: &str) -> Option<String> {
+        let all: Vec<String> = match (reference.starts_with('!'), key) {
+            (true, "@" | "*") => self.maps.get(name)?.keys().cloned().collect(),
+            (false, "@" | "*") => self.maps.get(name)?.values().cloned().collect(),
+            (true, _) => return None,
+            (false, _) => {
+                let key = self.expand_string(key);
+                return self.maps.get(name)?.get(&key).cloned();
+            }
+        };
+
+        Some(all.join(" "))
+    }
+
+    // The words `${name[@]}` and `${!name[@]}` expand to, one per value or key
+    // like `$@`
+    fn elements(&self, word: &str) -> Option<Vec<String>> {
+        let unquoted = word
+            .strip_prefix('"')
+            .and_then(|word| word.strip_suffix('"'))
+            .unwrap_or(word);
+        let reference = unquoted.strip_prefix("${")?.strip_suffix('}')?;
+        let (name, keys) = match reference.strip_prefix('!') {
+            Some(name) => (name.strip_suffix("[@]")?, true),
+            None => (reference.strip_suffix("[@]")?, false),
+        };
+        let map = self.maps.get(name)?;
+
+        Some(match keys {
+            true => map.keys().cloned().collect(),
+            false => map.values().cloned().collect(),
+        })
+    }
+}
+
+// The body of a word consisting of a single `$(...)`
+fn whole_substitution(word: &str) -> Option<&str> {
+    let body = word.strip_prefix("$(")?;
+
+    match find_closing_paren(body) {
+        Ok(len) if len == body.len() - 1 && arithmetic_body(&body[..len]).is_none() => {
+            Some(&body[..len])
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_positional_parameters() {
+        let mut shell = Shell::new();
+        let params = vec![String::from("a"), String::from("b")];
+
+        let fields = shell.with_positional(params, |shell| {
+            shell.expand("$0 $1 ${2} $# $@ x$3y".split(' '))
+        });
+        assert_eq!(fields, vec!["rush", "a", "b", "2", "a", "b", "xy"]);
+
+        assert_eq!(shell.expand("$# $1".split(' ')), vec!["0"]);
+    }
+
+    #[test]
+    fn test_local_variables() {
+        let mut shell = Shell::new();
+        shell.vars.insert(String::from("x"), String::from("outer"));
+
+        assert!(!shell.make_local("x", None));
+
+        shell.with_scope(|shell| {
+            assert!(shell.make_local("x", Some(String::from("inner"))));
+            assert!(shell.make_local("y", Some(String::from("new"))));
+            shell.set_var("x", String::from("changed"));
+
+            shell.with_scope(|shell| {
+                shell.make_local("x", None);
+                assert!(!shell.vars.contains_key("x"));
+            });
+            assert_eq!(shell.vars["x"], "changed");
+        });
+
+        assert_eq!(shell.vars["x"], "outer");
+        assert!(!shell.vars.contains_key("y"));
+    }
+
+    #[test]
+    fn test_quote_removal() {
+        let mut shell = Shell::new();
+        shell.args.push(String::from("x y"));
+        let words = vec!["'$1'", "\"$1\"", "a\\ b", "\"\"", "\"\\$1\""];
+
+        assert_eq!(
+            shell.expand(words.into_iter()),
+            vec!["$1", "x y", "a b", "", "$1"]
+        );
+
+        shell.vars.insert("HOME".into(), "/home/me".into());
+        let words = vec!["~", "~/a", "'~'", "\\~", "a~", "~b"];
+        assert_eq!(
+            shell.expand(words.into_iter()),
+            vec!["/home/me", "/home/me/a", "~", "~", "a~", "~b"]
+        );
+    }
+
+    #[test]
+    fn test_associative_arrays() {
+        let mut shell = Shell::new();
+        shell.vars.insert("k".into(), "a b".into());
+        shell.set_element("m", "a b".into(), "1".into());
+        shell.set_element("m", "c".into(), "2".into());
+
+        let words = vec!["${m[$k]}", "${m[c]}", "${m[none]}x", "\"${m[\"a b\"]}\""];
+        assert_eq!(shell.expand(words.into_iter()), vec!["1", "2", "x", "1"]);
+
+        let mut keys = shell.expand(std::iter::once("\"${!m[@]}\""));
+        keys.sort();
+        assert_eq!(keys, vec!["a b", "c"]);
+        assert_eq!(shell.expand(std::iter::once("${m[@]}")).len(), 2);
+
+        shell.unset_var("m[c]");
+        assert_eq!(shell.expand_string("${m[*]}"), "1");
+        shell.unset_var("m");
+        assert!(shell.maps.is_empty());
+    }
+
+    #[test]
+    fn test_alias_expansion() {
+        let mut shell = Shell::new();
+        shell.aliases.insert("ll".into(), "ls -la".into());
+        shell.aliases.insert("ls".into(), "ls --color".into());
+        shell.aliases.insert("a".into(), "b".into());
+        shell.aliases.insert("b".into(), "a".into());
+
+        assert_eq!(shell.expand_aliases("ll src"), "ls --color -la src");
+        assert_eq!(
+            shell.expand_aliases("echo ll; ll"),
+            "echo ll; ls --color -la"
+        );
+        assert_eq!(shell.expand_aliases("'ll' && a"), "'ll' && a");
+    }
+}
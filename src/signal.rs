@@ -0,0 +1,206 @@
+use std::os::raw::c_int;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::sys;
+
+// Signal numbers, names and descriptions as used on Linux
+pub const SIGNALS: &[(i32, &str, &str)] = &[
+    (1, "HUP", "Hangup"),
+    (2, "INT", "Interrupt"),
+    (3, "QUIT", "Quit"),
+    (4, "ILL", "Illegal instruction"),
+    (5, "TRAP", "Trace/breakpoint trap"),
+    (6, "ABRT", "Aborted"),
+    (7, "BUS", "Bus error"),
+    (8, "FPE", "Floating point exception"),
+    (9, "KILL", "Killed"),
+    (10, "USR1", "User defined signal 1"),
+    (11, "SEGV", "Segmentation fault"),
+    (12, "USR2", "User defined signal 2"),
+    (13, "PIPE", "Broken pipe"),
+    (14, "ALRM", "Alarm clock"),
+    (15, "TERM", "Terminated"),
+    (16, "STKFLT", "Stack fault"),
+    (17, "CHLD", "Child exited"),
+    (18, "CONT", "Continued"),
+    (19, "STOP", "Stopped (signal)"),
+    (20, "TSTP", "Stopped"),
+    (21, "TTIN", "Stopped (tty input)"),
+    (22, "TTOU", "Stopped (tty output)"),
+    (23, "URG", "Urgent I/O condition"),
+    (24, "XCPU", "CPU time limit exceeded"),
+    (25, "XFSZ", "File size limit exceeded"),
+    (26, "VTALRM", "Virtual timer expired"),
+    (27, "PROF", "Profiling timer expired"),
+    (28, "WINCH", "Window changed"),
+    (29, "IO", "I/O possible"),
+    (30, "PWR", "Power failure"),
+    (31, "SYS", "Bad system call"),
+];
+
+pub const SIGHUP: i32 = 1;
+pub const SIGINT: i32 = 2;
+pub const SIGKILL: i32 = 9;
+pub const SIGPIPE: i32 = 13;
+pub const SIGTERM: i32 = 15;
+pub const SIGCONT: i32 = 18;
+pub const SIGSTOP: i32 = 19;
+pub const SIGTSTP: i32 = 20;
+pub const SIGTTIN: i32 = 21;
+pub const SIGTTOU: i32 = 22;
+
+// Ignored by a shell doing job control so it isn't stopped along with its
+// foreground job, and reset for the commands it runs
+pub const JOB_CONTROL: &[i32] = &[SIGTSTP, SIGTTIN, SIGTTOU];
+
+const NSIG: usize = 32;
+
+#[allow(clippy::declare_interior_mutable_const)]
+const UNSET: AtomicBool = AtomicBool::new(false);
+static PENDING: [AtomicBool; NSIG] = [UNSET; NSIG];
+// Caught by the shell itself, for as long as it runs
+static KEPT: [AtomicBool; NSIG] = [UNSET; NSIG];
+
+pub fn name(signum: i32) -> Option<&'static str> {
+    SIGNALS
+        .iter()
+        .find(|(n, _, _)| *n == signum)
+        .map(|(_, name, _)| *name)
+}
+
+pub fn description(signum: i32) -> &'static str {
+    SIGNALS
+        .iter()
+        .find(|(n, _, _)| *n == signum)
+        .map_or("Unknown signal", |(_, _, description)| *description)
+}
+
+// Map an exit status of 128+n back to signal n, anything else is taken to be
+// a signal number already
+pub fn from_status(status: i32) -> Option<i32> {
+    let signum = if status > 128 { status - 128 } else { status };
+    name(signum).map(|_| signum)
+}
+
+// The signal list in the columned format of `kill -l` and `trap -l`
+pub fn table() -> String {
+    let mut table = String::new();
+
+    for (i, (signum, name, _)) in SIGNALS.iter().enumerate() {
+        let sep = if i % 5 == 4 || i == SIGNALS.len() - 1 {
+            '\n'
+        } else {
+            '\t'
+        };
+        table.push_str(&format!("{:2}) SIG{}{}", signum, name, sep));
+    }
+
+    table
+}
+
+// Accept `INT`, `SIGINT`, `int` or `2`
+pub fn parse(spec: &str) -> Option<i32> {
+    if let Ok(signum) = spec.parse::<i32>() {
+        return name(signum).map(|_| signum);
+    }
+
+    let spec = spec.to_ascii_uppercase();
+    let spec = spec.strip_prefix("SIG").unwrap_or(&spec);
+
+    SIGNALS
+        .iter()
+        .find(|(_, name, _)| *name == spec)
+        .map(|(n, _, _)| *n)
+}
+
+extern "C" fn record(signum: c_int) {
+    if let Some(pending) = PENDING.get(signum as usize) {
+        pending.store(true, Ordering::SeqCst);
+    }
+}
+
+// Deliver the signal to `pending` instead of its default action
+pub fn catch(signum: i32) {
+    unsafe {
+        sys::signal(signum, record as extern "C" fn(c_int) as sys::SigHandler);
+    }
+}
+
+pub fn ignore(signum: i32) {
+    unsafe {
+        sys::signal(signum, sys::SIG_IGN);
+    }
+}
+
+// Catch the signal for good, as an interactive shell does with SIGINT.
+// Resetting it goes back to catching it rather than to its default action
+pub fn keep(signum: i32) {
+    if let Some(kept) = KEPT.get(signum as usize) {
+        kept.store(true, Ordering::SeqCst);
+    }
+    catch(signum);
+}
+
+// Stop keeping the signal caught, its disposition left as it is
+pub fn release(signum: i32) {
+    if let Some(kept) = KEPT.get(signum as usize) {
+        kept.store(false, Ordering::SeqCst);
+    }
+}
+
+pub fn reset(signum: i32) {
+    let kept = KEPT
+        .get(signum as usize)
+        .is_some_and(|kept| kept.load(Ordering::SeqCst));
+    if kept {
+        return catch(signum);
+    }
+
+    unsafe {
+        sys::signal(signum, sys::SIG_DFL);
+    }
+}
+
+// The first signal caught and not yet handled, leaving it pending
+pub fn peek() -> Option<i32> {
+    (1..NSIG)
+        .find(|&signum| PENDING[signum].load(Ordering::SeqCst))
+        .map(|signum| signum as i32)
+}
+
+// Whether the signal was caught and not yet handled, leaving it pending
+pub fn is_pending(signum: i32) -> bool {
+    PENDING
+        .get(signum as usize)
+        .is_some_and(|pending| pending.load(Ordering::SeqCst))
+}
+
+// Signals caught since the last call
+pub fn pending() -> Vec<i32> {
+    (1..NSIG)
+        .filter(|&signum| PENDING[signum].swap(false, Ordering::SeqCst))
+        .map(|signum| signum as i32)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_signal() {
+        assert_eq!(parse("INT"), Some(2));
+        assert_eq!(parse("sigterm"), Some(15));
+        assert_eq!(parse("9"), Some(9));
+        assert_eq!(parse("64"), None);
+        assert_eq!(parse("NOPE"), None);
+    }
+
+    #[test]
+    fn test_describe_status() {
+        assert_eq!(from_status(143), Some(SIGTERM));
+        assert_eq!(from_status(9), Some(SIGKILL));
+        assert_eq!(from_status(200), None);
+        assert_eq!(description(SIGTERM), "Terminated");
+    }
+}
@@ -0,0 +1,91 @@
+use std::fmt;
+use std::os::unix::process::ExitStatusExt;
+use std::process::ExitStatus;
+
+use crate::signal;
+
+// How a command finished
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Status {
+    Exited(i32),
+    Signaled { signum: i32, core_dumped: bool },
+}
+
+impl Status {
+    pub fn success(self) -> bool {
+        self == Status::Exited(0)
+    }
+
+    // The value of `$?`, 128+n for commands killed by signal n
+    pub fn code(self) -> i32 {
+        match self {
+            Status::Exited(code) => code,
+            Status::Signaled { signum, .. } => 128 + signum,
+        }
+    }
+}
+
+impl From<bool> for Status {
+    fn from(success: bool) -> Self {
+        Status::Exited(if success { 0 } else { 1 })
+    }
+}
+
+impl From<ExitStatus> for Status {
+    fn from(status: ExitStatus) -> Self {
+        match status.signal() {
+            Some(signum) => Status::Signaled {
+                signum,
+                core_dumped: status.core_dumped(),
+            },
+            None => Status::Exited(status.code().unwrap_or(1)),
+        }
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Status::Exited(code) => write!(f, "{}", tr!("Exit {}", code)),
+
+            Status::Signaled {
+                signum,
+                core_dumped,
+            } => {
+                write!(f, "{}", tr!(signal::description(signum)))?;
+
+                match (signal::name(signum), core_dumped) {
+                    (Some(name), true) => write!(f, " (SIG{}, {})", name, tr!("core dumped")),
+                    (Some(name), false) => write!(f, " (SIG{})", name),
+                    (None, true) => write!(f, " ({})", tr!("core dumped")),
+                    (None, false) => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_signaled_status() {
+        let status = Status::Signaled {
+            signum: signal::SIGTERM,
+            core_dumped: false,
+        };
+        assert_eq!(status.code(), 143);
+        assert_eq!(status.to_string(), "Terminated (SIGTERM)");
+
+        let status = Status::Signaled {
+            signum: 11,
+            core_dumped: true,
+        };
+        assert_eq!(
+            status.to_string(),
+            "Segmentation fault (SIGSEGV, core dumped)"
+        );
+        assert!(!status.success());
+    }
+}
@@ -0,0 +1,231 @@
+// Bindings to the bits of libc the standard library doesn't expose. The
+// structs are laid out and the constants numbered as glibc has them on 64-bit
+// Linux, as are the signals and resource limits elsewhere. Other targets lay
+// out termios and number ioctls differently, so they're refused rather than
+// built with the wrong values
+
+#[cfg(not(all(
+    target_os = "linux",
+    target_env = "gnu",
+    any(
+        target_arch = "x86_64",
+        target_arch = "aarch64",
+        target_arch = "riscv64"
+    )
+)))]
+compile_error!("rush's libc bindings only describe glibc on x86_64, aarch64 and riscv64 Linux");
+
+use std::ffi::{CStr, CString};
+use std::io;
+use std::mem;
+use std::os::raw::{c_char, c_int, c_long, c_short, c_ulong, c_void};
+
+pub type SigHandler = usize;
+
+pub const SIG_DFL: SigHandler = 0;
+pub const SIG_IGN: SigHandler = 1;
+
+pub const RLIM_INFINITY: u64 = u64::MAX;
+
+#[repr(C)]
+pub struct RLimit {
+    pub rlim_cur: u64,
+    pub rlim_max: u64,
+}
+
+// Terminal attributes, laid out as glibc's `struct termios`
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct Termios {
+    pub c_iflag: u32,
+    pub c_oflag: u32,
+    pub c_cflag: u32,
+    pub c_lflag: u32,
+    pub c_line: u8,
+    pub c_cc: [u8; 32],
+    pub c_ispeed: u32,
+    pub c_ospeed: u32,
+}
+
+pub const ICRNL: u32 = 0o400;
+pub const IXON: u32 = 0o2000;
+pub const ICANON: u32 = 0o2;
+pub const ECHO: u32 = 0o10;
+pub const IEXTEN: u32 = 0o100000;
+pub const VTIME: usize = 5;
+pub const VMIN: usize = 6;
+pub const TCSADRAIN: c_int = 1;
+
+#[repr(C)]
+#[derive(Default)]
+pub struct WinSize {
+    pub ws_row: u16,
+    pub ws_col: u16,
+    pub ws_xpixel: u16,
+    pub ws_ypixel: u16,
+}
+
+pub const WNOHANG: c_int = 1;
+pub const WUNTRACED: c_int = 2;
+pub const WCONTINUED: c_int = 8;
+
+pub const TIOCGWINSZ: c_ulong = 0x5413;
+
+#[repr(C)]
+pub struct PollFd {
+    pub fd: c_int,
+    pub events: c_short,
+    pub revents: c_short,
+}
+
+pub const POLLIN: c_short = 1;
+
+// Broken-down time, laid out as glibc's `struct tm`
+#[repr(C)]
+pub struct Tm {
+    pub tm_sec: c_int,
+    pub tm_min: c_int,
+    pub tm_hour: c_int,
+    pub tm_mday: c_int,
+    pub tm_mon: c_int,
+    pub tm_year: c_int,
+    pub tm_wday: c_int,
+    pub tm_yday: c_int,
+    pub tm_isdst: c_int,
+    pub tm_gmtoff: c_long,
+    pub tm_zone: *const c_char,
+}
+
+// A user account, laid out as glibc's `struct passwd`
+#[repr(C)]
+pub struct Passwd {
+    pub pw_name: *const c_char,
+    pub pw_passwd: *const c_char,
+    pub pw_uid: u32,
+    pub pw_gid: u32,
+    pub pw_gecos: *const c_char,
+    pub pw_dir: *const c_char,
+    pub pw_shell: *const c_char,
+}
+
+pub const F_GETFD: c_int = 1;
+pub const F_DUPFD_CLOEXEC: c_int = 1030;
+
+pub const R_OK: c_int = 4;
+pub const W_OK: c_int = 2;
+pub const X_OK: c_int = 1;
+
+pub const _SC_PAGESIZE: c_int = 30;
+pub const _PC_PATH_MAX: c_int = 4;
+
+extern "C" {
+    pub fn access(path: *const c_char, mode: c_int) -> c_int;
+    pub fn close(fd: c_int) -> c_int;
+    pub fn dup2(oldfd: c_int, newfd: c_int) -> c_int;
+    pub fn fcntl(fd: c_int, cmd: c_int, ...) -> c_int;
+    pub fn _exit(status: c_int) -> !;
+    pub fn fork() -> c_int;
+    pub fn gethostname(name: *mut c_char, len: usize) -> c_int;
+    pub fn getpgrp() -> c_int;
+    pub fn getpid() -> c_int;
+    pub fn getpwuid(uid: u32) -> *const Passwd;
+    pub fn getuid() -> u32;
+    pub fn getrlimit(resource: c_int, rlim: *mut RLimit) -> c_int;
+    pub fn ioctl(fd: c_int, request: c_ulong, ...) -> c_int;
+    pub fn isatty(fd: c_int) -> c_int;
+    pub fn kill(pid: c_int, sig: c_int) -> c_int;
+    pub fn localtime_r(time: *const i64, tm: *mut Tm) -> *mut Tm;
+    pub fn pipe(fds: *mut c_int) -> c_int;
+    pub fn pathconf(path: *const c_char, name: c_int) -> c_long;
+    pub fn poll(fds: *mut PollFd, nfds: c_ulong, timeout: c_int) -> c_int;
+    pub fn read(fd: c_int, buf: *mut c_void, count: usize) -> isize;
+    pub fn setpgid(pid: c_int, pgid: c_int) -> c_int;
+    pub fn setrlimit(resource: c_int, rlim: *const RLimit) -> c_int;
+    pub fn signal(signum: c_int, handler: SigHandler) -> SigHandler;
+    pub fn strftime(s: *mut c_char, max: usize, format: *const c_char, tm: *const Tm) -> usize;
+    pub fn sysconf(name: c_int) -> c_long;
+    pub fn umask(mask: u32) -> u32;
+    pub fn tcgetattr(fd: c_int, termios: *mut Termios) -> c_int;
+    pub fn tcsetattr(fd: c_int, action: c_int, termios: *const Termios) -> c_int;
+    pub fn tcsetpgrp(fd: c_int, pgrp: c_int) -> c_int;
+    pub fn waitpid(pid: c_int, status: *mut c_int, options: c_int) -> c_int;
+}
+
+pub fn is_terminal(fd: c_int) -> bool {
+    unsafe { isatty(fd) == 1 }
+}
+
+pub fn hostname() -> String {
+    let mut buf = [0 as c_char; 256];
+
+    // The last byte is kept for the terminator in case the name is truncated
+    if unsafe { gethostname(buf.as_mut_ptr(), buf.len() - 1) } != 0 {
+        return String::new();
+    }
+
+    let name = unsafe { CStr::from_ptr(buf.as_ptr()) };
+    name.to_string_lossy().into_owned()
+}
+
+// The name of the account with user id `uid`, if it has one
+pub fn user_name(uid: u32) -> Option<String> {
+    let passwd = unsafe { getpwuid(uid) };
+    if passwd.is_null() {
+        return None;
+    }
+
+    let name = unsafe { CStr::from_ptr((*passwd).pw_name) };
+    Some(name.to_string_lossy().into_owned())
+}
+
+// Whether the process may read, write or run the file as `mode` says
+pub fn can_access(path: &str, mode: c_int) -> bool {
+    match CString::new(path) {
+        Ok(path) => unsafe { access(path.as_ptr(), mode) == 0 },
+        Err(_) => false,
+    }
+}
+
+// Format the time, in seconds since the epoch, in the local timezone
+pub fn format_time(format: &str, time: i64) -> String {
+    let format = match CString::new(format) {
+        Ok(format) => format,
+        Err(_) => return String::new(),
+    };
+
+    let mut tm: Tm = unsafe { mem::zeroed() };
+    if unsafe { localtime_r(&time, &mut tm) }.is_null() {
+        return String::new();
+    }
+
+    // Nothing written is also what a buffer too small gives, so it grows a
+    // few times before settling on an empty result
+    let mut buf = vec![0 as c_char; 256];
+
+    while buf.len() <= 1 << 16 {
+        let len = unsafe { strftime(buf.as_mut_ptr(), buf.len(), format.as_ptr(), &tm) };
+
+        if len > 0 {
+            let bytes: Vec<u8> = buf[..len].iter().map(|&c| c as u8).collect();
+            return String::from_utf8_lossy(&bytes).into_owned();
+        }
+        buf.resize(buf.len() * 4, 0);
+    }
+
+    String::new()
+}
+
+// The description of `errno` without the `(os error n)` suffix
+pub fn last_error() -> String {
+    describe(&io::Error::last_os_error())
+}
+
+// The description of an error without the `(os error n)` suffix
+pub fn describe(error: &io::Error) -> String {
+    let error = error.to_string();
+
+    match error.find(" (os error") {
+        Some(i) => error[..i].to_string(),
+        None => error,
+    }
+}
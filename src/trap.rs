@@ -0,0 +1,123 @@
+use std::collections::BTreeMap;
+
+use crate::cmd::quote;
+use crate::signal;
+
+// The EXIT pseudo signal runs when the shell exits
+pub const EXIT: i32 = 0;
+// The DEBUG pseudo signal runs before every simple command
+pub const DEBUG: i32 = -1;
+
+#[derive(Default)]
+pub struct Traps {
+    actions: BTreeMap<i32, String>,
+}
+
+#[derive(Debug)]
+pub enum TrapError {
+    BadSignal(String),
+    Untrappable(String),
+}
+
+impl Traps {
+    pub fn parse_condition(spec: &str) -> Result<i32, TrapError> {
+        if spec == "EXIT" || spec == "0" {
+            return Ok(EXIT);
+        }
+
+        if spec == "DEBUG" {
+            return Ok(DEBUG);
+        }
+
+        match signal::parse(spec) {
+            Some(signum) if signum == signal::SIGKILL || signum == signal::SIGSTOP => {
+                Err(TrapError::Untrappable(spec.to_string()))
+            }
+            Some(signum) => Ok(signum),
+            None => Err(TrapError::BadSignal(spec.to_string())),
+        }
+    }
+
+    pub fn condition_name(condition: i32) -> String {
+        match condition {
+            EXIT => String::from("EXIT"),
+            DEBUG => String::from("DEBUG"),
+            signum => format!("SIG{}", signal::name(signum).unwrap_or("?")),
+        }
+    }
+
+    pub fn get(&self, condition: i32) -> Option<&str> {
+        self.actions.get(&condition).map(String::as_str)
+    }
+
+    // An empty action ignores the signal
+    pub fn set(&mut self, condition: i32, action: String) {
+        if condition > EXIT {
+            if action.is_empty() {
+                signal::ignore(condition);
+            } else {
+                signal::catch(condition);
+            }
+        }
+
+        self.actions.insert(condition, action);
+    }
+
+    pub fn reset(&mut self, condition: i32) {
+        if condition > EXIT {
+            signal::reset(condition);
+        }
+
+        self.actions.remove(&condition);
+    }
+
+    // Whether a signal has an action to run, rather than being ignored
+    pub fn catches_signals(&self) -> bool {
+        self.actions
+            .range(EXIT + 1..)
+            .any(|(_, action)| !action.is_empty())
+    }
+
+    // Render the registered handlers as `trap` commands that recreate them
+    pub fn serialize(&self, conditions: Option<&[i32]>) -> Vec<String> {
+        self.actions
+            .iter()
+            .filter(|(condition, _)| conditions.is_none_or(|c| c.contains(condition)))
+            .map(|(&condition, action)| {
+                format!(
+                    "trap -- {} {}",
+                    quote(action),
+                    Self::condition_name(condition)
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_serialize_traps() {
+        let mut traps = Traps::default();
+        traps.set(EXIT, String::from("echo 'bye'"));
+        traps.set(
+            Traps::parse_condition("USR1").unwrap(),
+            String::from("echo usr1"),
+        );
+
+        assert_eq!(
+            traps.serialize(None),
+            vec![
+                "trap -- 'echo '\\''bye'\\''' EXIT",
+                "trap -- 'echo usr1' SIGUSR1"
+            ]
+        );
+        assert_eq!(traps.serialize(Some(&[EXIT])).len(), 1);
+        assert!(traps.catches_signals());
+
+        traps.reset(EXIT);
+        assert_eq!(traps.get(EXIT), None);
+    }
+}
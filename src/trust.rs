@@ -0,0 +1,125 @@
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, Write};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+use crate::sha256;
+use crate::sys;
+
+// `RUSH_TRUSTFILE`, or `~/.rush_trusted` by default
+fn default_path() -> Option<PathBuf> {
+    match env::var_os("RUSH_TRUSTFILE") {
+        Some(path) => Some(PathBuf::from(path)),
+        None => env::var_os("HOME").map(|home| PathBuf::from(home).join(".rush_trusted")),
+    }
+}
+
+// A file could have been put there by someone else when it's owned by
+// another user or writable by others, and a `.rushrc` other than the one in
+// the home directory comes with the directory it's found in
+fn needs_trust(path: &Path) -> bool {
+    let meta = match fs::metadata(path) {
+        Ok(meta) => meta,
+        Err(_) => return false,
+    };
+
+    let uid = unsafe { sys::getuid() };
+    let foreign = meta.uid() != uid && meta.uid() != 0;
+    let writable = meta.mode() & 0o022 != 0;
+
+    let rc = path.file_name().is_some_and(|name| name == ".rushrc")
+        && env::var_os("HOME").is_none_or(|home| path != Path::new(&home).join(".rushrc"));
+
+    foreign || writable || rc
+}
+
+// Trusted files are recorded with a hash of their contents so that they are
+// asked about again once they change. The hash is tagged with the algorithm,
+// entries of any other are just never matched
+fn entry(path: &Path, contents: &str) -> String {
+    format!(
+        "sha256:{}\t{}",
+        sha256::hex(contents.as_bytes()),
+        path.display()
+    )
+}
+
+fn is_trusted(entry: &str, trusted: &Path) -> bool {
+    fs::read_to_string(trusted).is_ok_and(|list| list.lines().any(|line| line == entry))
+}
+
+fn trust(entry: &str, trusted: &Path) -> io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(trusted)?;
+    writeln!(file, "{}", entry)
+}
+
+// Whether the file may be sourced, asking the user the first time a file
+// that needs trusting is sourced
+pub fn allow(path: &Path, contents: &str) -> bool {
+    if !needs_trust(path) {
+        return true;
+    }
+
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let entry = entry(&path, contents);
+    let trusted = default_path();
+
+    if trusted
+        .as_ref()
+        .is_some_and(|file| is_trusted(&entry, file))
+    {
+        return true;
+    }
+
+    eprint!(
+        "{}",
+        tr!("rush: {} is not trusted, source it? [y/N] ", path.display())
+    );
+    let _ = io::stderr().flush();
+
+    let mut answer = String::new();
+    if io::stdin().lock().read_line(&mut answer).is_err() {
+        return false;
+    }
+
+    if !matches!(answer.trim(), "y" | "Y" | "yes") {
+        return false;
+    }
+
+    if let Some(file) = trusted {
+        if let Err(e) = trust(&entry, &file) {
+            eprintln!("rush: {}: {}", file.display(), e);
+        }
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_trusted_entries() {
+        let dir = env::temp_dir().join(format!("rush-trust-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let trusted = dir.join("trusted");
+        let rc = dir.join(".rushrc");
+
+        let before = entry(&rc, "alias ll='ls -l'\n");
+        assert!(!is_trusted(&before, &trusted));
+
+        trust(&before, &trusted).unwrap();
+        assert!(is_trusted(&before, &trusted));
+
+        // Changing the file means trusting it again
+        let after = entry(&rc, "alias ll='rm -rf'\n");
+        assert!(!is_trusted(&after, &trusted));
+
+        fs::write(&rc, "").unwrap();
+        assert!(needs_trust(&rc));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}